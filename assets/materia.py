@@ -0,0 +1,221 @@
+# The materia module, shared helpers for scripts.
+# Task, Tag, Date, DateTime, Duration, TaskRef and today are the same as the globals of the scripts.
+
+# Dates
+
+def _days_from_civil(y, m, d):
+	if m <= 2:
+		y -= 1
+	era = (y if y >= 0 else y - 399) // 400
+	yoe = y - era * 400
+	mp = (m + 9) % 12
+	doy = (153 * mp + 2) // 5 + d - 1
+	doe = yoe * 365 + yoe // 4 - yoe // 100 + doy
+	return era * 146097 + doe - 719468
+
+def _civil_from_days(z):
+	z += 719468
+	era = (z if z >= 0 else z - 146096) // 146097
+	doe = z - era * 146097
+	yoe = (doe - doe // 1460 + doe // 36524 - doe // 146096) // 365
+	y = yoe + era * 400
+	doy = doe - (365 * yoe + yoe // 4 - yoe // 100)
+	mp = (5 * doy + 2) // 153
+	d = doy - (153 * mp + 2) // 5 + 1
+	m = mp + 3 if mp < 10 else mp - 9
+	if m <= 2:
+		y += 1
+	return (y, m, d)
+
+def is_leap_year(year):
+	return year % 4 == 0 and (year % 100 != 0 or year % 400 == 0)
+
+def days_in_month(year, month):
+	if month == 2:
+		return 29 if is_leap_year(year) else 28
+	if month in (4, 6, 9, 11):
+		return 30
+	return 31
+
+def from_ordinal(days):
+	"""The date `days` days after 1970-01-01."""
+	y, m, d = _civil_from_days(days)
+	return Date(y, m, d)
+
+def parse_date(text):
+	"""Reads a `YYYY-MM-DD` date, returns None when it isn't one."""
+	parts = text.strip().split('-')
+	if len(parts) != 3:
+		return None
+	for part in parts:
+		if len(part) == 0:
+			return None
+		for c in part:
+			if ord(c) < ord('0') or ord(c) > ord('9'):
+				return None
+	year = int(parts[0])
+	month = int(parts[1])
+	day = int(parts[2])
+	if month < 1 or month > 12 or day < 1 or day > days_in_month(year, month):
+		return None
+	return Date(year, month, day)
+
+def add_days(date, days):
+	return from_ordinal(date.ordinal() + days)
+
+def add_months(date, months):
+	"""Adds months, clamping the day to the end of the month."""
+	index = date.year * 12 + date.month - 1 + months
+	year = index // 12
+	month = index % 12 + 1
+	day = date.day
+	if day > days_in_month(year, month):
+		day = days_in_month(year, month)
+	return Date(year, month, day)
+
+def days_between(start, end):
+	return end.ordinal() - start.ordinal()
+
+WEEKDAYS = ['monday', 'tuesday', 'wednesday', 'thursday', 'friday', 'saturday', 'sunday']
+
+def _date_ordinal(self):
+	return _days_from_civil(self.year, self.month, self.day)
+
+def _date_weekday(self):
+	return (self.ordinal() + 3) % 7
+
+def _date_weekday_name(self):
+	return WEEKDAYS[self.weekday()]
+
+def _date_add(self, days):
+	if type(days) is not int:
+		return NotImplemented
+	return add_days(self, days)
+
+def _date_sub(self, other):
+	if type(other) is int:
+		return add_days(self, -other)
+	if type(other) is Date:
+		return self.ordinal() - other.ordinal()
+	return NotImplemented
+
+def _date_cmp(a, b):
+	return a.ordinal() - b.ordinal()
+
+def _date_eq(self, other):
+	if type(other) is not Date:
+		return False
+	return _date_cmp(self, other) == 0
+
+def _date_ne(self, other):
+	return not _date_eq(self, other)
+
+def _date_lt(self, other):
+	return _date_cmp(self, other) < 0
+
+def _date_le(self, other):
+	return _date_cmp(self, other) <= 0
+
+def _date_gt(self, other):
+	return _date_cmp(self, other) > 0
+
+def _date_ge(self, other):
+	return _date_cmp(self, other) >= 0
+
+def _date_hash(self):
+	return hash(self.ordinal())
+
+Date.ordinal = _date_ordinal
+Date.weekday = _date_weekday
+Date.weekday_name = _date_weekday_name
+Date.__add__ = _date_add
+Date.__sub__ = _date_sub
+Date.__eq__ = _date_eq
+Date.__ne__ = _date_ne
+Date.__lt__ = _date_lt
+Date.__le__ = _date_le
+Date.__gt__ = _date_gt
+Date.__ge__ = _date_ge
+Date.__hash__ = _date_hash
+
+# Tasks
+
+def tasks():
+	"""Every saved task, read once per run of the script."""
+	return _tasks()
+
+def is_done(task):
+	return task.has_tag_with_name('done')
+
+def find_tasks(name=None, tag=None, done=None):
+	"""The saved tasks whose name contains `name`, that have the tag `tag` and are done or not."""
+	found = []
+	for task in _tasks():
+		if name is not None and name.lower() not in task.name.lower():
+			continue
+		if tag is not None and not task.has_tag_with_name(tag):
+			continue
+		if done is not None and is_done(task) != done:
+			continue
+		found.append(task)
+	return found
+
+def get_task(ref):
+	"""The saved task a TaskRef points to, None if it doesn't exist anymore."""
+	for task in _tasks():
+		if task.ref == ref:
+			return task
+	return None
+
+def subtasks_of(task):
+	"""The saved tasks that are subtasks of a task or of a TaskRef."""
+	ref = task
+	if type(task) is Task:
+		ref = task.ref
+	found = []
+	for other in _tasks():
+		for parent in other.get_tags_with_name('subtask_of'):
+			if parent.value == ref:
+				found.append(other)
+				break
+	return found
+
+# Settings
+
+def settings():
+	"""date_format, colors, sprint, script_time_limit_ms and scheduled_task_tag if set."""
+	return _settings()
+
+def sprint():
+	"""frequency, reference, done and past_done of the sprints."""
+	return _settings()['sprint']
+
+# Tags
+
+def tag_value(task, name, default=None):
+	"""The value of the first tag named `name`, `default` if there is none or it has no value."""
+	tag = task.get_tag_with_name(name)
+	if tag is None or tag.value is None:
+		return default
+	return tag.value
+
+def has_tag(task, name):
+	return task.has_tag_with_name(name)
+
+def set_tag(task, name, value=None):
+	"""Sets the value of the first tag named `name`, adding it if there is none."""
+	for tag in task.tags:
+		if tag.name == name:
+			tag.value = value
+			return task
+	task.tags.append(Tag(name, value))
+	return task
+
+def remove_tags(task, name):
+	"""Removes every tag named `name`."""
+	kept = []
+	for tag in task.tags:
+		if tag.name != name:
+			kept.append(tag)
+	task.tags = kept
+	return task
@@ -8,16 +8,20 @@ use eframe::{App, CreationContext};
 use uuid::Uuid;
 
 use crate::{
-	data_dir::DataDirError, handle_toast_error, help_string, ok_cancel_dialog::{OkCancelDialog, OkCancelResult}, scripts::{
+	command_palette::{CommandPalette, CommandPaletteResult, PaletteCommand}, data_dir::DataDirError, handle_toast_error, keybindings::KeyAction, help_string, ok_cancel_dialog::{OkCancelDialog, OkCancelResult}, schedule_dialog::ScheduleDialog, selection::Selection, scripts::{
 		badge::{BadgeList, BadgeType},
+		decorator::DecoratorList,
 		filter::FilterList,
+		hooks,
+		runner,
 		sorting::SortingList,
-		PocketPyLock,
-	}, session::Session, settings::Settings, side_panel::{SidePanel, SidePanelKind}, startup_script::StartupScript, tag::{Tag, TagValue}, task::{
+		PocketPyLock, PocketPyScriptError,
+	}, session::Session, settings::Settings, side_panel::{SidePanel, SidePanelKind}, startup_script::StartupScript, tag::{suggestions_popup, Tag, TagIndex, TagValue}, task::{
 		display_list::TaskDisplayList,
 		list::{TaskList, TaskListError},
-		TaskPath,
-	}, toast_error, toast_info, toast_success
+		markdown::{self, ChecklistClick},
+		Task, TaskAction, TaskPath,
+	}, toast_error, toast_info, toast_success, views::{order_parent, ManualOrders, ViewLayout, ViewLayoutKind, ViewList, ViewsError}
 };
 
 static mut SCRIPT_LOCK: Option<crate::scripts::PocketPyLock> = None;
@@ -26,12 +30,24 @@ pub fn script_lock() -> crate::scripts::PocketPyLockGuard<'static> {
 	unsafe { SCRIPT_LOCK.as_ref().unwrap() }.lock()
 }
 
+/// Like `script_lock`, without waiting for a running script.
+pub fn try_script_lock() -> Option<crate::scripts::PocketPyLockGuard<'static>> {
+	unsafe { (*std::ptr::addr_of!(SCRIPT_LOCK)).as_ref()? }.try_lock()
+}
+
 static SCRIPTS_WAITLIST: Mutex<VecDeque<String>> = Mutex::new(VecDeque::new());
 
 pub fn push_script_to_waitlist(script_name: String) {
 	SCRIPTS_WAITLIST.lock().unwrap().push_back(script_name);
 }
 
+static TAG_RENAMES: Mutex<VecDeque<(String, String)>> = Mutex::new(VecDeque::new());
+
+/// Renames a tag in every task of the list, once the current frame is drawn.
+pub fn push_tag_rename(from: String, to: String) {
+	TAG_RENAMES.lock().unwrap().push_back((from, to));
+}
+
 pub struct AdhdMateriaApp {
 	task_list: Result<TaskList, TaskListError>,
 	task_display_list: Option<TaskDisplayList>,
@@ -39,51 +55,83 @@ pub struct AdhdMateriaApp {
 	side_panel: SidePanel,
 
 	selected_task: Option<SelectedTask>,
+	selection: Selection,
+	schedule_dialog: Option<ScheduleDialog>,
 	scroll_to_task: Option<Uuid>,
+	keyboard_cursor: Option<Uuid>,
+	command_palette: Option<CommandPalette>,
+	display_update_pending: bool,
 
 	interactable: bool,
 
 	filter_list: Result<FilterList, &'static DataDirError>,
 	sorting_list: Result<SortingList, &'static DataDirError>,
+	decorator_list: Result<DecoratorList, &'static DataDirError>,
+	views: Result<ViewList, ViewsError>,
+}
+
+/// How the tasks of the main list are laid out, following the current view.
+enum TaskLayout {
+	Rows(Vec<(Uuid, usize)>),
+	Columns(Vec<(String, Vec<Uuid>)>),
+}
+
+impl TaskLayout {
+	fn order(&self) -> Vec<Uuid> {
+		match self {
+			Self::Rows(rows) => rows.iter().map(|(uuid, _)| *uuid).collect(),
+			Self::Columns(columns) => columns.iter().flat_map(|(_, tasks)| tasks.iter().copied()).collect(),
+		}
+	}
 }
 
+/// Payload of a task dragged by its handle to reorder it.
+struct DraggedTask(Uuid);
+
 struct SelectedTask {
 	uuid: Uuid,
 	display_list: TaskDisplayList,
+	/// Note being typed for the log of the task
+	note_text: String,
 }
 
 impl SelectedTask {
-	fn new(uuid: Uuid, task_list: &TaskList, filter_list: &FilterList, sorting_list: &SortingList) -> Self {
-		Self { uuid, display_list: TaskDisplayList::new(task_list, filter_list, sorting_list, Some(uuid)) }
+	fn new(uuid: Uuid, task_list: &TaskList, filter_list: &FilterList, sorting_list: &SortingList, decorator_list: &DecoratorList, manual_orders: &ManualOrders) -> Self {
+		Self { uuid, display_list: TaskDisplayList::new(task_list, filter_list, sorting_list, decorator_list, Some(uuid), "", manual_orders), note_text: String::new() }
 	}
 }
 
-impl AdhdMateriaApp {
-	pub fn new(cc: &CreationContext) -> Self {
-		unsafe {
-			SCRIPT_LOCK = Some(PocketPyLock::new());
-		}
+/// Applies the theme and fonts shared by every window of the app.
+pub(crate) fn setup_style(ctx: &egui::Context) {
+	// Enable catppuccin theme
+	Settings::get().theme.apply(ctx);
 
-		// Enable catppuccin theme
-		Settings::get().theme.apply(&cc.egui_ctx);
+	// Setup Nunito font
+	const NUNITO_REGULAR: &str = "nunito_regular";
 
-		// Setup Nunito font
-		const NUNITO_REGULAR: &str = "nunito_regular";
+	let mut fonts = egui::FontDefinitions::default();
 
-		let mut fonts = egui::FontDefinitions::default();
+	fonts.font_data.insert(
+		String::from(NUNITO_REGULAR),
+		egui::FontData::from_static(include_bytes!("../assets/fonts/Nunito-Regular.ttf")),
+	);
 
-		fonts.font_data.insert(
-			String::from(NUNITO_REGULAR),
-			egui::FontData::from_static(include_bytes!("../assets/fonts/Nunito-Regular.ttf")),
-		);
+	fonts
+		.families
+		.entry(egui::FontFamily::Proportional)
+		.or_default()
+		.insert(0, String::from(NUNITO_REGULAR));
 
-		fonts
-			.families
-			.entry(egui::FontFamily::Proportional)
-			.or_default()
-			.insert(0, String::from(NUNITO_REGULAR));
+	ctx.set_fonts(fonts);
+}
 
-		cc.egui_ctx.set_fonts(fonts);
+impl AdhdMateriaApp {
+	pub fn new(cc: &CreationContext) -> Self {
+		unsafe {
+			SCRIPT_LOCK = Some(PocketPyLock::new());
+		}
+
+		setup_style(&cc.egui_ctx);
 
 		match StartupScript::run() {
 			Ok(errors) => {
@@ -114,8 +162,21 @@ impl AdhdMateriaApp {
 			});
 		}
 
+		let views = ViewList::load();
+
+		if let Err(e) = &views {
+			toast_error!("Could not load views: {}", e);
+		}
+
 		let filter_list = FilterList::new();
 		let sorting_list = SortingList::new();
+		let decorator_list = DecoratorList::new();
+		let search = views.as_ref().map(|views| views.current().search.clone()).unwrap_or_default();
+		let manual_orders = views.as_ref().map(|views| views.current().manual_orders.clone()).unwrap_or_default();
+
+		if let Ok((task_list, _)) = &task_list {
+			TagIndex::rebuild(task_list.tasks());
+		}
 
 		Self {
 			task_display_list: task_list
@@ -126,22 +187,527 @@ impl AdhdMateriaApp {
 				.and_then(|(task_list, filter_list)| {
 					Some((task_list, filter_list, sorting_list.as_ref().ok()?))
 				})
-				.map(|(task_list, filter_list, sorting_list)| {
-					TaskDisplayList::new(task_list, filter_list, sorting_list, None)
+				.and_then(|(task_list, filter_list, sorting_list)| {
+					Some((task_list, filter_list, sorting_list, decorator_list.as_ref().ok()?))
+				})
+				.map(|(task_list, filter_list, sorting_list, decorator_list)| {
+					TaskDisplayList::new(task_list, filter_list, sorting_list, decorator_list, None, search.as_str(), &manual_orders)
 				}),
 			task_list: task_list.map(|(list, _)| list),
 			task_name_cache: HashMap::new(),
 			side_panel: SidePanel::default(),
 
 			selected_task: None,
+			selection: Selection::default(),
+			schedule_dialog: None,
 			scroll_to_task: None,
+			keyboard_cursor: None,
+			command_palette: None,
+			display_update_pending: false,
 
 			interactable: true,
 
 			filter_list,
 			sorting_list,
+			decorator_list,
+			views,
+		}
+	}
+
+	fn task_layout(&self) -> Option<TaskLayout> {
+		let task_list = self.task_list.as_ref().ok()?;
+		let tasks: Vec<&Task> = self
+			.task_display_list
+			.as_ref()?
+			.tasks()
+			.filter_map(|uuid| task_list.get(uuid))
+			.collect();
+
+		Some(match self.views.as_ref().map(|views| &views.current().layout) {
+			Ok(ViewLayout::Tree) => TaskLayout::Rows(crate::task::list::tree_order(tasks.iter().copied(), None)),
+			Ok(ViewLayout::Board { column_tag }) => TaskLayout::Columns(
+				crate::views::board_columns(&tasks, column_tag)
+					.into_iter()
+					.map(|(name, tasks)| {
+						if name.is_empty() {
+							(format!("No {}", column_tag), tasks)
+						} else {
+							(name, tasks)
+						}
+					})
+					.collect(),
+			),
+			_ => TaskLayout::Rows(tasks.iter().map(|task| (*task.get_uuid(), 0)).collect()),
+		})
+	}
+
+	/// Shows the view tabs, and the search/layout options of the current view. Returns whether the display lists need an update.
+	fn show_views(&mut self, ui: &mut egui::Ui) -> bool {
+		let views = match self.views.as_mut() {
+			Ok(views) => views,
+			Err(e) => {
+				ui.colored_label(ui.visuals().error_fg_color, format!("Couldn't load views: {}", e));
+				return false;
+			}
+		};
+
+		let mut update_required = false;
+		let mut to_select = None;
+		let mut to_remove = None;
+		let mut duplicate = false;
+
+		ui.horizontal_wrapped(|ui| {
+			for (idx, view) in views.iter().enumerate() {
+				let response = ui.selectable_label(
+					idx == views.current_index(),
+					egui::RichText::new(view.name.as_str()).size(16.0),
+				);
+
+				if response.clicked() {
+					to_select = Some(idx);
+				}
+
+				response.context_menu(|ui| {
+					if ui.button("Delete view").clicked() {
+						to_remove = Some(idx);
+						ui.close_menu();
+					}
+				});
+			}
+
+			if ui.small_button("+").on_hover_text("New view, copied from the current one").clicked() {
+				duplicate = true;
+			}
+		});
+
+		ui.add_space(4.0);
+
+		let mut save = false;
+		let mut rename = None;
+
+		ui.horizontal_wrapped(|ui| {
+			let view = views.current_mut();
+
+			// The name being typed is kept aside, and given to the view once the field is left
+			let name_id = ui.id().with("view_name");
+			let mut name = ui.data_mut(|d| d.get_temp::<String>(name_id)).unwrap_or_else(|| view.name.clone());
+
+			ui.label("Name");
+			let response = ui.add(egui::TextEdit::singleline(&mut name).desired_width(128.0));
+
+			if response.lost_focus() {
+				ui.data_mut(|d| d.remove::<String>(name_id));
+
+				if name != view.name {
+					rename = Some(name);
+				}
+			} else if response.changed() {
+				ui.data_mut(|d| d.insert_temp(name_id, name));
+			}
+
+			ui.add_space(8.0);
+			ui.label("🔍");
+			let response = ui.add(egui::TextEdit::singleline(&mut view.search).hint_text("Search").desired_width(192.0));
+
+			if response.changed() {
+				update_required = true;
+				save = true;
+			}
+
+			// Completes the last word of the search with a tag name
+			let (start, last_word) = view.search.rsplit_once(' ').map_or(("", view.search.as_str()), |(start, last)| (start, last));
+
+			if let Some(name) = suggestions_popup(ui, &response, || {
+				if last_word.is_empty() {
+					Vec::new()
+				} else {
+					TagIndex::get().suggest_names(last_word, 8)
+				}
+			}) {
+				view.search = if start.is_empty() { name } else { format!("{} {}", start, name) };
+				update_required = true;
+				save = true;
+			}
+
+			ui.add_space(8.0);
+			ui.label("Layout");
+
+			let mut layout = view.layout.kind();
+
+			egui::ComboBox::from_id_source("view_layout")
+				.selected_text(layout.to_string())
+				.show_ui(ui, |ui| {
+					ui.selectable_value(&mut layout, ViewLayoutKind::List, "List");
+					ui.selectable_value(&mut layout, ViewLayoutKind::Tree, "Tree");
+					ui.selectable_value(&mut layout, ViewLayoutKind::Board, "Board");
+				});
+
+			if layout != view.layout.kind() {
+				view.layout = match layout {
+					ViewLayoutKind::List => ViewLayout::List,
+					ViewLayoutKind::Tree => ViewLayout::Tree,
+					ViewLayoutKind::Board => ViewLayout::Board {
+						column_tag: String::from("status"),
+					},
+				};
+				save = true;
+			}
+
+			if let ViewLayout::Board { column_tag } = &mut view.layout {
+				ui.label("by tag");
+				save |= ui.add(egui::TextEdit::singleline(column_tag).desired_width(96.0)).changed();
+			}
+		});
+
+		if save {
+			handle_toast_error!("Could not save views: {}", views.save());
+		}
+
+		if let Some(name) = rename {
+			handle_toast_error!("Could not rename view: {}", views.rename_current(name.as_str()));
+		}
+
+		if duplicate {
+			handle_toast_error!("Could not create view: {}", views.duplicate_current());
+		}
+
+		if let Some(idx) = to_remove {
+			handle_toast_error!("Could not delete view: {}", views.remove(idx));
+			to_select = Some(views.current_index());
+		}
+
+		if let Some(idx) = to_select {
+			handle_toast_error!("Could not switch view: {}", views.select(idx));
+			self.filter_list = FilterList::new();
+			self.sorting_list = SortingList::new();
+			self.decorator_list = DecoratorList::new();
+			update_required = true;
+		}
+
+		update_required
+	}
+
+	/// Copies the enabled filters, sortings and decorators into the current view.
+	fn sync_view_badges(&mut self) {
+		if let (Ok(views), Ok(filter_list), Ok(sorting_list), Ok(decorator_list)) = (
+			self.views.as_mut(),
+			self.filter_list.as_ref(),
+			self.sorting_list.as_ref(),
+			self.decorator_list.as_ref(),
+		) {
+			let filters: Vec<String> = filter_list.iter_set().map(String::from).collect();
+			let sortings: Vec<String> = sorting_list.iter_set().map(String::from).collect();
+			let decorators: Vec<String> = decorator_list.iter_set().map(String::from).collect();
+			let view = views.current_mut();
+
+			if view.filters != filters || view.sortings != sortings || view.decorators != decorators {
+				view.filters = filters;
+				view.sortings = sortings;
+				view.decorators = decorators;
+				handle_toast_error!("Could not save views: {}", views.save());
+			}
+		}
+	}
+
+	/// The manual orders of the current view, none if the views could not be loaded.
+	fn manual_orders(&self) -> ManualOrders {
+		self.views.as_ref().map(|views| views.current().manual_orders.clone()).unwrap_or_default()
+	}
+
+	fn toggle_selected(&mut self, uuid: Uuid) {
+		if self.selected_task.as_ref().is_some_and(|selected| selected.uuid == uuid) {
+			self.selected_task = None;
+		} else {
+			let manual_orders = self.manual_orders();
+
+			if let Ok(task_list) = self.task_list.as_ref() {
+				if let Ok(filter_list) = self.filter_list.as_ref() {
+					if let Ok(sorting_list) = self.sorting_list.as_ref() {
+						if let Ok(decorator_list) = self.decorator_list.as_ref() {
+							self.selected_task = Some(SelectedTask::new(uuid, task_list, filter_list, sorting_list, decorator_list, &manual_orders));
+						}
+					}
+				}
+			}
+		}
+	}
+
+	/// Runs a keyboard action on the task under the keyboard cursor, returns whether the display lists need an update.
+	fn run_key_action(&mut self, action: KeyAction) -> bool {
+		let visible: Vec<Uuid> = match self.task_layout() {
+			Some(layout) => layout.order(),
+			None => return false,
+		};
+
+		let cursor_idx = self
+			.keyboard_cursor
+			.and_then(|cursor| visible.iter().position(|uuid| *uuid == cursor));
+		let cursor = cursor_idx.map(|idx| visible[idx]);
+
+		match action {
+			KeyAction::NextTask | KeyAction::PreviousTask => {
+				let idx = match (cursor_idx, action) {
+					(Some(idx), KeyAction::NextTask) => (idx + 1).min(visible.len().saturating_sub(1)),
+					(Some(idx), _) => idx.saturating_sub(1),
+					(None, _) => 0,
+				};
+
+				self.keyboard_cursor = visible.get(idx).copied();
+				self.scroll_to_task = self.keyboard_cursor;
+				false
+			}
+			KeyAction::SelectTask => {
+				if let Some(cursor) = cursor {
+					self.toggle_selected(cursor);
+				}
+
+				false
+			}
+			KeyAction::SelectAll => {
+				self.selection.select_all(&visible);
+				false
+			}
+			KeyAction::CommandPalette => {
+				self.command_palette = Some(CommandPalette::new(
+					self.filter_list.as_ref().ok(),
+					self.sorting_list.as_ref().ok(),
+					self.decorator_list.as_ref().ok(),
+				));
+				false
+			}
+			KeyAction::NewTask => match self.task_list.as_mut() {
+				Ok(task_list) => {
+					let new_task = create_task(task_list);
+					self.keyboard_cursor = Some(new_task);
+					self.scroll_to_task = Some(new_task);
+					true
+				}
+				Err(_) => false,
+			},
+			KeyAction::EditTask | KeyAction::CompleteTask | KeyAction::DeleteTask => {
+				let Some(task) = cursor.and_then(|cursor| {
+					self.task_list.as_mut().ok()?.get_mut(&cursor)
+				}) else {
+					return false;
+				};
+
+				self.scroll_to_task = cursor;
+
+				match action {
+					KeyAction::EditTask => {
+						task.edit();
+						false
+					}
+					KeyAction::DeleteTask => {
+						task.request_delete();
+						false
+					}
+					_ if task.is_done() => false,
+					_ => {
+						handle_toast_error!("Could not save task: {}", task.mark_done(TaskPath::Tasks));
+						true
+					}
+				}
+			}
+		}
+	}
+
+	/// Runs an action of the menu of a task, returns whether the display lists need an update.
+	fn run_task_action(&mut self, uuid: Uuid, action: TaskAction) -> bool {
+		let Some(task_list) = self.task_list.as_mut().ok() else {
+			return false;
+		};
+
+		let name = task_list.get(&uuid).map(|task| task.name.clone()).unwrap_or_default();
+
+		match action {
+			TaskAction::Duplicate { with_subtasks } => {
+				match task_list.duplicate(&uuid, with_subtasks) {
+					Ok(copy) => self.scroll_to_task = copy,
+					Err(e) => {
+						toast_error!("Could not duplicate task: {}", e);
+					}
+				}
+
+				true
+			}
+			TaskAction::MakeRecurring => {
+				self.schedule_dialog = Some(ScheduleDialog::make_recurring(uuid, name.as_str()));
+				false
+			}
+			TaskAction::Snooze => {
+				self.schedule_dialog = Some(ScheduleDialog::snooze(uuid, name.as_str()));
+				false
+			}
+			TaskAction::MakeChecklistItemSubtask { line } => {
+				match task_list.make_checklist_item_subtask(&uuid, line) {
+					Ok(subtask) => self.scroll_to_task = subtask,
+					Err(e) => {
+						toast_error!("Could not create subtask: {}", e);
+					}
+				}
+
+				true
+			}
+		}
+	}
+
+	/// Shows the dialog moving tasks to the scheduled tasks if it is open, returns whether the display lists need an update.
+	fn show_schedule_dialog(&mut self, ctx: &egui::Context) -> bool {
+		let Some(dialog) = self.schedule_dialog.as_mut() else {
+			return false;
+		};
+
+		self.interactable = false;
+
+		let Some(result) = dialog.show(ctx) else {
+			return false;
+		};
+
+		let dialog = self.schedule_dialog.take().expect("dialog was shown");
+		self.interactable = true;
+
+		let (OkCancelResult::Ok, Ok(task_list)) = (result, self.task_list.as_mut()) else {
+			return false;
+		};
+
+		match task_list.convert_to_scheduled(&dialog.tasks, &dialog.scheduled_task()) {
+			Ok(count) => {
+				toast_success!("Moved {} tasks to the scheduled tasks", count);
+			}
+			Err(e) => {
+				toast_error!("Could not schedule tasks: {}", e);
+			}
+		}
+
+		self.selection.clear();
+
+		// The scheduled task list reads the new tasks when opened again
+		if self.side_panel.kind() == SidePanelKind::ScheduledTasks {
+			self.side_panel.open(SidePanelKind::ScheduledTasks);
+		}
+
+		true
+	}
+
+	/// Shows the command palette if it is open, returns whether the display lists need an update.
+	fn show_command_palette(&mut self, ctx: &egui::Context, clear_done: &mut bool) -> bool {
+		let Some(result) = self.command_palette.as_mut().and_then(|palette| palette.show(ctx)) else {
+			return false;
+		};
+
+		self.command_palette = None;
+
+		match result {
+			CommandPaletteResult::Close => false,
+			CommandPaletteResult::Run(command) => match command {
+				PaletteCommand::Action(action) => self.run_key_action(action),
+				PaletteCommand::ClearDoneTasks => {
+					*clear_done = true;
+					false
+				}
+				PaletteCommand::OpenSidePanel(kind) => {
+					self.side_panel.open(kind);
+					false
+				}
+				PaletteCommand::ToggleFilter(name) => {
+					if let Ok(filter_list) = self.filter_list.as_mut() {
+						filter_list.toggle(name.as_str());
+					}
+
+					false
+				}
+				PaletteCommand::ToggleSorting(name) => {
+					if let Ok(sorting_list) = self.sorting_list.as_mut() {
+						sorting_list.toggle(name.as_str());
+					}
+
+					false
+				}
+				PaletteCommand::ToggleDecorator(name) => {
+					if let Ok(decorator_list) = self.decorator_list.as_mut() {
+						decorator_list.toggle(name.as_str());
+					}
+
+					false
+				}
+				PaletteCommand::RunScript(name) => {
+					push_script_to_waitlist(name);
+					false
+				}
+			},
 		}
 	}
+
+	/// Starts waiting standalone scripts and shows the one that is running, returns whether a
+	/// script finished.
+	fn show_running_script(ctx: &egui::Context) -> bool {
+		let mut finished = false;
+
+		if let Some((name, result)) = runner::poll_finished() {
+			finished = true;
+
+			match result {
+				Ok(()) => {}
+				Err(PocketPyScriptError::Cancelled) => {
+					toast_info!("Script {} was cancelled", name);
+				}
+				Err(e) => {
+					toast_error!("Error in script {}:\n{}", name, e);
+				}
+			}
+		}
+
+		if !runner::is_running() {
+			if let Some(script) = SCRIPTS_WAITLIST.lock().unwrap().pop_front() {
+				runner::spawn(script);
+			}
+		}
+
+		if let Some(info) = runner::running_info() {
+			egui::TopBottomPanel::bottom("running_script").show(ctx, |ui| {
+				ui.horizontal(|ui| {
+					ui.spinner();
+					ui.label(format!(
+						"Running {} ({}s)",
+						info.name,
+						info.elapsed.as_secs()
+					));
+
+					if let Some(progress) = info.progress {
+						ui.add(
+							egui::ProgressBar::new(progress)
+								.show_percentage()
+								.desired_width(240.0),
+						);
+					}
+
+					if ui.button("Cancel").clicked() {
+						crate::scripts::cancel_running_script();
+					}
+				});
+			});
+
+			ctx.request_repaint_after(Duration::from_millis(100));
+		}
+
+		finished
+	}
+}
+
+fn create_task(task_list: &mut TaskList) -> Uuid {
+	let mut new_task = Settings::get().default_task.clone();
+	new_task.new_uuid();
+	new_task.edit();
+	let uuid = *new_task.get_uuid();
+
+	if let Err(e) = task_list.add_task(new_task) {
+		toast_error!("Could not create task: {}", e);
+	} else {
+		hooks::mark_unsaved(uuid);
+	}
+
+	uuid
 }
 
 impl App for AdhdMateriaApp {
@@ -149,6 +715,10 @@ impl App for AdhdMateriaApp {
 		let left_panel_was_shown = self.side_panel.is_shown();
 
 		let mut to_select = None;
+		let mut clear_done = false;
+		let mut task_action = None;
+		let mut task_drop = None;
+		let palette_update_required = self.show_command_palette(ctx, &mut clear_done) | self.show_schedule_dialog(ctx);
 
 		egui::SidePanel::left("left_panel_buttons")
 			.exact_width(64.0)
@@ -189,34 +759,81 @@ impl App for AdhdMateriaApp {
 					ui.separator();
 					side_panel_button(ui, SidePanelKind::SortingScripts, '🔤');
 					ui.separator();
+					side_panel_button(ui, SidePanelKind::DecoratorScripts, '🏷');
+					ui.separator();
 					side_panel_button(ui, SidePanelKind::Scripts, '📃');
 					ui.separator();
+					side_panel_button(ui, SidePanelKind::LibraryScripts, '📚');
+					ui.separator();
+					side_panel_button(ui, SidePanelKind::Packages, '📦');
+					ui.separator();
+					side_panel_button(ui, SidePanelKind::TagManager, '🔖');
+					ui.separator();
 					side_panel_button(ui, SidePanelKind::CompletedTasks, '☑');
 					ui.separator();
+					side_panel_button(ui, SidePanelKind::Console, '🖥');
+					ui.separator();
 					side_panel_button(ui, SidePanelKind::Settings, '⛭');
 				});
 			});
 
+		let no_orders = ManualOrders::new();
+		let manual_orders = self.views.as_ref().map(|views| &views.current().manual_orders).unwrap_or(&no_orders);
+
 		egui::SidePanel::left("left_panel")
 			.min_width(192.0)
 			.default_width(400.0)
 			.show_animated(ctx, self.side_panel.is_shown(), |ui| {
-				self.side_panel.show(ui, &self.task_name_cache, &mut self.scroll_to_task, &mut to_select);
+				self.side_panel.show(ui, &self.task_name_cache, &mut self.scroll_to_task, &mut to_select, manual_orders);
 			});
 
 		if left_panel_was_shown && !self.side_panel.is_shown() {
 			self.filter_list = FilterList::new();
 			self.sorting_list = SortingList::new();
+			self.decorator_list = DecoratorList::new();
 		}
 
-		let mut update_required = self
+		let badges_changed = self
 			.filter_list
 			.as_mut()
 			.is_ok_and(|filter_list| filter_list.check_changed())
-			|| self
+			| self
 				.sorting_list
 				.as_mut()
-				.is_ok_and(|sorting_list| sorting_list.check_changed());
+				.is_ok_and(|sorting_list| sorting_list.check_changed())
+			| self
+				.decorator_list
+				.as_mut()
+				.is_ok_and(|decorator_list| decorator_list.check_changed());
+
+		if badges_changed {
+			self.sync_view_badges();
+		}
+
+		let mut update_required =
+			palette_update_required | badges_changed | Self::show_running_script(ctx);
+
+		// Hooks run on this thread, so they wait for the standalone script holding the interpreter
+		if !runner::is_running() {
+			if let Ok(task_list) = self.task_list.as_mut() {
+				update_required |= hooks::run_pending(task_list);
+			}
+		}
+
+		while let Some((from, to)) = TAG_RENAMES.lock().unwrap().pop_front() {
+			if let Ok(task_list) = self.task_list.as_mut() {
+				match task_list.rename_tag(from.as_str(), to.as_str()) {
+					Ok(count) => {
+						toast_success!("Renamed tag {} to {} in {} tasks", from, to, count);
+					}
+					Err(e) => {
+						toast_error!("Could not rename tag {}: {}", from, e);
+					}
+				}
+
+				update_required = true;
+			}
+		}
 
 			let task_list = self.task_list.as_mut().expect("display list is some");
 
@@ -269,7 +886,18 @@ impl App for AdhdMateriaApp {
 
 						ui.add_space(8.0);
 
-						ui.label(selected_task.description.as_str());
+						match markdown::show(ui, selected_task.description.as_str(), true) {
+							Some(ChecklistClick::Toggle(line)) => match selected_task.toggle_checklist_item(line, TaskPath::Tasks) {
+								Ok(changed) => update_required |= changed,
+								Err(e) => {
+									toast_error!("Could not save task: {}", e);
+								}
+							},
+							Some(ChecklistClick::MakeSubtask(line)) => {
+								task_action = Some((selected_task_id, TaskAction::MakeChecklistItemSubtask { line }));
+							}
+							None => {}
+						}
 
 						ui.add_space(8.0);
 						ui.separator();
@@ -282,6 +910,31 @@ impl App for AdhdMateriaApp {
 							}
 						});
 
+						ui.add_space(8.0);
+						ui.strong("Notes");
+
+						for note in selected_task.notes.iter() {
+							ui.horizontal_wrapped(|ui| {
+								ui.label(egui::RichText::new(note.time_label()).weak().monospace());
+								ui.label(note.text.as_str());
+							});
+						}
+
+						ui.horizontal(|ui| {
+							let note_text = &mut self.selected_task.as_mut().expect("is some").note_text;
+							let response = ui.add(egui::TextEdit::singleline(note_text).hint_text("Called vendor, waiting for reply…").desired_width(ui.available_width() - 96.0));
+							let submitted = response.lost_focus() && ui.input(|i| i.key_pressed(egui::Key::Enter));
+
+							if (ui.button("Add Note").clicked() || submitted) && !note_text.trim().is_empty() {
+								selected_task.add_note(note_text);
+								note_text.clear();
+								handle_toast_error!("Could not save task: {}", selected_task.save(TaskPath::Tasks));
+								update_required = true;
+							}
+						});
+
+						ui.add_space(8.0);
+
 						ui.separator();
 						ui.add_space(8.0);
 
@@ -294,11 +947,13 @@ impl App for AdhdMateriaApp {
 										.spacing((40.0, 12.0))
 										.striped(true)
 										.show(ui, |ui| {
-											for task_id in self.selected_task.as_ref().unwrap().display_list.tasks() {
+											let display_list = &self.selected_task.as_ref().unwrap().display_list;
+
+											for task_id in display_list.tasks() {
 												let task = task_list.get_mut(task_id).expect("task display list should only have valid uuids");
 												if task.is_subtask_of(&selected_task_id) {
 													let task_widget_response =
-														task.widget().show(
+														task.widget().with_decorations(display_list.decorations(task_id)).show(
 																ui,
 																&self.task_name_cache,
 																selected_task_id == *task_id,
@@ -331,6 +986,10 @@ impl App for AdhdMateriaApp {
 														to_select = Some(task_id.clone());
 													}
 
+													if let Some(action) = task_widget_response.action {
+														task_action = Some((*task_id, action));
+													}
+
 													ui.end_row();
 												}
 											}
@@ -346,9 +1005,12 @@ impl App for AdhdMateriaApp {
 											Some(TagValue::TaskReference(selected_task_id.clone())),
 										));
 										new_task.edit();
+										let uuid = *new_task.get_uuid();
 
 										if let Err(e) = task_list.add_task(new_task) {
 											toast_error!("Could not create task: {}", e);
+										} else {
+											hooks::mark_unsaved(uuid);
 										}
 
 										update_required = true;
@@ -370,13 +1032,11 @@ impl App for AdhdMateriaApp {
 			ui.separator();
 			ui.add_space(8.0);
 
-			let mut clear_done = false;
-			
 			ui.horizontal_wrapped(|ui| {
 				ui.label("Tasks finished this sprint:");
 				ui.colored_label(Settings::get().theme.get_catppuccin().green, Session::current().current_done_counter.to_string());
 				ui.add_space(8.0);
-				clear_done = ui.button("Clear Done Tasks").clicked();
+				clear_done |= ui.button("Clear Done Tasks").clicked();
 			});
 
 			let mut done_cleared = 0;
@@ -385,13 +1045,36 @@ impl App for AdhdMateriaApp {
 			ui.separator();
 			ui.add_space(8.0);
 
+			update_required |= self.show_views(ui);
+
+			ui.add_space(8.0);
+
 			show_badge_list(ui, &mut self.filter_list, "Filter");
 			show_badge_list(ui, &mut self.sorting_list, "Sorting");
+			show_badge_list(ui, &mut self.decorator_list, "Decorator");
 
 			ui.add_space(8.0);
 			ui.separator();
 			ui.add_space(16.0);
 
+			let layout = self.task_layout();
+			let visible = layout.as_ref().map(TaskLayout::order).unwrap_or_default();
+
+			if let Ok(task_list) = self.task_list.as_mut() {
+				let response = self.selection.show(ui, task_list, &self.task_name_cache, &visible, &mut self.interactable);
+				update_required |= response.changed;
+
+				if response.schedule {
+					self.schedule_dialog = Some(ScheduleDialog::schedule(self.selection.tasks().to_vec()));
+				}
+
+				if !self.selection.is_empty() {
+					ui.add_space(8.0);
+					ui.separator();
+					ui.add_space(16.0);
+				}
+			}
+
 			ui.add_enabled_ui(self.interactable, |ui| {
 				if !self.interactable {
 					ui.multiply_opacity(0.25);
@@ -403,84 +1086,153 @@ impl App for AdhdMateriaApp {
 				match &mut self.task_list {
 					Ok(task_list) => {
 						ui.with_layout(egui::Layout::top_down_justified(egui::Align::TOP), |ui| {
-							egui::ScrollArea::vertical().auto_shrink(false).show(ui, |ui| {
-								egui::Grid::new("task_grid")
-									.num_columns(1)
-									.spacing((40.0, 12.0))
-									.striped(true)
-									.show(ui, |ui| {
-										for task_id in self.task_display_list.as_ref().expect("display list should be Some when task list is ok").tasks() {
-											let task = task_list.get_mut(task_id).expect("task display list should only have valid uuids");
-											let task_widget_response = task.widget().show(
-												ui,
-												&self.task_name_cache,
-												selected_task == Some(*task_id),
-												&mut self.scroll_to_task,
-												&mut to_select,
-											);
-											update_required |= task_widget_response.changed;
+							egui::ScrollArea::vertical()
+								.hscroll(matches!(layout, Some(TaskLayout::Columns(_))))
+								.auto_shrink(false)
+								.show(ui, |ui| {
+								let mut show_task = |ui: &mut egui::Ui, task_id: &Uuid| {
+									// Tasks are ordered among their siblings, so they can only be dropped next to them
+									let parent = |uuid: &Uuid| task_list.get(uuid).map(order_parent);
+									let droppable = egui::DragAndDrop::payload::<DraggedTask>(ui.ctx()).is_some_and(|dragged| {
+										dragged.0 != *task_id && parent(&dragged.0) == parent(task_id)
+									});
+
+									let task = task_list.get_mut(task_id).expect("task display list should only have valid uuids");
+									let decorations = self
+										.task_display_list
+										.as_ref()
+										.map(|display_list| display_list.decorations(task_id))
+										.unwrap_or_default();
+									let row = ui.horizontal(|ui| {
+										ui.dnd_drag_source(egui::Id::new(("drag_task", *task_id)), DraggedTask(*task_id), |ui| {
+											ui.label(egui::RichText::new("↕").weak());
+										})
+										.response
+										.on_hover_text("Drag to reorder");
+
+										task.widget().with_decorations(decorations).show(
+											ui,
+											&self.task_name_cache,
+											selected_task == Some(*task_id) || self.selection.contains(task_id),
+											&mut self.scroll_to_task,
+											&mut to_select,
+										)
+									});
+									let task_widget_response = row.inner;
+									update_required |= task_widget_response.changed;
 
-											ui.end_row();
+									if droppable {
+										let rect = row.response.rect;
+										let drop_zone = ui.interact(rect, egui::Id::new(("drop_task", *task_id)), egui::Sense::hover());
 
-											if task.is_pending_delete() {
-												self.interactable = false;
+										if let Some(pointer) = ui.ctx().pointer_interact_pos().filter(|_| drop_zone.contains_pointer()) {
+											let after = pointer.y > rect.center().y;
 
-												if let Some(result) = OkCancelDialog::default()
-													.with_title(format!("Delete task {}?", task.name))
-													.with_subtext("You cannot undo this action.")
-													.with_ok_text("Delete")
-													.with_ok_color(ui.style().visuals.error_fg_color)
-													.show(ctx)
-												{
-													self.interactable = true;
+											ui.painter().hline(
+												rect.x_range(),
+												if after { rect.bottom() + 4.0 } else { rect.top() - 4.0 },
+												ui.visuals().selection.stroke,
+											);
 
-													match result {
-														OkCancelResult::Ok => task.mark_for_delete(),
-														OkCancelResult::Cancel => task.edit(),
-													}
-												}
+											if let Some(dragged) = drop_zone.dnd_release_payload::<DraggedTask>() {
+												task_drop = Some((dragged.0, *task_id, after));
 											}
+										}
+									}
+
+									if self.keyboard_cursor == Some(*task_id) {
+										ui.painter().rect_stroke(
+											task_widget_response.rect.expand(2.0),
+											ui.visuals().widgets.active.rounding,
+											ui.visuals().selection.stroke,
+										);
+									}
 
-											if clear_done && task.is_done() {
-												task.mark_for_delete();
-												done_cleared += 1;
+									if task.is_pending_delete() {
+										self.interactable = false;
+
+										if let Some(result) = OkCancelDialog::default()
+											.with_title(format!("Delete task {}?", task.name))
+											.with_subtext("You cannot undo this action.")
+											.with_ok_text("Delete")
+											.with_ok_color(ui.style().visuals.error_fg_color)
+											.show(ctx)
+										{
+											self.interactable = true;
+
+											match result {
+												OkCancelResult::Ok => task.mark_for_delete(),
+												OkCancelResult::Cancel => task.edit(),
 											}
+										}
+									}
 
-											if task_widget_response.selected {
-												if let Ok(filter_list) = self.filter_list.as_ref() {
-													if let Ok(sorting_list) = self.sorting_list.as_ref() {
-														if self.selected_task.as_ref().is_some_and(|s| s.uuid == *task_id)
-														{
-															self.selected_task = None;
-														} else {
-															self.selected_task =
-																Some(SelectedTask::new(task_id.clone(), task_list, filter_list, sorting_list));
-														}
+									if clear_done && task.is_done() {
+										task.mark_for_delete();
+										done_cleared += 1;
+									}
+
+									if let Some(action) = task_widget_response.action {
+										task_action = Some((*task_id, action));
+									}
+
+									// Ctrl and shift clicks select tasks for bulk actions instead of opening them
+									if task_widget_response.selected && !self.selection.click(*task_id, ui.input(|i| i.modifiers), &visible) {
+										self.selection.clear();
+										to_select = Some(*task_id);
+									}
+
+									if scroll_to == Some(*task_id) {
+										ui.scroll_to_rect(task_widget_response.rect, Some(egui::Align::Center));
+									}
+								};
+
+								match &layout {
+									Some(TaskLayout::Rows(rows)) => {
+										egui::Grid::new("task_grid")
+											.num_columns(1)
+											.spacing((40.0, 12.0))
+											.striped(true)
+											.show(ui, |ui| {
+												for (task_id, depth) in rows {
+													if *depth > 0 {
+														ui.horizontal(|ui| {
+															ui.add_space(24.0 * *depth as f32);
+															show_task(ui, task_id);
+														});
+													} else {
+														show_task(ui, task_id);
 													}
+
+													ui.end_row();
 												}
-											}
+											});
+									}
+									Some(TaskLayout::Columns(columns)) => {
+										ui.horizontal_top(|ui| {
+											for (name, tasks) in columns {
+												ui.vertical(|ui| {
+													ui.set_width(320.0);
+													ui.heading(name.as_str());
+													ui.separator();
+
+													for task_id in tasks {
+														show_task(ui, task_id);
+														ui.add_space(12.0);
+													}
+												});
 
-											if scroll_to == Some(*task_id) {
-												ui.scroll_to_rect(task_widget_response.rect, Some(egui::Align::Center));
+												ui.separator();
 											}
-										}
-									});
+										});
+									}
+									None => {}
+								}
 
 								ui.add_space(16.0);
 
 								if ui.button("New Task").clicked() {
-									let mut new_task = Settings::get().default_task.clone();
-									new_task.new_uuid();
-									new_task.edit();
-									self.scroll_to_task = Some(new_task.get_uuid().clone());
-
-									if let Err(e) = task_list.add_task(new_task) {
-										crate::toasts()
-											.error(format!("Could not create task: {}", e))
-											.set_closable(true)
-											.set_duration(Some(Duration::from_millis(10_000)));
-									}
-
+									self.scroll_to_task = Some(create_task(task_list));
 									update_required = true;
 								}
 							});
@@ -516,15 +1268,18 @@ impl App for AdhdMateriaApp {
 
 
 			if let Some(to_select) = to_select {
-				if self.selected_task.as_ref().is_some_and(|selected| selected.uuid == to_select) {
-					self.selected_task = None;
-				} else {
-					if let Ok(task_list) = self.task_list.as_ref() {
-						if let Ok(filter_list) = self.filter_list.as_ref() {
-							if let Ok(sorting_list) = self.sorting_list.as_ref() {
-								self.selected_task = Some(SelectedTask::new(to_select, task_list, filter_list, sorting_list));
-							}
-						}
+				self.toggle_selected(to_select);
+			}
+
+			if let Some((uuid, action)) = task_action {
+				update_required |= self.run_task_action(uuid, action);
+			}
+
+			if let Some((dragged, target, after)) = task_drop {
+				if let (Ok(views), Ok(task_list)) = (self.views.as_mut(), self.task_list.as_ref()) {
+					if views.current_mut().move_task(task_list.tasks(), dragged, target, after) {
+						handle_toast_error!("Could not save views: {}", views.save());
+						update_required = true;
 					}
 				}
 			}
@@ -550,7 +1305,26 @@ impl App for AdhdMateriaApp {
 			}
 		});
 
-		if update_required {
+		if self.interactable && self.command_palette.is_none() && !ctx.wants_keyboard_input() {
+			for action in KeyAction::ALL {
+				let keybinding = Settings::get().keybinding(action);
+
+				if keybinding.consume(ctx) {
+					update_required |= self.run_key_action(action);
+				}
+			}
+		}
+
+		// Filters and sortings can't run while a standalone script holds the interpreter, so
+		// rebuilding the display lists waits for it to finish
+		let display_update_required = update_required | self.display_update_pending;
+		self.display_update_pending = false;
+
+		if display_update_required && crate::scripts::runner::is_running() {
+			self.display_update_pending = true;
+		} else if display_update_required {
+			let manual_orders = self.manual_orders();
+
 			if self.task_display_list.take().is_some() {
 				self.task_display_list = Some(TaskDisplayList::new(
 					self.task_list.as_ref().expect("task display list is some"),
@@ -558,7 +1332,10 @@ impl App for AdhdMateriaApp {
 						.as_ref()
 						.expect("task display list is some"),
 					self.sorting_list.as_ref().expect("task display is some"),
-				None));
+					self.decorator_list.as_ref().expect("task display is some"),
+				None,
+				self.views.as_ref().map(|views| views.current().search.as_str()).unwrap_or_default(),
+				&manual_orders));
 			}
 
 			if let Some(selected_task) = self.selected_task.as_mut() {
@@ -568,9 +1345,23 @@ impl App for AdhdMateriaApp {
 						.as_ref()
 						.expect("task display list is some"),
 					self.sorting_list.as_ref().expect("task display is some"),
-				Some(selected_task.uuid.clone()));
+					self.decorator_list.as_ref().expect("task display is some"),
+				Some(selected_task.uuid.clone()),
+				"",
+				&manual_orders);
 			}
 
+			self.display_update_pending = self
+				.task_display_list
+				.as_ref()
+				.is_some_and(TaskDisplayList::is_incomplete)
+				|| self
+					.selected_task
+					.as_ref()
+					.is_some_and(|selected_task| selected_task.display_list.is_incomplete());
+		}
+
+		if update_required {
 			if let Ok(task_list) = self.task_list.as_ref() {
 				self.task_name_cache.clear();
 
@@ -578,13 +1369,9 @@ impl App for AdhdMateriaApp {
 					self.task_name_cache
 						.insert(task.get_uuid().clone(), task.name.clone());
 				}
-			}
-		}
 
-		let mut script_waitlist = SCRIPTS_WAITLIST.lock().unwrap();
-
-		while let Some(script) = script_waitlist.pop_front() {
-			crate::scripts::run_standalone_script(script.as_str())
+				TagIndex::rebuild(task_list.tasks());
+			}
 		}
 
 		while let Some(new_task) = crate::scripts::new_tasks_waitlist_next() {
@@ -616,19 +1403,42 @@ fn show_badge_list<T: BadgeType>(
 				ui.label(egui::RichText::new(format!("{}: ", script_name)).size(16.0));
 
 				let mut to_swap = None;
+				let mut to_set_params = None;
+				let mut to_set_descending = None;
 
 				for (idx, (badge, mut enabled)) in badge_list.iter_all().enumerate() {
 					let was_enabled = enabled;
-
-					ui.add(crate::scripts::badge::Badge::<T>::new(
-						badge,
-						&mut enabled,
-						idx as i32 + 1,
-					));
+					let mut params = badge_list.param_values(badge);
+					let was_descending = badge_list.is_descending(badge);
+					let mut descending = was_descending;
+
+					if ui
+						.add(
+							crate::scripts::badge::Badge::<T>::new(badge, &mut enabled, idx as i32 + 1)
+								.with_params(&mut params)
+								.with_direction(descending.as_mut())
+								.timed_out(badge_list.is_timed_out(badge)),
+						)
+						.changed()
+					{
+						to_set_params = Some((badge.to_string(), params));
+					}
 
 					if was_enabled != enabled {
 						to_swap = Some(idx);
 					}
+
+					if let (Some(descending), true) = (descending, descending != was_descending) {
+						to_set_descending = Some((badge.to_string(), descending));
+					}
+				}
+
+				if let Some((badge, params)) = to_set_params {
+					badge_list.set_param_values(badge.as_str(), params);
+				}
+
+				if let Some((badge, descending)) = to_set_descending {
+					badge_list.set_descending(badge.as_str(), descending);
 				}
 
 				if let Some(idx) = to_swap {
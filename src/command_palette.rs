@@ -0,0 +1,236 @@
+use crate::{
+	keybindings::KeyAction,
+	scripts::{
+		decorator::DecoratorList,
+		filter::FilterList,
+		list::{ScriptEditor, ScriptList},
+		sorting::SortingList,
+		standalone_script::StandaloneScriptBadgeType,
+	},
+	settings::Settings,
+	side_panel::SidePanelKind,
+};
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum PaletteCommand {
+	Action(KeyAction),
+	ClearDoneTasks,
+	OpenSidePanel(SidePanelKind),
+	ToggleFilter(String),
+	ToggleSorting(String),
+	ToggleDecorator(String),
+	RunScript(String),
+}
+
+pub enum CommandPaletteResult {
+	Run(PaletteCommand),
+	Close,
+}
+
+pub struct CommandPalette {
+	query: String,
+	cursor: usize,
+	focus_requested: bool,
+	commands: Vec<(String, PaletteCommand)>,
+}
+
+impl CommandPalette {
+	/// Lists every command available right now. Scripts are read from the data directory once, when the palette opens.
+	pub fn new(
+		filter_list: Option<&FilterList>,
+		sorting_list: Option<&SortingList>,
+		decorator_list: Option<&DecoratorList>,
+	) -> Self {
+		let mut commands = Vec::new();
+
+		for action in [
+			KeyAction::NewTask,
+			KeyAction::EditTask,
+			KeyAction::CompleteTask,
+			KeyAction::DeleteTask,
+			KeyAction::SelectAll,
+		] {
+			commands.push((
+				format!("{} ({})", action.name(), Settings::get().keybinding(action)),
+				PaletteCommand::Action(action),
+			));
+		}
+
+		commands.push((
+			String::from("Clear done tasks"),
+			PaletteCommand::ClearDoneTasks,
+		));
+
+		for kind in [
+			SidePanelKind::ScheduledTasks,
+			SidePanelKind::FilterScripts,
+			SidePanelKind::SortingScripts,
+			SidePanelKind::DecoratorScripts,
+			SidePanelKind::Scripts,
+			SidePanelKind::LibraryScripts,
+			SidePanelKind::Packages,
+			SidePanelKind::TagManager,
+			SidePanelKind::CompletedTasks,
+			SidePanelKind::Console,
+			SidePanelKind::Settings,
+		] {
+			commands.push((
+				format!("Open {}", kind.name()),
+				PaletteCommand::OpenSidePanel(kind),
+			));
+		}
+
+		if let Some(filter_list) = filter_list {
+			for (name, enabled) in filter_list.iter_all() {
+				commands.push((
+					format!("{} filter {}", if enabled { "Disable" } else { "Enable" }, name),
+					PaletteCommand::ToggleFilter(name.to_string()),
+				));
+			}
+		}
+
+		if let Some(sorting_list) = sorting_list {
+			for (name, enabled) in sorting_list.iter_all() {
+				commands.push((
+					format!("{} sorting {}", if enabled { "Disable" } else { "Enable" }, name),
+					PaletteCommand::ToggleSorting(name.to_string()),
+				));
+			}
+		}
+
+		if let Some(decorator_list) = decorator_list {
+			for (name, enabled) in decorator_list.iter_all() {
+				commands.push((
+					format!("{} decorator {}", if enabled { "Disable" } else { "Enable" }, name),
+					PaletteCommand::ToggleDecorator(name.to_string()),
+				));
+			}
+		}
+
+		if let Ok((mut script_list, _)) = ScriptList::<StandaloneScriptBadgeType>::new() {
+			for script in script_list.scripts_mut() {
+				let script: &mut ScriptEditor<StandaloneScriptBadgeType> = script;
+				commands.push((
+					format!("Run script {}", script.script.name),
+					PaletteCommand::RunScript(script.script.name.clone()),
+				));
+			}
+		}
+
+		Self {
+			query: String::new(),
+			cursor: 0,
+			focus_requested: false,
+			commands,
+		}
+	}
+
+	pub fn show(&mut self, ctx: &egui::Context) -> Option<CommandPaletteResult> {
+		let mut result = None;
+
+		let matching: Vec<&(String, PaletteCommand)> = self
+			.commands
+			.iter()
+			.filter(|(name, _)| fuzzy_match(self.query.as_str(), name.as_str()))
+			.collect();
+
+		ctx.input_mut(|i| {
+			if i.consume_key(egui::Modifiers::NONE, egui::Key::ArrowDown) {
+				self.cursor += 1;
+			}
+
+			if i.consume_key(egui::Modifiers::NONE, egui::Key::ArrowUp) {
+				self.cursor = self.cursor.saturating_sub(1);
+			}
+
+			if i.consume_key(egui::Modifiers::NONE, egui::Key::Escape) {
+				result = Some(CommandPaletteResult::Close);
+			}
+		});
+
+		self.cursor = self.cursor.min(matching.len().saturating_sub(1));
+
+		egui::Window::new("Command Palette")
+			.anchor(egui::Align2::CENTER_TOP, (0.0, 64.0))
+			.collapsible(false)
+			.resizable(false)
+			.title_bar(false)
+			.fixed_size((480.0, 0.0))
+			.show(ctx, |ui| {
+				let response = ui.add(
+					egui::TextEdit::singleline(&mut self.query)
+						.hint_text("Type a command...")
+						.desired_width(f32::INFINITY),
+				);
+
+				if !self.focus_requested {
+					response.request_focus();
+					self.focus_requested = true;
+				}
+
+				if response.changed() {
+					self.cursor = 0;
+				}
+
+				if response.lost_focus()
+					&& ui.input_mut(|i| i.consume_key(egui::Modifiers::NONE, egui::Key::Enter))
+				{
+					result = Some(match matching.get(self.cursor) {
+						Some((_, command)) => CommandPaletteResult::Run(command.clone()),
+						None => CommandPaletteResult::Close,
+					});
+				}
+
+				ui.separator();
+
+				egui::ScrollArea::vertical()
+					.max_height(320.0)
+					.show(ui, |ui| {
+						ui.with_layout(egui::Layout::top_down_justified(egui::Align::LEFT), |ui| {
+							for (i, (name, command)) in matching.iter().enumerate() {
+								let response = ui.selectable_label(i == self.cursor, name.as_str());
+
+								if i == self.cursor {
+									response.scroll_to_me(None);
+								}
+
+								if response.clicked() {
+									result = Some(CommandPaletteResult::Run(command.clone()));
+								}
+							}
+
+							if matching.is_empty() {
+								ui.weak("No matching command");
+							}
+						});
+					});
+			});
+
+		result
+	}
+}
+
+/// Whether all characters of `query` appear in order in `text`, ignoring case.
+pub(crate) fn fuzzy_match(query: &str, text: &str) -> bool {
+	let mut text_chars = text.chars().flat_map(char::to_lowercase);
+
+	query
+		.chars()
+		.filter(|c| !c.is_whitespace())
+		.flat_map(char::to_lowercase)
+		.all(|q| text_chars.any(|t| t == q))
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	#[test]
+	fn fuzzy() {
+		assert!(fuzzy_match("", "Open Settings"));
+		assert!(fuzzy_match("opset", "Open Settings"));
+		assert!(fuzzy_match("run my", "Run script my_script"));
+		assert!(!fuzzy_match("setop", "Open Settings"));
+		assert!(!fuzzy_match("x", "Open Settings"));
+	}
+}
@@ -5,43 +5,79 @@ pub struct DataDir {
 	scheduled_dir: PathBuf,
 	session_file: PathBuf,
 	settings_file: PathBuf,
+	views_file: PathBuf,
+	packages_file: PathBuf,
+	tag_schemas_file: PathBuf,
 	filter_scripts_dir: PathBuf,
 	sorting_scripts_dir: PathBuf,
+	decorator_scripts_dir: PathBuf,
 	standalone_scripts_dir: PathBuf,
+	library_scripts_dir: PathBuf,
+	script_tests_dir: PathBuf,
+	attachments_dir: PathBuf,
 }
 
 impl DataDir {
 	pub fn new() -> Result<Self, DataDirError> {
-		let dir = directories::ProjectDirs::from("", "", "adhd-materia")
-			.ok_or(DataDirError::NoHomeDirectory)?
-			.data_dir()
-			.to_path_buf();
+		let dir = Self::root()?;
 
 		let tasks_dir = dir.join("tasks");
 		let scheduled_dir = dir.join("scheduled");
 		let session_file = dir.join("session.ron");
 		let settings_file = dir.join("settings.ron");
+		let views_file = dir.join("views.ron");
+		let packages_file = dir.join("packages.ron");
+		let tag_schemas_file = dir.join("tag_schemas.ron");
 		let filter_scripts_dir = dir.join("filter_scripts");
 		let sorting_scripts_dir = dir.join("sorting_scripts");
+		let decorator_scripts_dir = dir.join("decorator_scripts");
 		let standalone_scripts_dir = dir.join("standalone_scripts");
+		let library_scripts_dir = dir.join("library_scripts");
+		let script_tests_dir = dir.join("script_tests");
+		let attachments_dir = dir.join("attachments");
 
 		std::fs::create_dir_all(&tasks_dir)?;
 		std::fs::create_dir_all(&scheduled_dir)?;
 		std::fs::create_dir_all(&filter_scripts_dir)?;
 		std::fs::create_dir_all(&sorting_scripts_dir)?;
+		std::fs::create_dir_all(&decorator_scripts_dir)?;
 		std::fs::create_dir_all(&standalone_scripts_dir)?;
+		std::fs::create_dir_all(&library_scripts_dir)?;
+		std::fs::create_dir_all(&script_tests_dir)?;
+		std::fs::create_dir_all(&attachments_dir)?;
 
 		Ok(Self {
 			tasks_dir,
 			scheduled_dir,
 			session_file,
 			settings_file,
+			views_file,
+			packages_file,
+			tag_schemas_file,
 			filter_scripts_dir,
 			sorting_scripts_dir,
+			decorator_scripts_dir,
 			standalone_scripts_dir,
+			library_scripts_dir,
+			script_tests_dir,
+			attachments_dir,
 		})
 	}
 
+	#[cfg(not(test))]
+	fn root() -> Result<PathBuf, DataDirError> {
+		Ok(directories::ProjectDirs::from("", "", "adhd-materia")
+			.ok_or(DataDirError::NoHomeDirectory)?
+			.data_dir()
+			.to_path_buf())
+	}
+
+	/// Tests get a folder of their own, so that they never touch the data of the user.
+	#[cfg(test)]
+	fn root() -> Result<PathBuf, DataDirError> {
+		Ok(std::env::temp_dir().join(format!("adhd_materia_test_{}", std::process::id())))
+	}
+
 	pub fn tasks(&self) -> &Path {
 		self.tasks_dir.as_path()
 	}
@@ -58,6 +94,18 @@ impl DataDir {
 		self.settings_file.as_path()
 	}
 
+	pub fn views(&self) -> &Path {
+		self.views_file.as_path()
+	}
+
+	pub fn packages(&self) -> &Path {
+		self.packages_file.as_path()
+	}
+
+	pub fn tag_schemas(&self) -> &Path {
+		self.tag_schemas_file.as_path()
+	}
+
 	pub fn filter_scripts(&self) -> &Path {
 		self.filter_scripts_dir.as_path()
 	}
@@ -66,9 +114,26 @@ impl DataDir {
 		self.sorting_scripts_dir.as_path()
 	}
 
+	pub fn decorator_scripts(&self) -> &Path {
+		self.decorator_scripts_dir.as_path()
+	}
+
 	pub fn standalone_scripts(&self) -> &Path {
 		self.standalone_scripts_dir.as_path()
 	}
+
+	pub fn library_scripts(&self) -> &Path {
+		self.library_scripts_dir.as_path()
+	}
+
+	pub fn script_tests(&self) -> &Path {
+		self.script_tests_dir.as_path()
+	}
+
+	/// Files copied into tasks, in a folder per task uuid.
+	pub fn attachments(&self) -> &Path {
+		self.attachments_dir.as_path()
+	}
 }
 
 #[derive(Debug, thiserror::Error)]
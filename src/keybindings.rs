@@ -0,0 +1,177 @@
+use std::str::FromStr;
+
+/// A keyboard shortcut, stored in the settings in its textual form, like `Ctrl+P`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, serde::Serialize, serde::Deserialize)]
+#[serde(try_from = "String", into = "String")]
+pub struct Keybinding(pub egui::KeyboardShortcut);
+
+impl Keybinding {
+	pub const fn new(modifiers: egui::Modifiers, key: egui::Key) -> Self {
+		Self(egui::KeyboardShortcut::new(modifiers, key))
+	}
+
+	pub fn consume(&self, ctx: &egui::Context) -> bool {
+		ctx.input_mut(|i| i.consume_shortcut(&self.0))
+	}
+}
+
+impl std::fmt::Display for Keybinding {
+	fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+		write!(f, "{}", self.0.format(&egui::ModifierNames::NAMES, false))
+	}
+}
+
+impl FromStr for Keybinding {
+	type Err = KeybindingError;
+
+	fn from_str(s: &str) -> Result<Self, Self::Err> {
+		let mut parts: Vec<&str> = s.split('+').map(|part| part.trim()).collect();
+
+		// A binding on the `+` key itself ends with an empty part
+		if parts.len() > 1 && parts.last() == Some(&"") {
+			parts.pop();
+			parts.pop();
+			parts.push("+");
+		}
+
+		let key_name = parts.pop().filter(|k| !k.is_empty()).ok_or(KeybindingError::NoKey)?;
+		let key = egui::Key::from_name(key_name)
+			.or_else(|| egui::Key::from_name(key_name.to_uppercase().as_str()))
+			.ok_or_else(|| KeybindingError::UnknownKey(key_name.to_string()))?;
+
+		let mut modifiers = egui::Modifiers::NONE;
+
+		for modifier in parts {
+			match modifier.to_lowercase().as_str() {
+				"ctrl" | "control" => modifiers.ctrl = true,
+				"shift" => modifiers.shift = true,
+				"alt" | "option" => modifiers.alt = true,
+				"cmd" | "command" => modifiers.command = true,
+				other => return Err(KeybindingError::UnknownModifier(other.to_string())),
+			}
+		}
+
+		Ok(Self::new(modifiers, key))
+	}
+}
+
+impl TryFrom<String> for Keybinding {
+	type Error = KeybindingError;
+
+	fn try_from(value: String) -> Result<Self, Self::Error> {
+		value.parse()
+	}
+}
+
+impl From<Keybinding> for String {
+	fn from(value: Keybinding) -> Self {
+		value.to_string()
+	}
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, serde::Serialize, serde::Deserialize)]
+pub enum KeyAction {
+	NextTask,
+	PreviousTask,
+	SelectTask,
+	EditTask,
+	CompleteTask,
+	DeleteTask,
+	NewTask,
+	SelectAll,
+	CommandPalette,
+}
+
+impl KeyAction {
+	pub const ALL: [KeyAction; 9] = [
+		KeyAction::NextTask,
+		KeyAction::PreviousTask,
+		KeyAction::SelectTask,
+		KeyAction::EditTask,
+		KeyAction::CompleteTask,
+		KeyAction::DeleteTask,
+		KeyAction::NewTask,
+		KeyAction::SelectAll,
+		KeyAction::CommandPalette,
+	];
+
+	pub fn name(&self) -> &'static str {
+		match self {
+			KeyAction::NextTask => "Next task",
+			KeyAction::PreviousTask => "Previous task",
+			KeyAction::SelectTask => "Open/close task",
+			KeyAction::EditTask => "Edit task",
+			KeyAction::CompleteTask => "Complete task",
+			KeyAction::DeleteTask => "Delete task",
+			KeyAction::NewTask => "New task",
+			KeyAction::SelectAll => "Select all visible tasks",
+			KeyAction::CommandPalette => "Command palette",
+		}
+	}
+
+	pub fn default_binding(&self) -> Keybinding {
+		use egui::{Key, Modifiers};
+
+		match self {
+			KeyAction::NextTask => Keybinding::new(Modifiers::NONE, Key::J),
+			KeyAction::PreviousTask => Keybinding::new(Modifiers::NONE, Key::K),
+			KeyAction::SelectTask => Keybinding::new(Modifiers::NONE, Key::Enter),
+			KeyAction::EditTask => Keybinding::new(Modifiers::NONE, Key::E),
+			KeyAction::CompleteTask => Keybinding::new(Modifiers::NONE, Key::X),
+			KeyAction::DeleteTask => Keybinding::new(Modifiers::NONE, Key::D),
+			KeyAction::NewTask => Keybinding::new(Modifiers::NONE, Key::N),
+			KeyAction::SelectAll => Keybinding::new(Modifiers::CTRL, Key::A),
+			KeyAction::CommandPalette => Keybinding::new(Modifiers::CTRL, Key::P),
+		}
+	}
+}
+
+#[derive(Debug, thiserror::Error, PartialEq, Eq)]
+pub enum KeybindingError {
+	#[error("Keybinding has no key")]
+	NoKey,
+
+	#[error("Unknown key `{0}`")]
+	UnknownKey(String),
+
+	#[error("Unknown modifier `{0}`, expected Ctrl, Shift, Alt or Cmd")]
+	UnknownModifier(String),
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	#[test]
+	fn de_ser() {
+		for action in KeyAction::ALL {
+			let binding = action.default_binding();
+			assert_eq!(binding.to_string().parse::<Keybinding>(), Ok(binding));
+		}
+
+		assert_eq!(
+			"ctrl + shift + s".parse::<Keybinding>(),
+			Ok(Keybinding::new(
+				egui::Modifiers::CTRL | egui::Modifiers::SHIFT,
+				egui::Key::S
+			))
+		);
+		assert_eq!(
+			"Ctrl++".parse::<Keybinding>(),
+			Ok(Keybinding::new(egui::Modifiers::CTRL, egui::Key::Plus))
+		);
+	}
+
+	#[test]
+	fn errors() {
+		assert_eq!("".parse::<Keybinding>(), Err(KeybindingError::NoKey));
+		assert_eq!(
+			"Ctrl+Nope".parse::<Keybinding>(),
+			Err(KeybindingError::UnknownKey(String::from("Nope")))
+		);
+		assert_eq!(
+			"Hyper+A".parse::<Keybinding>(),
+			Err(KeybindingError::UnknownModifier(String::from("hyper")))
+		);
+	}
+}
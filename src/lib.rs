@@ -1,7 +1,12 @@
 mod app;
+mod command_palette;
 mod data_dir;
+mod keybindings;
 mod ok_cancel_dialog;
+mod quick_capture;
+mod schedule_dialog;
 mod scripts;
+mod selection;
 mod session;
 mod settings;
 mod side_panel;
@@ -9,10 +14,13 @@ mod startup_script;
 mod tag;
 mod task;
 mod utils;
+mod views;
 
 use std::sync::{LazyLock, Mutex, MutexGuard};
 
 pub use app::AdhdMateriaApp;
+pub use quick_capture::QuickCaptureApp;
+pub use scripts::testing::run_script_tests;
 use data_dir::{DataDir, DataDirError};
 
 static TOASTS: LazyLock<Mutex<egui_notify::Toasts>> =
@@ -5,13 +5,34 @@ fn main() -> eframe::Result {
 
 	env_logger::init();
 
+	let icon = eframe::icon_data::from_png_bytes(&include_bytes!("../assets/icon256.png")[..])
+		.expect("icon256.png image data should be valid");
+
+	if std::env::args().any(|arg| arg == "--test-scripts") {
+		std::process::exit(if adhd_materia::run_script_tests() { 0 } else { 1 });
+	}
+
+	if std::env::args().any(|arg| arg == "--quick-add") {
+		let native_options = eframe::NativeOptions {
+			viewport: egui::ViewportBuilder::default()
+				.with_inner_size((420.0, 72.0))
+				.with_resizable(false)
+				.with_always_on_top()
+				.with_icon(icon),
+			..Default::default()
+		};
+
+		return eframe::run_native(
+			"ADHD Materia Quick Add",
+			native_options,
+			Box::new(|cc| Ok(Box::new(adhd_materia::QuickCaptureApp::new(cc)))),
+		);
+	}
+
 	let native_options = eframe::NativeOptions {
 		viewport: egui::ViewportBuilder::default()
 			.with_inner_size((640.0, 480.0))
-			.with_icon(
-				eframe::icon_data::from_png_bytes(&include_bytes!("../assets/icon256.png")[..])
-					.expect("icon256.png image data should be valid"),
-			),
+			.with_icon(icon),
 		..Default::default()
 	};
 	eframe::run_native(
@@ -0,0 +1,483 @@
+use std::str::FromStr;
+
+use chrono::{Datelike, NaiveDate};
+use eframe::{App, CreationContext};
+
+use crate::{
+	settings::{Settings, DEFAULT_DATE_FORMAT},
+	tag::{Tag, TagError, TagSchemas, TagValue},
+	task::{
+		scheduled::{RepeatMode, ScheduledTask},
+		Task, TaskError, TaskPath,
+	},
+	toast_error,
+	utils::ChronoDelayFormatExt,
+};
+
+/// The result of parsing a quick capture line, like `Call dentist tomorrow !A #health every month`.
+#[derive(Debug, Clone, PartialEq)]
+pub struct QuickCapture {
+	pub name: String,
+	pub due: Option<NaiveDate>,
+	pub tags: Vec<Tag>,
+	pub repeat_mode: Option<RepeatMode>,
+}
+
+impl QuickCapture {
+	pub fn parse(text: &str, today: NaiveDate) -> Self {
+		let words: Vec<&str> = text.split_whitespace().collect();
+
+		let mut name_words = Vec::new();
+		let mut due = None;
+		let mut tags = Vec::new();
+		let mut repeat_mode = None;
+
+		let mut i = 0;
+
+		while i < words.len() {
+			let word = words[i];
+			let lower = word.to_lowercase();
+			let next = words.get(i + 1).map(|w| w.to_lowercase());
+
+			if let Some(priority) = word.strip_prefix('!').filter(|p| !p.is_empty()) {
+				tags.push(Tag::new(
+					String::from("priority"),
+					Some(TagValue::Text(priority.to_string())),
+				));
+				i += 1;
+				continue;
+			}
+
+			if let Some(tag) = word
+				.strip_prefix('#')
+				.and_then(|t| Tag::from_str(t).ok())
+			{
+				tags.push(tag);
+				i += 1;
+				continue;
+			}
+
+			if lower == "every" {
+				if let Some(next) = &next {
+					if let Some(mode) = parse_repeat_unit(next) {
+						repeat_mode = Some(mode);
+						i += 2;
+						continue;
+					}
+
+					if let Some(weekday) = parse_weekday(next, true) {
+						repeat_mode = Some(RepeatMode::Weekly);
+						due.get_or_insert(next_weekday(today, weekday, false));
+						i += 2;
+						continue;
+					}
+				}
+			}
+
+			if lower == "on" || lower == "next" {
+				if let Some(weekday) = next.as_deref().and_then(|next| parse_weekday(next, true)) {
+					due = Some(next_weekday(today, weekday, lower == "next"));
+					i += 2;
+					continue;
+				}
+			}
+
+			if lower == "in" {
+				if let (Some(amount), Some(unit)) = (
+					next.as_deref().and_then(|n| n.parse::<u32>().ok()),
+					words.get(i + 2).map(|w| w.to_lowercase()),
+				) {
+					if let Some(date) = add_duration(today, amount, &unit) {
+						due = Some(date);
+						i += 3;
+						continue;
+					}
+				}
+			}
+
+			if let Some(date) = parse_single_date(&lower, today) {
+				due = Some(date);
+				i += 1;
+				continue;
+			}
+
+			name_words.push(word);
+			i += 1;
+		}
+
+		// A repeat like `daily` only counts at the end of the name, so that "Read the daily news"
+		// keeps its words
+		if repeat_mode.is_none() {
+			if let Some(mode) = name_words
+				.last()
+				.and_then(|word| parse_repeat_adverb(&word.to_lowercase()))
+			{
+				repeat_mode = Some(mode);
+				name_words.pop();
+			}
+		}
+
+		Self {
+			name: name_words.join(" "),
+			due,
+			tags,
+			repeat_mode,
+		}
+	}
+
+	/// The captured tags checked against their schemas, with their default values filled in.
+	pub fn validated_tags(&self) -> Result<Vec<Tag>, TagError> {
+		let schemas = TagSchemas::get();
+
+		self.tags
+			.iter()
+			.map(|tag| {
+				schemas.validate(tag)?;
+
+				let mut tag = tag.clone();
+				schemas.fill_default(&mut tag);
+				Ok(tag)
+			})
+			.collect()
+	}
+
+	fn build_task(&self) -> Result<Task, TagError> {
+		let tags = self.validated_tags()?;
+
+		let mut task = Settings::get().default_task.clone();
+		task.new_uuid();
+
+		if !self.name.is_empty() {
+			task.name = self.name.clone();
+		}
+
+		// A repeating task gets its date from the scheduled task, a fixed due date would be the
+		// same for every spawn
+		if let (Some(due), None) = (self.due, self.repeat_mode) {
+			task.tags
+				.push(Tag::new(String::from("due"), Some(TagValue::Date(due))));
+		}
+
+		task.tags.extend(tags);
+		Ok(task)
+	}
+
+	/// Saves the captured task, into the scheduled tasks if it repeats, or the task list otherwise.
+	pub fn save(&self, today: NaiveDate) -> Result<(), QuickCaptureError> {
+		let task = self.build_task()?;
+
+		match self.repeat_mode {
+			Some(repeat_mode) => task
+				.convert(ScheduledTask {
+					active: true,
+					date: self.due.unwrap_or(today),
+					repeat_mode,
+				})
+				.save(TaskPath::Scheduled)?,
+			None => task.save(TaskPath::Tasks)?,
+		}
+
+		Ok(())
+	}
+}
+
+#[derive(Debug, thiserror::Error)]
+pub enum QuickCaptureError {
+	#[error("Invalid tag: {0}")]
+	Tag(
+		#[from]
+		#[source]
+		TagError,
+	),
+
+	#[error("{0}")]
+	Task(
+		#[from]
+		#[source]
+		TaskError,
+	),
+}
+
+fn parse_repeat_adverb(word: &str) -> Option<RepeatMode> {
+	match word {
+		"daily" => Some(RepeatMode::Daily),
+		"weekly" => Some(RepeatMode::Weekly),
+		"monthly" => Some(RepeatMode::Monthly),
+		"yearly" | "annually" => Some(RepeatMode::Yearly),
+		_ => None,
+	}
+}
+
+fn parse_repeat_unit(word: &str) -> Option<RepeatMode> {
+	match word {
+		"day" => Some(RepeatMode::Daily),
+		"week" => Some(RepeatMode::Weekly),
+		"month" => Some(RepeatMode::Monthly),
+		"year" => Some(RepeatMode::Yearly),
+		_ => None,
+	}
+}
+
+/// Reads a weekday, also from its abbreviation like `mon` when `abbreviated`. Abbreviations are
+/// only read after "on", "next" or "every", as they are common words on their own, like the
+/// `sun` of "Buy sun cream".
+fn parse_weekday(word: &str, abbreviated: bool) -> Option<chrono::Weekday> {
+	match (word, abbreviated) {
+		("monday", _) | ("mon", true) => Some(chrono::Weekday::Mon),
+		("tuesday", _) | ("tue", true) => Some(chrono::Weekday::Tue),
+		("wednesday", _) | ("wed", true) => Some(chrono::Weekday::Wed),
+		("thursday", _) | ("thu", true) => Some(chrono::Weekday::Thu),
+		("friday", _) | ("fri", true) => Some(chrono::Weekday::Fri),
+		("saturday", _) | ("sat", true) => Some(chrono::Weekday::Sat),
+		("sunday", _) | ("sun", true) => Some(chrono::Weekday::Sun),
+		_ => None,
+	}
+}
+
+/// Next date that falls on `weekday`. Today counts, unless `skip_today` is set.
+fn next_weekday(today: NaiveDate, weekday: chrono::Weekday, skip_today: bool) -> NaiveDate {
+	let mut days_ahead = (7 + weekday.num_days_from_monday() as i64
+		- today.weekday().num_days_from_monday() as i64)
+		% 7;
+
+	if days_ahead == 0 && skip_today {
+		days_ahead = 7;
+	}
+
+	today + chrono::Days::new(days_ahead as u64)
+}
+
+fn add_duration(today: NaiveDate, amount: u32, unit: &str) -> Option<NaiveDate> {
+	match unit.trim_end_matches('s') {
+		"day" => today.checked_add_days(chrono::Days::new(amount.into())),
+		"week" => today.checked_add_days(chrono::Days::new(amount as u64 * 7)),
+		"month" => today.checked_add_months(chrono::Months::new(amount)),
+		"year" => today.checked_add_months(chrono::Months::new(amount * 12)),
+		_ => None,
+	}
+}
+
+fn parse_single_date(word: &str, today: NaiveDate) -> Option<NaiveDate> {
+	match word {
+		"today" => Some(today),
+		"tomorrow" => today.succ_opt(),
+		other => parse_weekday(other, false)
+			.map(|weekday| next_weekday(today, weekday, false))
+			.or_else(|| other.parse::<NaiveDate>().ok()),
+	}
+}
+
+/// A tiny window to capture a task in a single line of text.
+pub struct QuickCaptureApp {
+	text: String,
+	focus_requested: bool,
+}
+
+impl QuickCaptureApp {
+	pub fn new(cc: &CreationContext) -> Self {
+		crate::app::setup_style(&cc.egui_ctx);
+
+		Self {
+			text: String::new(),
+			focus_requested: false,
+		}
+	}
+}
+
+impl App for QuickCaptureApp {
+	fn update(&mut self, ctx: &egui::Context, _frame: &mut eframe::Frame) {
+		let today = chrono::Local::now().date_naive();
+		let capture = QuickCapture::parse(self.text.as_str(), today);
+
+		egui::CentralPanel::default().show(ctx, |ui| {
+			let response = ui.add(
+				egui::TextEdit::singleline(&mut self.text)
+					.hint_text("Call dentist tomorrow !A #health every month")
+					.desired_width(f32::INFINITY),
+			);
+
+			if !self.focus_requested {
+				response.request_focus();
+				self.focus_requested = true;
+			}
+
+			ui.add_space(4.0);
+
+			ui.horizontal_wrapped(|ui| {
+				ui.strong(if capture.name.is_empty() {
+					Settings::get().default_task.name.clone()
+				} else {
+					capture.name.clone()
+				});
+
+				if let Some(due) = capture.due {
+					ui.label(format!(
+						"{} {}",
+						if capture.repeat_mode.is_some() { "from" } else { "due" },
+						due.format_or_err(Settings::get().date_format.as_str())
+							.unwrap_or(due.format(DEFAULT_DATE_FORMAT).to_string())
+					));
+				}
+
+				if let Some(repeat_mode) = capture.repeat_mode {
+					ui.label(repeat_mode.to_string());
+				}
+
+				let schemas = TagSchemas::get();
+
+				for tag in capture.tags.iter() {
+					match schemas.validate(tag) {
+						Ok(()) => ui.weak(tag.to_string()),
+						Err(e) => ui
+							.colored_label(ui.visuals().error_fg_color, tag.to_string())
+							.on_hover_text(e.to_string()),
+					};
+				}
+			});
+
+			if ui.input(|i| i.key_pressed(egui::Key::Enter)) && !self.text.trim().is_empty() {
+				match capture.save(today) {
+					Ok(()) => ctx.send_viewport_cmd(egui::ViewportCommand::Close),
+					Err(e) => {
+						toast_error!("Could not save task: {}", e);
+					}
+				}
+			}
+
+			if ui.input(|i| i.key_pressed(egui::Key::Escape)) {
+				ctx.send_viewport_cmd(egui::ViewportCommand::Close);
+			}
+		});
+
+		crate::toasts().show(ctx);
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	macro_rules! date {
+		($y: expr, $m: expr, $d: expr) => {
+			NaiveDate::from_ymd_opt($y, $m, $d).unwrap()
+		};
+	}
+
+	#[test]
+	fn full_example() {
+		// 2024-01-10 is a wednesday
+		let capture = QuickCapture::parse(
+			"Call dentist tomorrow !A #health every month",
+			date!(2024, 1, 10),
+		);
+
+		assert_eq!(capture.name, "Call dentist");
+		assert_eq!(capture.due, Some(date!(2024, 1, 11)));
+		assert_eq!(capture.repeat_mode, Some(RepeatMode::Monthly));
+		assert_eq!(
+			capture.tags,
+			vec![
+				Tag::new(
+					String::from("priority"),
+					Some(TagValue::Text(String::from("A")))
+				),
+				Tag::new(String::from("health"), None),
+			]
+		);
+	}
+
+	#[test]
+	fn dates() {
+		let today = date!(2024, 1, 10);
+
+		assert_eq!(
+			QuickCapture::parse("a friday", today).due,
+			Some(date!(2024, 1, 12))
+		);
+		assert_eq!(
+			QuickCapture::parse("a wednesday", today).due,
+			Some(today)
+		);
+		assert_eq!(
+			QuickCapture::parse("a next wednesday", today).due,
+			Some(date!(2024, 1, 17))
+		);
+		assert_eq!(
+			QuickCapture::parse("a in 2 weeks", today).due,
+			Some(date!(2024, 1, 24))
+		);
+		assert_eq!(
+			QuickCapture::parse("a 2024-03-01", today).due,
+			Some(date!(2024, 3, 1))
+		);
+	}
+
+	#[test]
+	fn repeats_and_plain_words() {
+		let today = date!(2024, 1, 10);
+		let capture = QuickCapture::parse("Water the plants every monday #home(\"garden\")", today);
+
+		assert_eq!(capture.name, "Water the plants");
+		assert_eq!(capture.repeat_mode, Some(RepeatMode::Weekly));
+		assert_eq!(capture.due, Some(date!(2024, 1, 15)));
+		assert_eq!(
+			capture.tags,
+			vec![Tag::new(
+				String::from("home"),
+				Some(TagValue::Text(String::from("garden")))
+			)]
+		);
+
+		let capture = QuickCapture::parse("Read in the morning", today);
+		assert_eq!(capture.name, "Read in the morning");
+		assert_eq!(capture.due, None);
+		assert_eq!(capture.repeat_mode, None);
+
+		let capture = QuickCapture::parse("Stretch daily !B", today);
+		assert_eq!(capture.name, "Stretch");
+		assert_eq!(capture.repeat_mode, Some(RepeatMode::Daily));
+
+		let capture = QuickCapture::parse("Pay rent on fri every month", today);
+		assert_eq!(capture.name, "Pay rent");
+		assert_eq!(capture.due, Some(date!(2024, 1, 12)));
+		assert_eq!(capture.repeat_mode, Some(RepeatMode::Monthly));
+
+		let task = capture.build_task().unwrap();
+		assert!(task.tags.iter().all(|tag| tag.name != "due"));
+	}
+
+	#[test]
+	fn common_words() {
+		let today = date!(2024, 1, 10);
+
+		for text in [
+			"Buy sun cream",
+			"Fix the mon cable",
+			"Book sat nav",
+			"Read the daily news",
+			"Wed invitations",
+		] {
+			let capture = QuickCapture::parse(text, today);
+			assert_eq!(capture.name, text);
+			assert_eq!(capture.due, None);
+			assert_eq!(capture.repeat_mode, None);
+		}
+
+		assert_eq!(
+			QuickCapture::parse("Buy sun cream next sat", today).due,
+			Some(date!(2024, 1, 13))
+		);
+	}
+
+	#[test]
+	fn build_task() {
+		let today = date!(2024, 1, 10);
+		let task = QuickCapture::parse("Call dentist tomorrow !A", today).build_task().unwrap();
+
+		assert!(task
+			.tags
+			.contains(&Tag::new(String::from("due"), Some(TagValue::Date(date!(2024, 1, 11))))));
+
+		assert!(QuickCapture::parse("Call dentist !urgent", today).build_task().is_err());
+	}
+}
@@ -0,0 +1,123 @@
+use chrono::NaiveDate;
+use uuid::Uuid;
+
+use crate::{
+	ok_cancel_dialog::OkCancelResult,
+	task::scheduled::{RepeatMode, ScheduledTask},
+};
+
+/// Asks when normal tasks should come back as scheduled tasks, to snooze them or make them
+/// recurring.
+pub struct ScheduleDialog {
+	title: String,
+	pub tasks: Vec<Uuid>,
+	/// Snoozed tasks come back once, so only the date is asked
+	snooze: bool,
+	date: NaiveDate,
+	repeat_mode: RepeatMode,
+}
+
+impl ScheduleDialog {
+	pub fn snooze(task: Uuid, task_name: &str) -> Self {
+		Self::new(format!("Snooze {}", task_name), vec![task], true, RepeatMode::Never)
+	}
+
+	pub fn make_recurring(task: Uuid, task_name: &str) -> Self {
+		Self::new(
+			format!("Make {} recurring", task_name),
+			vec![task],
+			false,
+			RepeatMode::Daily,
+		)
+	}
+
+	pub fn schedule(tasks: Vec<Uuid>) -> Self {
+		Self::new(
+			format!("Schedule {} tasks", tasks.len()),
+			tasks,
+			false,
+			RepeatMode::Never,
+		)
+	}
+
+	fn new(title: String, tasks: Vec<Uuid>, snooze: bool, repeat_mode: RepeatMode) -> Self {
+		let today = chrono::Local::now().date_naive();
+
+		Self {
+			title,
+			tasks,
+			snooze,
+			date: today.succ_opt().unwrap_or(today),
+			repeat_mode,
+		}
+	}
+
+	pub fn scheduled_task(&self) -> ScheduledTask {
+		ScheduledTask {
+			active: true,
+			date: self.date,
+			repeat_mode: self.repeat_mode,
+		}
+	}
+
+	pub fn show(&mut self, ctx: &egui::Context) -> Option<OkCancelResult> {
+		let mut result = None;
+		let today = chrono::Local::now().date_naive();
+
+		egui::Window::new(self.title.as_str())
+			.anchor(egui::Align2::CENTER_CENTER, (0.0, 0.0))
+			.collapsible(false)
+			.auto_sized()
+			.show(ctx, |ui| {
+				ui.horizontal(|ui| {
+					ui.label(if self.snooze { "Snooze until" } else { "First date" });
+					ui.add(egui_extras::DatePickerButton::new(&mut self.date).id_source("schedule_dialog_date"));
+				});
+
+				if !self.snooze {
+					ui.horizontal(|ui| {
+						ui.label("Repeat mode");
+
+						egui::ComboBox::from_id_source("schedule_dialog_repeat_mode")
+							.selected_text(self.repeat_mode.to_string())
+							.show_ui(ui, |ui| {
+								for repeat_mode in [
+									RepeatMode::Never,
+									RepeatMode::Daily,
+									RepeatMode::Weekly,
+									RepeatMode::Monthly,
+									RepeatMode::Yearly,
+								] {
+									ui.selectable_value(
+										&mut self.repeat_mode,
+										repeat_mode,
+										repeat_mode.to_string(),
+									);
+								}
+							});
+					});
+				}
+
+				// Scheduled tasks spawn at startup from the day after the current session
+				let valid = self.date > today;
+
+				if valid {
+					ui.weak("The tasks leave the list until then, and come back when the app starts.");
+				} else {
+					ui.colored_label(ui.visuals().error_fg_color, "The date must be after today.");
+				}
+
+				ui.horizontal(|ui| {
+					if ui.add_enabled(valid, egui::Button::new("Ok")).clicked() {
+						result = Some(OkCancelResult::Ok);
+					}
+
+					if ui.button("Cancel").clicked() {
+						result = Some(OkCancelResult::Cancel);
+					}
+				});
+			});
+
+		result
+	}
+}
@@ -1,21 +1,32 @@
+use std::collections::HashMap;
+
 use convert_case::Casing;
 
 use crate::{
 	data_dir::{DataDir, DataDirError},
 	session::Session,
 	settings::Settings,
+	tag::TagValue,
 	toast_error,
 };
 
 use super::{
 	list::{ScriptEditor, ScriptEditorStateKind, ScriptList},
+	params::{param_editor, parse_params, resolve_values, ScriptParam},
+	preview::PreviewKind,
 	PocketPyScript,
 };
 
+/// Parameter values set by the user, by script name then parameter name.
+pub type BadgeParams = HashMap<String, HashMap<String, TagValue>>;
+
 pub struct Badge<'list, 'pressed, T> {
 	pressed: &'pressed mut bool,
 	script_name: &'list str,
 	order: i32,
+	params: Option<&'pressed mut Vec<(String, TagValue)>>,
+	descending: Option<&'pressed mut bool>,
+	timed_out: bool,
 	_t: std::marker::PhantomData<T>,
 }
 
@@ -25,9 +36,30 @@ impl<'list, 'pressed, T: BadgeType> Badge<'list, 'pressed, T> {
 			script_name,
 			pressed,
 			order,
+			params: None,
+			descending: None,
+			timed_out: false,
 			_t: std::marker::PhantomData,
 		}
 	}
+
+	/// Shows an inline editor for these parameters while the badge is set. The response is marked changed when one is edited.
+	pub fn with_params(mut self, params: &'pressed mut Vec<(String, TagValue)>) -> Self {
+		self.params = Some(params);
+		self
+	}
+
+	/// Shows a button to switch between ascending and descending order while the badge is set.
+	pub fn with_direction(mut self, descending: Option<&'pressed mut bool>) -> Self {
+		self.descending = descending;
+		self
+	}
+
+	/// Marks the script as disabled for exceeding its time limit.
+	pub fn timed_out(mut self, timed_out: bool) -> Self {
+		self.timed_out = timed_out;
+		self
+	}
 }
 
 impl<T: BadgeType> egui::Widget for Badge<'_, '_, T> {
@@ -62,6 +94,17 @@ impl<T: BadgeType> egui::Widget for Badge<'_, '_, T> {
 				}
 			}
 
+			if self.timed_out {
+				layout_job.append(
+					"⏱ ",
+					0.0,
+					egui::TextFormat::simple(
+						egui::FontId::proportional(16.0),
+						ui.visuals().warn_fg_color,
+					),
+				);
+			}
+
 			layout_job.append(
 				self.script_name.to_case(convert_case::Case::Title).as_str(),
 				0.0,
@@ -70,12 +113,45 @@ impl<T: BadgeType> egui::Widget for Badge<'_, '_, T> {
 					ui.visuals().text_color(),
 				),
 			);
-			let response = ui.button(layout_job);
+			let mut response = ui.button(layout_job);
+
+			if self.timed_out {
+				response = response.on_hover_text(
+					"This script exceeded its time limit, edit the script to re-enable it",
+				);
+			}
 
 			if response.clicked() {
 				*self.pressed = !*self.pressed;
 			}
 
+			if let Some(descending) = self.descending.filter(|_| *self.pressed) {
+				let (arrow, hover) = if *descending {
+					("⬇", "Descending, click for ascending")
+				} else {
+					("⬆", "Ascending, click for descending")
+				};
+
+				if ui.small_button(arrow).on_hover_text(hover).clicked() {
+					*descending = !*descending;
+				}
+			}
+
+			if let Some(params) = self.params.filter(|params| *self.pressed && !params.is_empty()) {
+				let mut changed = false;
+
+				for (name, value) in params.iter_mut() {
+					ui.weak(format!("{}:", name));
+					changed |= param_editor(ui, format!("{}_{}", self.script_name, name).as_str(), value);
+				}
+
+				ui.add_space(8.0);
+
+				if changed {
+					response.mark_changed();
+				}
+			}
+
 			response
 		})
 		.inner
@@ -94,6 +170,29 @@ pub trait BadgeType {
 	fn get_session_badge_list(session: &Session) -> &Vec<String>;
 	fn get_session_badge_list_mut(session: &mut Session) -> &mut Vec<String>;
 
+	fn get_session_badge_params(session: &Session) -> &BadgeParams;
+	fn get_session_badge_params_mut(session: &mut Session) -> &mut BadgeParams;
+
+	/// How scripts of this type can be previewed from their editor, if they can.
+	fn preview_kind() -> Option<PreviewKind> {
+		None
+	}
+
+	/// The badges of this type set to descending order, for types that can be reversed.
+	#[allow(unused_variables)]
+	fn get_session_descending(session: &Session) -> Option<&Vec<String>> {
+		None
+	}
+
+	#[allow(unused_variables)]
+	fn get_session_descending_mut(session: &mut Session) -> Option<&mut Vec<String>> {
+		None
+	}
+
+	/// Called after a script of this type was saved from its editor.
+	#[allow(unused_variables)]
+	fn on_saved(script: &PocketPyScript) {}
+
 	#[allow(unused_variables)]
 	fn draw_ui_titlebar(
 		ui: &mut egui::Ui,
@@ -108,6 +207,8 @@ pub trait BadgeType {
 pub struct BadgeList<T> {
 	unset: Vec<String>,
 	set: Vec<String>,
+	params: HashMap<String, Vec<ScriptParam>>,
+	code_hashes: HashMap<String, u64>,
 	changed: bool,
 	_t: std::marker::PhantomData<T>,
 }
@@ -126,14 +227,25 @@ impl<T: BadgeType> BadgeList<T> {
 			.cloned()
 			.collect();
 
+		let mut params = HashMap::new();
+		let mut code_hashes = HashMap::new();
+
+		let unset = ScriptList::new()?
+			.0
+			.scripts_mut()
+			.map(|script: &mut ScriptEditor<T>| {
+				params.insert(script.script.name.clone(), parse_params(script.script.code.as_str()));
+				code_hashes.insert(script.script.name.clone(), script.script.code_hash());
+				script.script.name.clone()
+			})
+			.filter(|name| !set.contains(name))
+			.collect();
+
 		Ok(Self {
-			unset: ScriptList::new()?
-				.0
-				.scripts_mut()
-				.map(|script: &mut ScriptEditor<T>| script.script.name.clone())
-				.filter(|name| !set.contains(name))
-				.collect(),
+			unset,
 			set,
+			params,
+			code_hashes,
 			changed: true,
 			_t: std::marker::PhantomData,
 		})
@@ -150,6 +262,55 @@ impl<T: BadgeType> BadgeList<T> {
 			.chain(self.unset.iter().map(|s| (s.as_str(), false)))
 	}
 
+	/// Values of the parameters of a script, from the session or their defaults.
+	pub fn param_values(&self, name: &str) -> Vec<(String, TagValue)> {
+		resolve_values(
+			self.params.get(name).map(Vec::as_slice).unwrap_or_default(),
+			T::get_session_badge_params(&Session::current()).get(name),
+		)
+	}
+
+	pub fn set_param_values(&mut self, name: &str, values: Vec<(String, TagValue)>) {
+		self.changed = true;
+
+		if let Err(e) = Session::mutate(|session| {
+			T::get_session_badge_params_mut(session).insert(name.to_string(), values.into_iter().collect());
+		}) {
+			toast_error!("Could not save session: {}", e);
+		}
+	}
+
+	/// Whether the badge sorts in descending order, `None` if badges of this type have no order.
+	pub fn is_descending(&self, name: &str) -> Option<bool> {
+		T::get_session_descending(&Session::current())
+			.map(|descending| descending.iter().any(|badge| badge == name))
+	}
+
+	pub fn set_descending(&mut self, name: &str, descending: bool) {
+		self.changed = true;
+
+		if let Err(e) = Session::mutate(|session| {
+			if let Some(list) = T::get_session_descending_mut(session) {
+				list.retain(|badge| badge != name);
+
+				if descending {
+					list.push(name.to_string());
+				}
+			}
+		}) {
+			toast_error!("Could not save session: {}", e);
+		}
+	}
+
+	/// Whether the script was disabled for exceeding its time limit, and wasn't edited since.
+	pub fn is_timed_out(&self, name: &str) -> bool {
+		let (Ok(path), Some(code_hash)) = (T::get_path(), self.code_hashes.get(name)) else {
+			return false;
+		};
+
+		Session::current().is_timed_out(&path.join(name).with_extension("py"), *code_hash)
+	}
+
 	pub fn check_changed(&mut self) -> bool {
 		if self.changed {
 			self.changed = false;
@@ -186,6 +347,17 @@ impl<T: BadgeType> BadgeList<T> {
 	}
 }
 
+impl<T: BadgeType> BadgeList<T> {
+	/// Enables or disables the badge with this name, if it exists.
+	pub fn toggle(&mut self, name: &str) {
+		let idx = self.iter_all().position(|(badge, _)| badge == name);
+
+		if let Some(idx) = idx {
+			self.swap(idx);
+		}
+	}
+}
+
 enum BadgeListVecIndex {
 	Set(usize),
 	Unset(usize),
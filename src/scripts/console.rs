@@ -0,0 +1,186 @@
+use std::{
+	collections::VecDeque,
+	ffi::{CStr, CString},
+	ptr::null_mut,
+	sync::{Mutex, MutexGuard},
+	time::Duration,
+};
+
+use chrono::{DateTime, Local};
+use pocketpy_sys::*;
+
+use crate::settings::Settings;
+
+use super::{
+	sandbox::{self, Interruption},
+	traceback, PocketPyLockGuard, PocketPyScriptError,
+};
+
+/// Oldest entries are dropped past this count.
+const MAX_ENTRIES: usize = 1000;
+
+const CONSOLE_FILE: &CStr = c"<console>";
+
+/// Name used as the source of lines typed in the console.
+pub const CONSOLE_SOURCE: &str = "console";
+
+static ENTRIES: Mutex<VecDeque<ConsoleEntry>> = Mutex::new(VecDeque::new());
+
+/// Name of the script currently running, used as the source of what it prints.
+static SOURCE: Mutex<String> = Mutex::new(String::new());
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ConsoleEntryKind {
+	/// Printed by a script
+	Output,
+	/// Tracebacks and other script errors
+	Error,
+	/// Typed in the console
+	Input,
+}
+
+#[derive(Debug, Clone)]
+pub struct ConsoleEntry {
+	pub timestamp: DateTime<Local>,
+	pub source: String,
+	pub kind: ConsoleEntryKind,
+	pub text: String,
+}
+
+pub(super) fn set_source(source: &str) {
+	let mut current = SOURCE.lock().unwrap();
+	current.clear();
+	current.push_str(source);
+}
+
+/// Adds text to the console. Output without a trailing newline is continued by the next write
+/// from the same source.
+pub fn write(kind: ConsoleEntryKind, text: &str) {
+	let source = SOURCE.lock().unwrap().clone();
+	let mut entries = ENTRIES.lock().unwrap();
+
+	if let Some(last) = entries.back_mut().filter(|last| {
+		last.kind == kind
+			&& kind == ConsoleEntryKind::Output
+			&& last.source == source
+			&& !last.text.ends_with('\n')
+	}) {
+		last.text.push_str(text);
+		return;
+	}
+
+	if entries.len() >= MAX_ENTRIES {
+		entries.pop_front();
+	}
+
+	entries.push_back(ConsoleEntry {
+		timestamp: Local::now(),
+		source,
+		kind,
+		text: text.to_string(),
+	});
+}
+
+pub fn entries() -> MutexGuard<'static, VecDeque<ConsoleEntry>> {
+	ENTRIES.lock().unwrap()
+}
+
+pub fn clear() {
+	ENTRIES.lock().unwrap().clear();
+}
+
+pub(super) unsafe extern "C" fn print(text: *const std::os::raw::c_char) {
+	let text = CStr::from_ptr(text).to_string_lossy();
+	print!("{}", text);
+	write(ConsoleEntryKind::Output, text.as_ref());
+}
+
+/// Writes the pending exception to the console and returns it, with its lines remapped by `map`.
+pub(super) unsafe fn take_traceback(map: impl Fn(&str, usize) -> Option<usize>) -> String {
+	let traceback = traceback::remap(
+		CStr::from_ptr(py_formatexc()).to_string_lossy().as_ref(),
+		map,
+	);
+	py_clearexc(null_mut());
+
+	write(ConsoleEntryKind::Error, traceback.as_str());
+	traceback
+}
+
+/// Evaluates a line, or a block of lines, typed in the console. Expression results are printed
+/// like in the python REPL.
+pub fn eval(_lock: PocketPyLockGuard<'_>, code: &str) -> Result<(), PocketPyScriptError> {
+	set_source(CONSOLE_SOURCE);
+	write(ConsoleEntryKind::Input, code);
+
+	let instrumented = sandbox::instrument_loops(code);
+	let code = CString::new(instrumented.code.as_str())
+		.map_err(|_| PocketPyScriptError::PocketPyError(String::from("Code contains a 0 byte")))?;
+	let time_limit = Duration::from_millis(Settings::get().script_time_limit_ms);
+
+	sandbox::start(Some(time_limit));
+
+	unsafe {
+		super::library::reload_stale();
+
+		if !py_exec(
+			code.as_ptr(),
+			CONSOLE_FILE.as_ptr(),
+			py_CompileMode_SINGLE_MODE,
+			null_mut(),
+		) {
+			if let Some(interruption) = sandbox::take_interruption() {
+				py_clearexc(null_mut());
+
+				let e = match interruption {
+					Interruption::Timeout(limit) => PocketPyScriptError::Timeout(limit),
+					Interruption::Cancelled => PocketPyScriptError::Cancelled,
+				};
+				write(ConsoleEntryKind::Error, e.to_string().as_str());
+				return Err(e);
+			}
+
+			return Err(PocketPyScriptError::PocketPyError(take_traceback(|file, line| {
+				if file.as_bytes() == CONSOLE_FILE.to_bytes() {
+					instrumented.original_line(line)
+				} else {
+					super::library::original_line(file, line)
+				}
+			})));
+		}
+	}
+
+	Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use crate::scripts::PocketPyLock;
+
+	#[test]
+	fn repl() {
+		let lock = PocketPyLock::new();
+
+		eval(lock.lock(), "materia_console_x = 20").unwrap();
+		eval(lock.lock(), "materia_console_x + 22").unwrap();
+		eval(lock.lock(), "for i in range(2):\n\tprint('line', i, end='')").unwrap();
+		assert!(eval(lock.lock(), "materia_console_y").is_err());
+
+		let entries = entries();
+		let console_texts: Vec<(ConsoleEntryKind, &str)> = entries
+			.iter()
+			.filter(|entry| entry.source == CONSOLE_SOURCE)
+			.map(|entry| (entry.kind, entry.text.as_str()))
+			.collect();
+
+		assert!(console_texts.contains(&(ConsoleEntryKind::Output, "42\n")));
+		assert!(console_texts.contains(&(ConsoleEntryKind::Output, "line 0line 1")));
+		assert!(console_texts
+			.iter()
+			.any(|(kind, text)| *kind == ConsoleEntryKind::Error && text.contains("materia_console_y")));
+
+		drop(entries);
+		drop(lock);
+	}
+}
@@ -0,0 +1,162 @@
+use std::sync::LazyLock;
+
+use crate::{settings::Settings, tag::TagValue, task::Task};
+
+use super::{
+	badge::{BadgeList, BadgeParams, BadgeType},
+	params::param_args,
+	preview::PreviewKind,
+	value::AnyIntoPocketPyValue,
+	PocketPyScript, PocketPyScriptError,
+};
+
+pub static DEFAULT_DECORATOR_SCRIPT: LazyLock<PocketPyScript> = LazyLock::new(|| PocketPyScript {
+	name: "new_decorator".to_string(),
+	code: "return None".to_string(),
+});
+
+pub struct DecoratorBadgeType;
+
+impl BadgeType for DecoratorBadgeType {
+	fn get_path_from_data_dir(
+		data_dir: &'static crate::data_dir::DataDir,
+	) -> &'static std::path::Path {
+		data_dir.decorator_scripts()
+	}
+
+	fn display_order() -> bool {
+		true
+	}
+
+	fn get_session_badge_list(session: &crate::session::Session) -> &Vec<String> {
+		&session.set_decorators
+	}
+
+	fn get_session_badge_list_mut(session: &mut crate::session::Session) -> &mut Vec<String> {
+		&mut session.set_decorators
+	}
+
+	fn get_session_badge_params(session: &crate::session::Session) -> &BadgeParams {
+		&session.decorator_params
+	}
+
+	fn get_session_badge_params_mut(session: &mut crate::session::Session) -> &mut BadgeParams {
+		&mut session.decorator_params
+	}
+
+	fn preview_kind() -> Option<PreviewKind> {
+		Some(PreviewKind::Decorator)
+	}
+}
+
+pub type DecoratorList = BadgeList<DecoratorBadgeType>;
+
+/// A chip shown on a task card, computed by a decorator script.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Decoration {
+	pub text: String,
+	/// Either `#rrggbb`, or a name looked up in the color associations of the settings
+	pub color: Option<String>,
+}
+
+impl Decoration {
+	/// The color of the chip, the color of the script that made it when none was given.
+	fn resolved_color(&self, script_name: &str) -> egui::Color32 {
+		let settings = Settings::get();
+
+		match self.color.as_deref() {
+			Some(color) if color.starts_with('#') => egui::Color32::from_hex(color)
+				.unwrap_or_else(|_| settings.get_color(script_name)),
+			Some(color) => settings.get_color(color),
+			None => settings.get_color(script_name),
+		}
+	}
+
+	pub fn show(&self, ui: &mut egui::Ui, script_name: &str) -> egui::Response {
+		let col = self.resolved_color(script_name);
+
+		egui::Frame::group(ui.style())
+			.inner_margin(egui::Margin::symmetric(6.0, 2.0))
+			.stroke(egui::Stroke::new(
+				ui.style().visuals.window_stroke().width,
+				col,
+			))
+			.fill(col.lerp_to_gamma(ui.style().visuals.window_fill(), 0.8))
+			.show(ui, |ui| {
+				if self.text.is_empty() {
+					ui.colored_label(col, "●");
+				} else {
+					ui.label(self.text.as_str());
+				}
+			})
+			.response
+			.on_hover_text(script_name)
+	}
+}
+
+/// Runs a decorator script on every task, returns the chip of each of them, if any.
+pub fn run_decorator_script(
+	script: &PocketPyScript,
+	tasks: &[&Task],
+	params: Vec<(String, TagValue)>,
+) -> Result<Vec<Option<Decoration>>, PocketPyScriptError> {
+	script.execute_function_for::<Option<Decoration>>(
+		crate::app::script_lock(),
+		script.name.as_str(),
+		[(
+			"task",
+			tasks
+				.iter()
+				.map(|task| Box::new((*task).clone()) as AnyIntoPocketPyValue)
+				.collect(),
+		)]
+		.into_iter()
+		.map(|(arg_name, values)| (String::from(arg_name), values))
+		.chain(param_args(params, tasks.len())),
+	)
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use crate::scripts::PocketPyLock;
+
+	#[test]
+	fn decorations() {
+		let lock = PocketPyLock::new();
+
+		let decorations = PocketPyScript {
+			name: "test_decorations".to_string(),
+			code: "if x == 0:\n\treturn None\nif x == 1:\n\treturn '3d left'\nreturn ('late', '#ff0000')"
+				.to_string(),
+		}
+		.execute_function_for::<Option<Decoration>>(
+			lock.lock(),
+			"decorations",
+			[(
+				"x",
+				vec![
+					Box::new(0i64) as AnyIntoPocketPyValue,
+					Box::new(1i64),
+					Box::new(2i64),
+				],
+			)],
+		)
+		.unwrap();
+
+		assert_eq!(
+			decorations,
+			vec![
+				None,
+				Some(Decoration {
+					text: String::from("3d left"),
+					color: None,
+				}),
+				Some(Decoration {
+					text: String::from("late"),
+					color: Some(String::from("#ff0000")),
+				}),
+			]
+		);
+	}
+}
@@ -0,0 +1,159 @@
+use std::fmt::Display;
+
+use super::sandbox::LineScanner;
+
+/// An error in a script, on a line of the code as the user wrote it.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ScriptDiagnostic {
+	pub line: Option<usize>,
+	pub message: String,
+}
+
+impl std::error::Error for ScriptDiagnostic {}
+
+impl Display for ScriptDiagnostic {
+	fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+		match self.line {
+			Some(line) => write!(f, "Line {}: {}", line, self.message),
+			None => write!(f, "{}", self.message),
+		}
+	}
+}
+
+/// Re-indents a script with one tab per block level.
+///
+/// PocketPy counts a tab as 4 spaces, so scripts mixing tabs with another indentation width
+/// fail in confusing ways. Here a tab counts as one indentation unit of the script instead,
+/// which is the smallest indentation made of spaces, or 4. Lines continuing a statement are
+/// left as they are.
+pub fn normalize_indentation(code: &str) -> Result<String, ScriptDiagnostic> {
+	let lines: Vec<&str> = code.lines().collect();
+
+	// Whether each line starts a statement, and so has a meaningful indentation
+	let mut scanner = LineScanner::default();
+	let starts_statement: Vec<bool> = lines
+		.iter()
+		.map(|line| {
+			let trimmed = line.trim_start_matches([' ', '\t']);
+			let starts = !scanner.in_continuation() && !trimmed.is_empty() && !trimmed.starts_with('#');
+			scanner.scan(line);
+			starts
+		})
+		.collect();
+
+	let indent_of = |line: &str| line.len() - line.trim_start_matches([' ', '\t']).len();
+
+	let unit = lines
+		.iter()
+		.zip(starts_statement.iter())
+		.filter(|(_, starts)| **starts)
+		.map(|(line, _)| &line[..indent_of(line)])
+		.filter(|indent| !indent.is_empty() && !indent.contains('\t'))
+		.map(str::len)
+		.min()
+		.unwrap_or(4);
+
+	let mut levels: Vec<usize> = vec![0];
+	let mut result = Vec::with_capacity(lines.len());
+
+	for (idx, (line, starts)) in lines.iter().zip(starts_statement).enumerate() {
+		if !starts {
+			result.push(line.to_string());
+			continue;
+		}
+
+		let indent = &line[..indent_of(line)];
+		let width: usize = indent
+			.chars()
+			.map(|c| if c == '\t' { unit } else { 1 })
+			.sum();
+
+		if width > *levels.last().expect("never empty") {
+			levels.push(width);
+		} else {
+			while width < *levels.last().expect("never empty") {
+				levels.pop();
+			}
+
+			if width != *levels.last().expect("never empty") {
+				return Err(ScriptDiagnostic {
+					line: Some(idx + 1),
+					message: String::from("The indentation doesn't match any outer block"),
+				});
+			}
+		}
+
+		result.push(format!("{}{}", "\t".repeat(levels.len() - 1), &line[indent.len()..]));
+	}
+
+	Ok(result.join("\n"))
+}
+
+/// Highlights a 1-based line of a laid out text, splitting the sections that cross it.
+pub fn mark_line(job: &mut egui::text::LayoutJob, line: usize, color: egui::Color32) {
+	let start = if line > 1 {
+		match job.text.match_indices('\n').nth(line - 2) {
+			Some((i, _)) => i + 1,
+			None => return,
+		}
+	} else {
+		0
+	};
+	let end = job.text[start..]
+		.find('\n')
+		.map(|i| start + i)
+		.unwrap_or(job.text.len());
+
+	let sections = std::mem::take(&mut job.sections);
+
+	for section in sections {
+		let range = section.byte_range.clone();
+		let parts = [
+			(range.start..range.end.min(start), false),
+			(range.start.max(start)..range.end.min(end), true),
+			(range.start.max(end)..range.end, false),
+		];
+		let mut first = true;
+
+		for (part, marked) in parts {
+			if part.start >= part.end {
+				continue;
+			}
+
+			let mut part_section = section.clone();
+			part_section.byte_range = part;
+
+			if !first {
+				part_section.leading_space = 0.0;
+			}
+
+			if marked {
+				part_section.format.background = color.gamma_multiply(0.2);
+				part_section.format.underline = egui::Stroke::new(1.0, color);
+			}
+
+			job.sections.push(part_section);
+			first = false;
+		}
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	#[test]
+	fn indentation() {
+		assert_eq!(
+			normalize_indentation("if x:\n  y = [1,\n         2]\n\tz = 2\n\n  # comment\n  if y:\n    w\nreturn z"),
+			Ok(String::from("if x:\n\ty = [1,\n         2]\n\tz = 2\n\n  # comment\n\tif y:\n\t\tw\nreturn z"))
+		);
+		assert_eq!(
+			normalize_indentation("for i in x:\n    if i:\n        a\n  b"),
+			Err(ScriptDiagnostic {
+				line: Some(4),
+				message: String::from("The indentation doesn't match any outer block"),
+			})
+		);
+	}
+}
@@ -1,8 +1,15 @@
 use std::sync::LazyLock;
 
+use uuid::Uuid;
+
+use crate::{tag::TagValue, task::Task};
+
 use super::{
-	badge::{BadgeList, BadgeType},
-	PocketPyScript,
+	badge::{BadgeList, BadgeParams, BadgeType},
+	params::param_args,
+	preview::PreviewKind,
+	value::AnyIntoPocketPyValue,
+	PocketPyScript, PocketPyScriptError,
 };
 
 pub static DEFAULT_FILTER_SCRIPT: LazyLock<PocketPyScript> = LazyLock::new(|| PocketPyScript {
@@ -30,6 +37,54 @@ impl BadgeType for FilterBadgeType {
 	fn get_session_badge_list_mut(session: &mut crate::session::Session) -> &mut Vec<String> {
 		&mut session.set_filters
 	}
+
+	fn get_session_badge_params(session: &crate::session::Session) -> &BadgeParams {
+		&session.filter_params
+	}
+
+	fn get_session_badge_params_mut(session: &mut crate::session::Session) -> &mut BadgeParams {
+		&mut session.filter_params
+	}
+
+	fn preview_kind() -> Option<PreviewKind> {
+		Some(PreviewKind::Filter)
+	}
 }
 
 pub type FilterList = BadgeList<FilterBadgeType>;
+
+/// Runs a filter script on every task, returns whether each of them passes.
+pub fn run_filter_script(
+	script: &PocketPyScript,
+	tasks: &[&Task],
+	parent: Option<Uuid>,
+	params: Vec<(String, TagValue)>,
+) -> Result<Vec<bool>, PocketPyScriptError> {
+	script.execute_function_for::<bool>(
+		crate::app::script_lock(),
+		script.name.as_str(),
+		[
+			(
+				"task",
+				tasks
+					.iter()
+					.map(|task| Box::new((*task).clone()) as AnyIntoPocketPyValue)
+					.collect(),
+			),
+			(
+				"parent",
+				match parent {
+					Some(parent) => std::iter::repeat_n(parent, tasks.len())
+						.map(|u| Box::new(u) as AnyIntoPocketPyValue)
+						.collect(),
+					None => std::iter::repeat_n((), tasks.len())
+						.map(|none| Box::new(none) as AnyIntoPocketPyValue)
+						.collect(),
+				},
+			),
+		]
+		.into_iter()
+		.map(|(arg_name, values)| (String::from(arg_name), values))
+		.chain(param_args(params, tasks.len())),
+	)
+}
@@ -0,0 +1,302 @@
+use std::{
+	collections::{HashSet, VecDeque},
+	sync::Mutex,
+};
+
+use uuid::Uuid;
+
+use crate::{
+	handle_toast_error,
+	tag::TagValue,
+	task::{attachment, list::TaskList, Task, TaskPath},
+	toast_error,
+};
+
+use super::{
+	badge::BadgeType, standalone_script::StandaloneScriptBadgeType, value::AnyIntoPocketPyValue,
+	PocketPyLockGuard, PocketPyScript, PocketPyScriptError,
+};
+
+static EVENTS: Mutex<VecDeque<(HookEvent, Option<Task>)>> = Mutex::new(VecDeque::new());
+
+/// Tasks created in the UI that haven't been saved yet, their first save is their creation.
+static UNSAVED_TASKS: Mutex<Option<HashSet<Uuid>>> = Mutex::new(None);
+
+/// Something that happened to the tasks, that standalone scripts can react to.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum HookEvent {
+	TaskCreated,
+	TaskDone,
+	TaskSaved,
+	TaskDeleted,
+	/// The task was moved to the scheduled tasks, instead of being deleted
+	TaskSnoozed,
+	SprintEnded,
+	ScheduledSpawned,
+	Startup,
+}
+
+impl HookEvent {
+	pub fn name(&self) -> &'static str {
+		match self {
+			Self::TaskCreated => "task_created",
+			Self::TaskDone => "task_done",
+			Self::TaskSaved => "task_saved",
+			Self::TaskDeleted => "task_deleted",
+			Self::TaskSnoozed => "task_snoozed",
+			Self::SprintEnded => "sprint_ended",
+			Self::ScheduledSpawned => "scheduled_spawned",
+			Self::Startup => "startup",
+		}
+	}
+
+	fn from_name(name: &str) -> Option<Self> {
+		[
+			Self::TaskCreated,
+			Self::TaskDone,
+			Self::TaskSaved,
+			Self::TaskDeleted,
+			Self::TaskSnoozed,
+			Self::SprintEnded,
+			Self::ScheduledSpawned,
+			Self::Startup,
+		]
+		.into_iter()
+		.find(|event| event.name() == name)
+	}
+}
+
+/// Reads the `# hook event_name` lines at the start of a script.
+///
+/// Only the leading comment block is read, and unknown events are ignored.
+pub fn parse_hooks(code: &str) -> Vec<HookEvent> {
+	code.lines()
+		.map(str::trim)
+		.take_while(|line| line.is_empty() || line.starts_with('#'))
+		.filter_map(|line| {
+			HookEvent::from_name(line.strip_prefix('#')?.trim().strip_prefix("hook ")?.trim())
+		})
+		.collect()
+}
+
+/// Queues an event, the scripts hooked to it run on the next frame.
+pub fn trigger(event: HookEvent, task: Option<Task>) {
+	EVENTS.lock().unwrap().push_back((event, task));
+}
+
+/// Serializes the tests that look at the queued events or run them, as the queue is shared.
+#[cfg(test)]
+pub(crate) static TEST_QUEUE: Mutex<()> = Mutex::new(());
+
+/// The queued events of a task.
+#[cfg(test)]
+pub(crate) fn queued_events(uuid: &Uuid) -> Vec<HookEvent> {
+	EVENTS
+		.lock()
+		.unwrap()
+		.iter()
+		.filter(|(_, task)| task.as_ref().is_some_and(|task| task.get_uuid() == uuid))
+		.map(|(event, _)| *event)
+		.collect()
+}
+
+/// Remembers a task that was just created, so that its first save triggers `task_created`
+/// instead of `task_saved`.
+pub fn mark_unsaved(uuid: Uuid) {
+	UNSAVED_TASKS
+		.lock()
+		.unwrap()
+		.get_or_insert_with(HashSet::new)
+		.insert(uuid);
+}
+
+/// Whether a task was created and not saved yet, forgetting it.
+pub fn take_unsaved(uuid: &Uuid) -> bool {
+	UNSAVED_TASKS
+		.lock()
+		.unwrap()
+		.as_mut()
+		.is_some_and(|unsaved| unsaved.remove(uuid))
+}
+
+fn hooked_scripts() -> Result<Vec<(PocketPyScript, Vec<HookEvent>)>, PocketPyScriptError> {
+	let mut scripts = Vec::new();
+
+	for entry in std::fs::read_dir(StandaloneScriptBadgeType::get_path()?)? {
+		let path = entry?.path();
+
+		if path.extension().is_some_and(|extension| extension == "py") {
+			let script = PocketPyScript::load(path)?;
+			let hooks = parse_hooks(script.code.as_str());
+
+			if !hooks.is_empty() {
+				scripts.push((script, hooks));
+			}
+		}
+	}
+
+	scripts.sort_by(|a, b| a.0.name.cmp(&b.0.name));
+	Ok(scripts)
+}
+
+/// Runs the scripts hooked to the queued events, with the task of the event as `task` and the
+/// name of the event as `event`. A script can return the task to save its changes.
+///
+/// Returns whether a task was changed. Saving these changes doesn't trigger more events. The
+/// copied files of the attachments the script removed are deleted, and only the notes it added
+/// after the existing ones are kept.
+pub fn run_pending(task_list: &mut TaskList) -> bool {
+	run_pending_with(task_list, crate::app::script_lock)
+}
+
+/// `run_pending`, locking the interpreter with `lock` for each script.
+fn run_pending_with<'lock>(task_list: &mut TaskList, lock: impl Fn() -> PocketPyLockGuard<'lock>) -> bool {
+	let events: Vec<(HookEvent, Option<Task>)> = EVENTS.lock().unwrap().drain(..).collect();
+
+	if events.is_empty() {
+		return false;
+	}
+
+	let scripts = match hooked_scripts() {
+		Ok(scripts) => scripts,
+		Err(e) => {
+			toast_error!("Could not load hooked scripts: {}", e);
+			return false;
+		}
+	};
+
+	let mut changed = false;
+
+	for (event, task) in events {
+		for (script, _) in scripts.iter().filter(|(_, hooks)| hooks.contains(&event)) {
+			let args: [(&'static str, AnyIntoPocketPyValue); 2] = [
+				("task", Box::new(task.clone())),
+				("event", Box::new(TagValue::Text(event.name().to_string()))),
+			];
+
+			match script.execute_function::<Option<Task>>(lock(), "hook", args) {
+				Ok(Some(returned)) => {
+					let Some(task) = task
+						.as_ref()
+						.and_then(|task| task_list.get_mut(task.get_uuid()))
+					else {
+						continue;
+					};
+
+					task.name = returned.name;
+					task.description = returned.description;
+					task.tags = returned.tags;
+					task.attachments = returned.attachments;
+
+					if !task.append_notes(&returned.notes) {
+						toast_error!(
+							"Script {} changed the notes of {}, only new notes can be added",
+							script.name,
+							task.name
+						);
+					}
+
+					match task.save(TaskPath::Tasks) {
+						Ok(()) => handle_toast_error!(
+							"Could not remove attachments: {}",
+							attachment::remove_unlisted_files(task.get_uuid(), &task.attachments)
+						),
+						Err(e) => {
+							toast_error!("Could not save task changed by a hook: {}", e);
+						}
+					}
+
+					changed = true;
+				}
+				Ok(None) => {}
+				Err(e) => {
+					toast_error!("Error in script {} on {}:\n{}", script.name, event.name(), e);
+				}
+			}
+		}
+	}
+
+	changed
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use crate::task::{attachment::Attachment, Note};
+
+	#[test]
+	fn hooks() {
+		assert_eq!(
+			parse_hooks("# hook task_done\n#hook  task_created\n# hook nothing\n# param x: int\n\n# hook startup\nreturn\n# hook task_saved"),
+			vec![HookEvent::TaskDone, HookEvent::TaskCreated, HookEvent::Startup]
+		);
+	}
+
+	#[test]
+	fn returned_task() {
+		let _queue = TEST_QUEUE.lock().unwrap_or_else(std::sync::PoisonError::into_inner);
+		let lock = super::super::PocketPyLock::new();
+
+		let script_path = StandaloneScriptBadgeType::get_path().unwrap().join("test_returned_task.py");
+		std::fs::write(
+			&script_path,
+			"# hook task_saved\nif task is None or not task.name.startswith('Hooked'):\n\treturn None\nif task.name == 'Hooked rewrite':\n\ttask.name = 'Hooked rewritten'\n\ttask.notes[0] = (task.notes[0][0], 'Never called')\n\treturn task\ntask.name = task.name + ' ' + event\ntask.attachments = [task.attachments[0], 'https://example.com']\ntask.notes.append((task.notes[0][0], task.notes[0][1] + ' again'))\nreturn task",
+		)
+		.unwrap();
+
+		let (mut task_list, _) = TaskList::new(TaskPath::Tasks).unwrap();
+		let mut task = Task::default();
+		task.name = String::from("Hooked task");
+		task.add_note("Called the vendor");
+		let uuid = *task.get_uuid();
+
+		let mut rewritten = Task::default();
+		rewritten.name = String::from("Hooked rewrite");
+		rewritten.add_note("Called the vendor");
+		let rewritten_uuid = *rewritten.get_uuid();
+
+		let source = std::env::temp_dir().join(format!("adhd_materia_hook_{}", std::process::id()));
+		std::fs::create_dir_all(&source).unwrap();
+
+		for name in ["kept.txt", "dropped.txt"] {
+			std::fs::write(source.join(name), name).unwrap();
+			task.attachments.push(attachment::copy_into_task_folder(&source.join(name), &uuid).unwrap());
+		}
+
+		std::fs::remove_dir_all(&source).unwrap();
+		let (kept, dropped) = (task.attachments[0].clone(), task.attachments[1].clone());
+
+		let notes = task.notes.clone();
+		task_list.add_task(task).unwrap();
+		task_list.add_task(rewritten).unwrap();
+
+		for uuid in [uuid, rewritten_uuid] {
+			trigger(HookEvent::TaskSaved, Some(task_list.get(&uuid).unwrap().hook_snapshot()));
+		}
+
+		assert!(run_pending_with(&mut task_list, || lock.lock()));
+		std::fs::remove_file(&script_path).unwrap();
+
+		let load = |uuid: &Uuid| -> Task { Task::load_from_path(crate::data_dir().unwrap().tasks().join(uuid.to_string())).unwrap() };
+
+		let saved = load(&uuid);
+		assert_eq!(saved.name, "Hooked task task_saved");
+		assert_eq!(saved.attachments, vec![kept.clone(), Attachment::Url(String::from("https://example.com"))]);
+		assert_eq!(
+			saved.notes,
+			vec![notes[0].clone(), Note { time: notes[0].time, text: String::from("Called the vendor again") }]
+		);
+
+		// The other changes are saved, the edited log is not
+		let saved = load(&rewritten_uuid);
+		assert_eq!(saved.name, "Hooked rewritten");
+		assert_eq!(saved.notes, notes);
+
+		let (Attachment::File(kept), Attachment::File(dropped)) = (kept, dropped) else {
+			panic!("copied files are file attachments");
+		};
+		assert!(kept.is_file() && !dropped.exists());
+
+		task_list.delete_tasks(&[uuid, rewritten_uuid]).unwrap();
+	}
+}
@@ -0,0 +1,241 @@
+use std::{
+	collections::{HashMap, HashSet},
+	ffi::{CStr, CString},
+	sync::{LazyLock, Mutex},
+};
+
+use pocketpy_sys::*;
+
+use super::{
+	badge::{BadgeParams, BadgeType},
+	console,
+	sandbox::{self, InstrumentedCode},
+	PocketPyScript,
+};
+
+pub static DEFAULT_LIBRARY_SCRIPT: LazyLock<PocketPyScript> = LazyLock::new(|| PocketPyScript {
+	name: "new_module".to_string(),
+	code: "def helper():\n\treturn None".to_string(),
+});
+
+/// Globals of the scripts that library modules can use without importing them.
+const SHARED_GLOBALS: [&CStr; 8] = [
+	c"Task",
+	c"Tag",
+	c"Date",
+	c"DateTime",
+	c"Duration",
+	c"TaskRef",
+	c"today",
+	c"__materia_tick",
+];
+
+/// Instrumented code of the imported library modules, by file name, to map the lines of their
+/// tracebacks back.
+static IMPORTED: Mutex<Option<HashMap<String, InstrumentedCode>>> = Mutex::new(None);
+
+/// Library modules edited since they were imported, reloaded before the next script runs.
+static STALE: Mutex<Option<HashSet<String>>> = Mutex::new(None);
+
+extern "C" {
+	fn malloc(size: usize) -> *mut std::os::raw::c_void;
+}
+
+pub struct LibraryBadgeType;
+
+impl BadgeType for LibraryBadgeType {
+	fn get_path_from_data_dir(
+		data_dir: &'static crate::data_dir::DataDir,
+	) -> &'static std::path::Path {
+		data_dir.library_scripts()
+	}
+
+	fn display_order() -> bool {
+		unimplemented!("Library scripts don't have badges")
+	}
+
+	fn get_session_badge_list(_session: &crate::session::Session) -> &Vec<String> {
+		unimplemented!("Library scripts don't have badges")
+	}
+
+	fn get_session_badge_list_mut(_session: &mut crate::session::Session) -> &mut Vec<String> {
+		unimplemented!("Library scripts don't have badges")
+	}
+
+	fn get_session_badge_params(_session: &crate::session::Session) -> &BadgeParams {
+		unimplemented!("Library scripts don't have badges")
+	}
+
+	fn get_session_badge_params_mut(_session: &mut crate::session::Session) -> &mut BadgeParams {
+		unimplemented!("Library scripts don't have badges")
+	}
+
+	fn on_saved(script: &PocketPyScript) {
+		invalidate(script.name.as_str());
+	}
+}
+
+/// Marks a library module as edited, so that it is loaded again before it is used.
+pub fn invalidate(name: &str) {
+	STALE
+		.lock()
+		.unwrap()
+		.get_or_insert_with(HashSet::new)
+		.insert(name.to_string());
+}
+
+/// Makes the globals of the scripts visible from library modules, and loads the modules
+/// from the library scripts directory on `import`.
+pub(super) unsafe fn initialize() {
+	let builtins = py_getmodule(c"builtins".as_ptr());
+
+	for name in SHARED_GLOBALS {
+		py_setdict(builtins, py_name(name.as_ptr()), py_getglobal(py_name(name.as_ptr())));
+	}
+
+	(*py_callbacks()).importfile = Some(import_file);
+	*IMPORTED.lock().unwrap() = None;
+}
+
+fn load_instrumented(file_name: &str) -> Option<String> {
+	let path = crate::data_dir().ok()?.library_scripts().join(file_name);
+	let code = std::fs::read_to_string(path).ok()?;
+	let instrumented = sandbox::instrument_loops(code.as_str());
+	let instrumented_code = instrumented.code.clone();
+
+	IMPORTED
+		.lock()
+		.unwrap()
+		.get_or_insert_with(HashMap::new)
+		.insert(file_name.to_string(), instrumented);
+
+	Some(instrumented_code)
+}
+
+/// Called by PocketPy with the file of a module it could not find, like `name.py`. Returns the
+/// source allocated with `malloc`, which PocketPy frees, or null.
+unsafe extern "C" fn import_file(path: *const std::os::raw::c_char) -> *mut std::os::raw::c_char {
+	let file_name = CStr::from_ptr(path).to_string_lossy();
+
+	let Some(code) = load_instrumented(file_name.as_ref()) else {
+		return std::ptr::null_mut();
+	};
+
+	let Ok(code) = CString::new(code) else {
+		return std::ptr::null_mut();
+	};
+
+	let bytes = code.as_bytes_with_nul();
+	let buffer = malloc(bytes.len()).cast::<u8>();
+
+	if buffer.is_null() {
+		return std::ptr::null_mut();
+	}
+
+	std::ptr::copy_nonoverlapping(bytes.as_ptr(), buffer, bytes.len());
+	buffer.cast()
+}
+
+/// Runs again the edited library modules that were already imported. Modules can't be removed
+/// from PocketPy, so the new code runs in the same module, and the modules whose script was
+/// removed are emptied.
+pub(super) unsafe fn reload_stale() {
+	let Some(stale) = STALE.lock().unwrap().take() else {
+		return;
+	};
+
+	for name in stale {
+		let Ok(name_c) = CString::new(name.as_str()) else {
+			continue;
+		};
+		let module = py_getmodule(name_c.as_ptr());
+
+		if module.is_null() {
+			continue;
+		}
+
+		let file_name = format!("{}.py", name);
+
+		let Some(code) = load_instrumented(file_name.as_str()) else {
+			clear_module(module);
+			continue;
+		};
+		let Ok(code) = CString::new(code) else {
+			continue;
+		};
+		let file_name_c = CString::new(file_name).expect("made from a name without 0 bytes");
+
+		if !py_exec(
+			code.as_ptr(),
+			file_name_c.as_ptr(),
+			py_CompileMode_EXEC_MODE,
+			module,
+		) {
+			console::take_traceback(original_line);
+		}
+	}
+}
+
+unsafe extern "C" fn push_name(name: py_Name, _value: py_Ref, names: *mut std::os::raw::c_void) -> bool {
+	(*names.cast::<Vec<py_Name>>()).push(name);
+	true
+}
+
+/// Removes the names defined by a module, keeping the ones PocketPy sets like `__name__`.
+unsafe fn clear_module(module: py_Ref) {
+	let mut names: Vec<py_Name> = Vec::new();
+
+	if !py_applydict(module, Some(push_name), (&mut names as *mut Vec<py_Name>).cast()) {
+		console::take_traceback(|_, _| None);
+		return;
+	}
+
+	for name in names {
+		if !CStr::from_ptr(py_name2str(name)).to_bytes().starts_with(b"__") {
+			py_deldict(module, name);
+		}
+	}
+}
+
+/// Maps a line of an imported library module to the line written in its file.
+pub(super) fn original_line(file_name: &str, line: usize) -> Option<usize> {
+	IMPORTED
+		.lock()
+		.unwrap()
+		.as_ref()?
+		.get(file_name)?
+		.original_line(line)
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use crate::{
+		scripts::{value::AnyIntoPocketPyValue, PocketPyLock},
+		tag::TagValue,
+	};
+
+	#[test]
+	fn removed_module() {
+		let lock = PocketPyLock::new();
+		let path = crate::data_dir().unwrap().library_scripts().join("test_removed.py");
+		std::fs::write(&path, "def answer():\n\treturn 42").unwrap();
+
+		let defines_answer = || {
+			let args: [(&'static str, AnyIntoPocketPyValue); 0] = [];
+
+			PocketPyScript {
+				name: "test_import".to_string(),
+				code: "import test_removed\nreturn hasattr(test_removed, 'answer')".to_string(),
+			}
+			.execute_function::<TagValue>(lock.lock(), "test", args)
+			.unwrap()
+		};
+
+		assert_eq!(defines_answer(), TagValue::Bool(true));
+
+		std::fs::remove_file(&path).unwrap();
+		invalidate("test_removed");
+		assert_eq!(defines_answer(), TagValue::Bool(false));
+	}
+}
@@ -2,7 +2,9 @@ use std::{collections::HashMap, time::Duration};
 
 use crate::data_dir::DataDirError;
 
-use super::{badge::BadgeType, ui::ScriptWidget, PocketPyScript, PocketPyScriptError};
+use super::{
+	badge::BadgeType, diagnostics::ScriptDiagnostic, preview::ScriptPreview, ui::ScriptWidget, PocketPyScript, PocketPyScriptError,
+};
 
 pub struct ScriptList<T> {
 	scripts: HashMap<String, ScriptEditor<T>>,
@@ -29,6 +31,8 @@ impl<T: BadgeType> ScriptList<T> {
 												script: script,
 												state: ScriptEditorState::DisplayMode,
 												deletion_state: ScriptEditorDeletionState::None,
+												preview: None,
+												diagnostic: None,
 												_t: std::marker::PhantomData,
 											},
 										);
@@ -57,6 +61,8 @@ impl<T: BadgeType> ScriptList<T> {
 				state: ScriptEditorState::EditMode(script.clone()),
 				script,
 				deletion_state: ScriptEditorDeletionState::None,
+				preview: None,
+				diagnostic: None,
 				_t: std::marker::PhantomData,
 			},
 		);
@@ -121,6 +127,9 @@ pub struct ScriptEditor<T> {
 	pub script: PocketPyScript,
 	pub state: ScriptEditorState,
 	pub deletion_state: ScriptEditorDeletionState,
+	pub preview: Option<ScriptPreview>,
+	/// Error to mark in the editor, from saving or previewing the script
+	pub diagnostic: Option<ScriptDiagnostic>,
 	_t: std::marker::PhantomData<T>,
 }
 
@@ -155,6 +164,16 @@ impl<T: BadgeType> ScriptEditor<T> {
 	pub fn display(&mut self) -> Result<(), PocketPyScriptError> {
 		let path = T::get_path()?;
 
+		if let ScriptEditorState::EditMode(script) = &self.state {
+			// Syntax errors are caught before the script can be enabled
+			if let Err(diagnostic) = script.check(crate::app::try_script_lock()) {
+				self.diagnostic = Some(diagnostic.clone());
+				return Err(diagnostic.into());
+			}
+
+			self.diagnostic = None;
+		}
+
 		if let Some(script) = self.state.take_edit() {
 			if script.name != self.script.name {
 				self.script.delete(&path)?;
@@ -163,6 +182,7 @@ impl<T: BadgeType> ScriptEditor<T> {
 			self.script = script;
 			self.script.save(path)?;
 			self.state = ScriptEditorState::DisplayMode;
+			T::on_saved(&self.script);
 		}
 
 		Ok(())
@@ -0,0 +1,238 @@
+use std::{collections::HashMap, ffi::CString, sync::Mutex};
+
+use pocketpy_sys::*;
+
+use crate::{
+	settings::{Settings, DEFAULT_DATE_FORMAT},
+	tag::TagValue,
+	task::{list::TaskList, Task, TaskPath},
+	utils::ChronoDelayFormatExt,
+};
+
+use super::{console, py_bindings::naive_date_from_py_date, value::IntoPocketPyValue};
+
+const MODULE_SOURCE: &str = include_str!("../../assets/materia.py");
+
+/// Globals of the scripts that the python part of the module uses.
+const SHARED_GLOBALS: [&std::ffi::CStr; 7] = [
+	c"Task",
+	c"Tag",
+	c"Date",
+	c"DateTime",
+	c"Duration",
+	c"TaskRef",
+	c"today",
+];
+
+/// The saved tasks, read once per invocation of a script rather than once per call, as filters
+/// and sortings run for every task.
+static TASKS: Mutex<Option<Vec<Task>>> = Mutex::new(None);
+
+/// Forgets the tasks read by the previous invocation, they are read again when a script asks for
+/// them.
+pub(super) fn start_invocation() {
+	*TASKS.lock().expect("task cache should be lockable") = None;
+}
+
+/// Creates the `materia` module. Its python part also adds arithmetic and comparisons to `Date`,
+/// so that they work without importing it.
+pub(super) unsafe fn initialize_module() {
+	let module = py_newmodule(c"materia".as_ptr());
+
+	for name in SHARED_GLOBALS {
+		py_setdict(module, py_name(name.as_ptr()), py_getglobal(py_name(name.as_ptr())));
+	}
+
+	py_bindfunc(module, c"_tasks".as_ptr(), Some(tasks));
+	py_bindfunc(module, c"_settings".as_ptr(), Some(settings));
+	py_bindfunc(module, c"format_date".as_ptr(), Some(format_date));
+	py_bindfunc(module, c"color".as_ptr(), Some(color));
+
+	let source = CString::new(MODULE_SOURCE).expect("module source has no 0 byte");
+
+	if !py_exec(
+		source.as_ptr(),
+		c"materia".as_ptr(),
+		py_CompileMode_EXEC_MODE,
+		module,
+	) {
+		console::take_traceback(|_, _| None);
+	}
+}
+
+unsafe fn exception(message: &str) -> bool {
+	let message = CString::new(message).unwrap_or_default();
+	py_newnone(py_retval());
+	py_exception(
+		py_totype(py_getbuiltin(py_name(c"Exception".as_ptr()))),
+		message.as_ptr(),
+	)
+}
+
+/// The saved tasks, read from the data directory on the first call of an invocation.
+unsafe extern "C" fn tasks(argc: std::os::raw::c_int, _argv: *mut py_TValue) -> bool {
+	if argc != 0 {
+		return exception("Expected 0 argument");
+	}
+
+	let mut cache = TASKS.lock().expect("task cache should be lockable");
+
+	let tasks = match cache.as_ref() {
+		Some(tasks) => tasks,
+		None => match TaskList::new(TaskPath::Tasks) {
+			Ok((task_list, _)) => cache.insert(task_list.tasks().cloned().collect()),
+			Err(e) => return exception(format!("Could not load tasks: {}", e).as_str()),
+		},
+	};
+
+	py_newlist(py_retval());
+
+	for task in tasks {
+		py_pushnil();
+		task.into_pocketpy_value(py_peek(-1));
+		py_list_append(py_retval(), py_peek(-1));
+		py_pop();
+	}
+
+	true
+}
+
+fn hex_color(color: egui::Color32) -> String {
+	format!("#{:02x}{:02x}{:02x}", color.r(), color.g(), color.b())
+}
+
+unsafe extern "C" fn settings(argc: std::os::raw::c_int, _argv: *mut py_TValue) -> bool {
+	if argc != 0 {
+		return exception("Expected 0 argument");
+	}
+
+	let settings = Settings::get();
+	let session = crate::session::Session::current();
+
+	let mut sprint = HashMap::new();
+	sprint.insert(
+		String::from("frequency"),
+		TagValue::Text(settings.sprint_end.to_string()),
+	);
+	sprint.insert(
+		String::from("reference"),
+		TagValue::Date(settings.sprint_end_reference),
+	);
+	sprint.insert(
+		String::from("done"),
+		TagValue::Int(session.current_done_counter as i64),
+	);
+	sprint.insert(
+		String::from("past_done"),
+		TagValue::List(
+			session
+				.past_done_counters
+				.iter()
+				.map(|count| TagValue::Int(*count as i64))
+				.collect(),
+		),
+	);
+
+	let mut dict = HashMap::new();
+	dict.insert(
+		String::from("date_format"),
+		TagValue::Text(settings.date_format.clone()),
+	);
+	dict.insert(
+		String::from("colors"),
+		TagValue::Dictionary(
+			settings
+				.color_associations
+				.iter()
+				.map(|(name, color)| (name.clone(), TagValue::Text(hex_color(*color))))
+				.collect(),
+		),
+	);
+	dict.insert(String::from("sprint"), TagValue::Dictionary(sprint));
+	dict.insert(
+		String::from("script_time_limit_ms"),
+		TagValue::Int(settings.script_time_limit_ms as i64),
+	);
+
+	if let Some(scheduled_task_tag) = &settings.scheduled_task_tag {
+		dict.insert(
+			String::from("scheduled_task_tag"),
+			TagValue::Text(scheduled_task_tag.clone()),
+		);
+	}
+
+	drop(settings);
+	drop(session);
+
+	TagValue::Dictionary(dict).into_pocketpy_value(py_retval());
+	true
+}
+
+/// Formats a date with the format from the settings.
+unsafe extern "C" fn format_date(argc: std::os::raw::c_int, argv: *mut py_TValue) -> bool {
+	if argc != 1 || !py_istype(argv, py_totype(py_getglobal(py_name(c"Date".as_ptr())))) {
+		return exception("Expected a Date argument");
+	}
+
+	let date = match naive_date_from_py_date(argv) {
+		Ok(date) => date,
+		Err(e) => return exception(e.to_string().as_str()),
+	};
+
+	let text = date
+		.format_or_err(Settings::get().date_format.as_str())
+		.unwrap_or(date.format(DEFAULT_DATE_FORMAT).to_string());
+
+	TagValue::Text(text).into_pocketpy_value(py_retval());
+	true
+}
+
+/// The color associated to a name in the settings, like tags and badges are colored.
+unsafe extern "C" fn color(argc: std::os::raw::c_int, argv: *mut py_TValue) -> bool {
+	if argc != 1 || !py_istype(argv, py_totype(py_getbuiltin(py_name(c"str".as_ptr())))) {
+		return exception("Expected string argument");
+	}
+
+	let name = std::ffi::CStr::from_ptr(py_tostr(argv)).to_string_lossy();
+	let color = Settings::get().get_color(name.as_ref());
+
+	TagValue::Text(hex_color(color)).into_pocketpy_value(py_retval());
+	true
+}
+
+#[cfg(test)]
+mod tests {
+	use crate::{
+		scripts::{value::AnyIntoPocketPyValue, PocketPyLock, PocketPyScript},
+		tag::TagValue,
+	};
+
+	#[test]
+	fn dates() {
+		let lock = PocketPyLock::new();
+
+		for check in [
+			"d + 1 == Date(2024, 2, 1)",
+			"Date(2024, 3, 1) - d == 30",
+			"str(d - 31) == '2023-12-31'",
+			"d.weekday() == 2 and d.weekday_name() == 'wednesday'",
+			"materia.from_ordinal(0) == Date(1970, 1, 1)",
+			"str(materia.add_months(d, 1)) == '2024-2-29'",
+			"materia.parse_date('2023-02-29') is None",
+			"materia.parse_date('2023-02-28') == Date(2023, 2, 28)",
+			"d < Date(2024, 2, 1) and not d > Date(2024, 2, 1) and d >= d",
+			"materia.tag_value(materia.set_tag(Task('a'), 'x', 3), 'x') == 3",
+			"materia.DateTime is DateTime and materia.Duration is Duration",
+		] {
+			let args: [(&'static str, AnyIntoPocketPyValue); 0] = [];
+
+			let result = PocketPyScript {
+				name: "test_materia".to_string(),
+				code: format!("import materia\nd = Date(2024, 1, 31)\nreturn {}", check),
+			}
+			.execute_function::<TagValue>(lock.lock(), "test", args);
+
+			assert_eq!(result.unwrap(), TagValue::Bool(true), "{}", check);
+		}
+	}
+}
@@ -2,27 +2,42 @@ use std::{
 	ffi::{CStr, CString},
 	ptr::null_mut,
 	sync::{Mutex, MutexGuard},
+	time::Duration,
 };
 
-use badge::BadgeType;
 use log::info;
 use pocketpy_sys::*;
 
-use standalone_script::StandaloneScriptBadgeType;
+use diagnostics::ScriptDiagnostic;
+use sandbox::Interruption;
 use value::{AnyIntoPocketPyValue, IntoPocketPyValue};
 
-use crate::data_dir::DataDirError;
+use crate::{data_dir::DataDirError, settings::Settings};
 
 pub mod badge;
+pub mod console;
+pub mod decorator;
+pub mod diagnostics;
 pub mod filter;
+pub mod hooks;
+pub mod library;
 pub mod list;
+mod materia;
+pub mod package;
+pub mod params;
+pub mod preview;
 mod py_bindings;
+pub mod runner;
+mod sandbox;
 pub mod sorting;
 pub mod standalone_script;
+pub mod testing;
+pub mod traceback;
 pub mod ui;
 pub mod value;
 
 pub use py_bindings::new_tasks_waitlist_next;
+pub use sandbox::cancel as cancel_running_script;
 
 static POCKETPY_LOCK: Mutex<Mutex<()>> = Mutex::new(Mutex::new(()));
 
@@ -49,6 +64,7 @@ impl PocketPyLock<'_> {
 
 		unsafe {
 			py_initialize();
+			(*py_callbacks()).print = Some(console::print);
 			py_bindings::initialize_bindings();
 		}
 
@@ -64,6 +80,11 @@ impl<'lock> PocketPyLock<'lock> {
 	pub fn lock(&'lock self) -> PocketPyLockGuard<'lock> {
 		PocketPyLockGuard(self.0.lock().unwrap())
 	}
+
+	/// Locks the interpreter if no script is using it.
+	pub fn try_lock(&'lock self) -> Option<PocketPyLockGuard<'lock>> {
+		self.0.try_lock().ok().map(PocketPyLockGuard)
+	}
 }
 
 impl Drop for PocketPyLock<'_> {
@@ -111,42 +132,145 @@ impl PocketPyScript {
 		Ok(())
 	}
 
+	/// Hash of the code, to notice when a script was edited.
+	///
+	/// It is saved in the session, so it uses 64-bit FNV-1a rather than the hasher of the standard
+	/// library, which may change between Rust releases.
+	pub fn code_hash(&self) -> u64 {
+		self.code.bytes().fold(0xcbf2_9ce4_8422_2325, |hash, byte| {
+			(hash ^ byte as u64).wrapping_mul(0x0000_0100_0000_01b3)
+		})
+	}
+
+	/// Wraps the script in a function definition, with the loops instrumented for the sandbox.
+	pub(super) fn wrap(&self, name: &str, signature: &str) -> WrappedScript {
+		self.wrap_with_prologue(name, signature, &[])
+	}
+
+	/// Like `wrap`, with lines of code run before the script in the function.
+	pub(super) fn wrap_with_prologue(
+		&self,
+		name: &str,
+		signature: &str,
+		prologue: &[String],
+	) -> WrappedScript {
+		// Badly indented code is left as is, for PocketPy to report the error
+		let code = diagnostics::normalize_indentation(self.code.as_str())
+			.unwrap_or_else(|_| self.code.clone());
+		let instrumented = sandbox::instrument_loops(code.as_str());
+
+		WrappedScript {
+			code: CString::new(format!(
+				"def {}({}):\n{}\t{}",
+				name,
+				signature,
+				prologue
+					.iter()
+					.map(|line| format!("\t{}\n", line))
+					.collect::<String>(),
+				instrumented.code.replace('\n', "\n\t").as_str()
+			))
+			.expect("Code should not contain 0 bytes"),
+			header_lines: 1 + prologue.len(),
+			instrumented,
+		}
+	}
+
+	/// Checks the indentation of the script, then compiles it without running it if the
+	/// interpreter is available.
+	pub fn check(&self, lock: Option<PocketPyLockGuard<'_>>) -> Result<(), ScriptDiagnostic> {
+		diagnostics::normalize_indentation(self.code.as_str())?;
+
+		let Some(_lock) = lock else {
+			return Ok(());
+		};
+
+		let wrapped = self.wrap("__materia_check", "");
+		let name_c = CString::new(self.name.as_str())
+			.expect("Name from filename should not contain 0 bytes");
+
+		unsafe {
+			if py_compile(
+				wrapped.code.as_ptr(),
+				name_c.as_ptr(),
+				py_CompileMode_EXEC_MODE,
+				false,
+			) {
+				return Ok(());
+			}
+
+			let traceback = traceback::remap(
+				CStr::from_ptr(py_formatexc()).to_string_lossy().as_ref(),
+				|file, line| (file == self.name).then(|| wrapped.original_line(line)).flatten(),
+			);
+			py_clearexc(null_mut());
+
+			Err(ScriptDiagnostic {
+				line: traceback::error_line(traceback.as_str(), self.name.as_str()),
+				message: traceback.lines().last().unwrap_or_default().to_string(),
+			})
+		}
+	}
+
+	/// Takes the pending exception, with the lines of this script mapped back to what the user wrote.
+	unsafe fn traceback_error(&self, wrapped: &WrappedScript) -> PocketPyScriptError {
+		PocketPyScriptError::PocketPyError(console::take_traceback(|file, line| {
+			if file == self.name {
+				wrapped.original_line(line)
+			} else {
+				library::original_line(file, line)
+			}
+		}))
+	}
+
+	/// Runs the script as a function once per set of arguments, within the time limit from the settings.
 	pub fn execute_function_for<ReturnType: IntoPocketPyValue + 'static>(
+		&self,
+		lock: PocketPyLockGuard<'_>,
+		name: &str,
+		args: impl IntoIterator<Item = (impl AsRef<str>, Vec<AnyIntoPocketPyValue>)>,
+	) -> Result<Vec<ReturnType>, PocketPyScriptError> {
+		let time_limit = Duration::from_millis(Settings::get().script_time_limit_ms);
+		self.execute_function_for_with_budget(lock, name, args, Some(time_limit))
+	}
+
+	/// Like `execute_function_for`, but the whole invocation is interrupted after `time_limit`.
+	/// Without a limit, the script can only be stopped with `cancel_running_script`.
+	pub fn execute_function_for_with_budget<ReturnType: IntoPocketPyValue + 'static>(
 		&self,
 		_lock: PocketPyLockGuard<'_>,
 		name: &str,
-		args: impl IntoIterator<Item = (&'static str, Vec<AnyIntoPocketPyValue>)>,
+		args: impl IntoIterator<Item = (impl AsRef<str>, Vec<AnyIntoPocketPyValue>)>,
+		time_limit: Option<Duration>,
 	) -> Result<Vec<ReturnType>, PocketPyScriptError> {
 		let name_c = CString::new(self.name.as_str())
 			.expect("Name from filename should not contain 0 bytes");
 		let func_name_c = CString::new(name).expect("Name should not contain 0 bytes");
 
-		let (arg_names, args): (Vec<&'static str>, Vec<_>) =
-			args.into_iter().map(|a| (a.0, a.1)).unzip();
-
-		let arg_names = arg_names.join(",");
+		let (arg_names, args): (Vec<String>, Vec<_>) = args
+			.into_iter()
+			.map(|a| (a.0.as_ref().to_string(), a.1))
+			.unzip();
 
-		let code = CString::new(format!(
-			"def {}({}):\n\t{}",
-			name,
-			arg_names,
-			self.code.replace('\n', "\n\t").as_str()
-		))
-		.expect("Code should not contain 0 bytes");
+		let wrapped = self.wrap(name, arg_names.join(",").as_str());
 
 		let call_count = args.get(0).map(|el| el.len()).unwrap_or(1);
 		let mut ret_vals = Vec::with_capacity(call_count);
 
+		console::set_source(self.name.as_str());
+		sandbox::start(time_limit);
+
 		unsafe {
+			library::reload_stale();
+			materia::start_invocation();
+
 			if !py_exec(
-				code.as_ptr(),
+				wrapped.code.as_ptr(),
 				name_c.as_ptr(),
 				py_CompileMode_EXEC_MODE,
 				null_mut(),
 			) {
-				return Err(PocketPyScriptError::PocketPyError(
-					CStr::from_ptr(py_formatexc()).to_string_lossy().to_string(),
-				));
+				return Err(self.traceback_error(&wrapped));
 			}
 
 			let func_ref = py_getglobal(py_name(func_name_c.as_ptr()));
@@ -162,9 +286,18 @@ impl PocketPyScript {
 				}
 
 				if !py_vectorcall(argc as u16, 0) {
-					return Err(PocketPyScriptError::PocketPyError(
-						CStr::from_ptr(py_formatexc()).to_string_lossy().to_string(),
-					));
+					if let Some(interruption) = sandbox::take_interruption() {
+						py_clearexc(null_mut());
+
+						let e = match interruption {
+							Interruption::Timeout(limit) => PocketPyScriptError::Timeout(limit),
+							Interruption::Cancelled => PocketPyScriptError::Cancelled,
+						};
+						console::write(console::ConsoleEntryKind::Error, e.to_string().as_str());
+						return Err(e);
+					}
+
+					return Err(self.traceback_error(&wrapped));
 				}
 
 				ret_vals.push(ReturnType::from_pocketpy_value_ptr(py_retval())?);
@@ -192,13 +325,19 @@ impl PocketPyScript {
 	}
 }
 
-pub fn run_standalone_script(name: &str) {
-	if let Ok(path) = StandaloneScriptBadgeType::get_path() {
-		if let Ok(script) = PocketPyScript::load(path.join(name).with_extension("py")) {
-			if let Err(e) = script.execute_function::<()>(crate::app::script_lock(), "run", []) {
-				eprintln!("Error running script: {}", e);
-			}
-		}
+/// The code of a script wrapped in a function definition, as it is executed.
+pub(super) struct WrappedScript {
+	pub code: CString,
+	/// Lines before the script, the `def` and the prologue
+	header_lines: usize,
+	instrumented: sandbox::InstrumentedCode,
+}
+
+impl WrappedScript {
+	/// Maps a line of the wrapped code to the line of the script it comes from.
+	pub fn original_line(&self, line: usize) -> Option<usize> {
+		self.instrumented
+			.original_line(line.checked_sub(self.header_lines)?)
 	}
 }
 
@@ -210,6 +349,15 @@ pub enum PocketPyScriptError {
 	#[error("Script returned value of the wrong type")]
 	WrongType,
 
+	#[error("Script took longer than its time limit of {}ms", .0.as_millis())]
+	Timeout(Duration),
+
+	#[error("Script was cancelled")]
+	Cancelled,
+
+	#[error("{0}")]
+	Diagnostic(#[from] ScriptDiagnostic),
+
 	#[error("IO Error: {0}")]
 	IOError(
 		#[from]
@@ -238,6 +386,17 @@ pub enum PocketPyScriptError {
 	),
 }
 
+impl PocketPyScriptError {
+	/// Line of the script the error happened on, as the user wrote it.
+	pub fn line(&self, script_name: &str) -> Option<usize> {
+		match self {
+			Self::PocketPyError(traceback) => traceback::error_line(traceback, script_name),
+			Self::Diagnostic(diagnostic) => diagnostic.line,
+			_ => None,
+		}
+	}
+}
+
 #[cfg(test)]
 mod tests {
 	use super::*;
@@ -295,4 +454,110 @@ mod tests {
 
 		drop(lock);
 	}
+
+	#[test]
+	fn error_line() {
+		let lock = PocketPyLock::new();
+
+		let error = PocketPyScript {
+			name: "test_error_line".to_string(),
+			code: "total = 0\nfor i in range(x):\n\ttotal += i\nreturn total + missing".to_string(),
+		}
+		.execute_function::<i64>(lock.lock(), "error_line", [py_arg!("x", 3i64)])
+		.unwrap_err();
+
+		assert_eq!(error.line("test_error_line"), Some(4));
+
+		drop(lock);
+	}
+
+	#[test]
+	fn check() {
+		let lock = PocketPyLock::new();
+
+		let script = |code: &str| PocketPyScript {
+			name: "test_check".to_string(),
+			code: code.to_string(),
+		};
+
+		assert_eq!(script("if x:\n  y = 1\n\treturn y").check(Some(lock.lock())), Ok(()));
+		assert_eq!(
+			script("for i in range(3):\n\tpass\nif x\n\treturn 2")
+				.check(Some(lock.lock()))
+				.unwrap_err()
+				.line,
+			Some(3)
+		);
+		assert_eq!(
+			script("if x:\n    y\n  return 2").check(None).unwrap_err().line,
+			Some(3)
+		);
+
+		drop(lock);
+	}
+
+	#[test]
+	fn timeout() {
+		let lock = PocketPyLock::new();
+
+		assert!(matches!(
+			PocketPyScript {
+				name: "test_timeout".to_string(),
+				code: "while True:\n\tx += 1\nreturn x".to_string(),
+			}
+			.execute_function_for_with_budget::<i64>(
+				lock.lock(),
+				"loop_forever",
+				[py_args!("x", 0i64)],
+				Some(Duration::from_millis(50)),
+			),
+			Err(PocketPyScriptError::Timeout(_))
+		));
+
+		assert!(matches!(
+			PocketPyScript {
+				name: "test_comprehension_timeout".to_string(),
+				code: "return len([i for i in range(1000000000) if i < 0]) + x".to_string(),
+			}
+			.execute_function_for_with_budget::<i64>(
+				lock.lock(),
+				"comprehend_forever",
+				[py_args!("x", 0i64)],
+				Some(Duration::from_millis(50)),
+			),
+			Err(PocketPyScriptError::Timeout(_))
+		));
+
+		assert_eq!(
+			PocketPyScript {
+				name: "test_after_timeout".to_string(),
+				code: "for i in range(3): x += i\nreturn x + sum([i for i in range(3)])".to_string(),
+			}
+			.execute_function_for_with_budget::<i64>(
+				lock.lock(),
+				"loop_a_bit",
+				[py_args!("x", 1i64)],
+				Some(Duration::from_millis(1000)),
+			)
+			.unwrap(),
+			vec![7i64],
+		);
+
+		drop(lock);
+	}
+
+	#[test]
+	fn code_hash() {
+		let hash = |code: &str| {
+			PocketPyScript {
+				name: "test_hash".to_string(),
+				code: code.to_string(),
+			}
+			.code_hash()
+		};
+
+		assert_eq!(hash(""), 0xcbf2_9ce4_8422_2325);
+		assert_eq!(hash("a"), 0xaf63_dc4c_8601_ec8c);
+		assert_eq!(hash("foobar"), 0x8594_4171_f739_67e8);
+	}
 }
@@ -0,0 +1,388 @@
+use std::{
+	collections::BTreeMap,
+	path::{Path, PathBuf},
+};
+
+use crate::data_dir::DataDirError;
+
+use super::{
+	badge::BadgeType, decorator::DecoratorBadgeType, filter::FilterBadgeType, library,
+	library::LibraryBadgeType, sorting::SortingBadgeType,
+	standalone_script::StandaloneScriptBadgeType, PocketPyScript, PocketPyScriptError,
+};
+
+/// Name of the manifest at the root of a package directory.
+pub const MANIFEST_FILE: &str = "package.ron";
+
+/// The kinds of scripts a package can contain, each installed in the directory of its type.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, serde::Serialize, serde::Deserialize)]
+pub enum ScriptKind {
+	Filter,
+	Sorting,
+	Decorator,
+	Standalone,
+	Library,
+}
+
+impl ScriptKind {
+	pub fn name(&self) -> &'static str {
+		match self {
+			Self::Filter => "Filter",
+			Self::Sorting => "Sorting",
+			Self::Decorator => "Decorator",
+			Self::Standalone => "Standalone",
+			Self::Library => "Library",
+		}
+	}
+
+	fn path(&self) -> Result<PathBuf, &'static DataDirError> {
+		Ok(match self {
+			Self::Filter => FilterBadgeType::get_path()?,
+			Self::Sorting => SortingBadgeType::get_path()?,
+			Self::Decorator => DecoratorBadgeType::get_path()?,
+			Self::Standalone => StandaloneScriptBadgeType::get_path()?,
+			Self::Library => LibraryBadgeType::get_path()?,
+		}
+		.to_path_buf())
+	}
+}
+
+/// The `package.ron` of a package: a directory with python files, listed by kind with paths
+/// relative to the directory.
+#[derive(Debug, Clone, Default, PartialEq, Eq, serde::Serialize, serde::Deserialize)]
+#[serde(default)]
+pub struct PackageManifest {
+	pub name: String,
+	pub version: String,
+	pub description: String,
+	pub filters: Vec<PathBuf>,
+	pub sortings: Vec<PathBuf>,
+	pub decorators: Vec<PathBuf>,
+	pub standalone: Vec<PathBuf>,
+	pub library: Vec<PathBuf>,
+}
+
+impl PackageManifest {
+	pub fn load(package_dir: &Path) -> Result<Self, PackageError> {
+		Ok(ron::from_str(
+			std::fs::read_to_string(package_dir.join(MANIFEST_FILE))?.as_str(),
+		)?)
+	}
+
+	fn scripts(&self) -> impl Iterator<Item = (ScriptKind, &PathBuf)> {
+		[
+			(ScriptKind::Filter, &self.filters),
+			(ScriptKind::Sorting, &self.sortings),
+			(ScriptKind::Decorator, &self.decorators),
+			(ScriptKind::Standalone, &self.standalone),
+			(ScriptKind::Library, &self.library),
+		]
+		.into_iter()
+		.flat_map(|(kind, paths)| paths.iter().map(move |path| (kind, path)))
+	}
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, serde::Serialize, serde::Deserialize)]
+pub struct InstalledPackage {
+	pub version: String,
+	pub description: String,
+	/// Where the package was installed from, to update it
+	pub source: PathBuf,
+	/// The scripts the package placed, by kind and name
+	pub scripts: Vec<(ScriptKind, String)>,
+}
+
+/// The installed packages, saved in the data directory.
+#[derive(Debug, Default)]
+pub struct PackageList {
+	packages: BTreeMap<String, InstalledPackage>,
+}
+
+impl PackageList {
+	pub fn load() -> Result<Self, PackageError> {
+		let path = crate::data_dir()?.packages();
+
+		Ok(Self {
+			packages: if path.exists() {
+				ron::from_str(std::fs::read_to_string(path)?.as_str())?
+			} else {
+				BTreeMap::new()
+			},
+		})
+	}
+
+	pub fn save(&self) -> Result<(), PackageError> {
+		std::fs::write(
+			crate::data_dir()?.packages(),
+			ron::ser::to_string_pretty(&self.packages, ron::ser::PrettyConfig::default())
+				.expect("ron serialization of packages should not fail"),
+		)?;
+		Ok(())
+	}
+
+	pub fn iter(&self) -> impl Iterator<Item = (&String, &InstalledPackage)> {
+		self.packages.iter()
+	}
+
+	/// The version of the package at the source of an installed package, if it differs from the
+	/// installed one.
+	pub fn available_update(&self, name: &str) -> Option<String> {
+		let installed = self.packages.get(name)?;
+		let manifest = PackageManifest::load(&installed.source).ok()?;
+
+		(manifest.version != installed.version).then_some(manifest.version)
+	}
+
+	/// Installs the package in a directory, or updates it if it is already installed. Scripts the
+	/// new version doesn't have anymore are removed.
+	///
+	/// Returns the previously installed version, if any.
+	pub fn install(&mut self, source: &Path) -> Result<Option<String>, PackageError> {
+		let previous = self.install_in(source, |kind| Ok(kind.path()?))?;
+		self.save()?;
+		Ok(previous)
+	}
+
+	/// Installs the package at its source again.
+	pub fn update(&mut self, name: &str) -> Result<Option<String>, PackageError> {
+		let source = self
+			.packages
+			.get(name)
+			.ok_or_else(|| PackageError::NotInstalled(name.to_string()))?
+			.source
+			.clone();
+
+		self.install(&source)
+	}
+
+	/// Removes the scripts of a package.
+	pub fn uninstall(&mut self, name: &str) -> Result<(), PackageError> {
+		self.uninstall_in(name, |kind| Ok(kind.path()?))?;
+		self.save()
+	}
+
+	fn install_in(
+		&mut self,
+		source: &Path,
+		dir_of: impl Fn(ScriptKind) -> Result<PathBuf, PackageError>,
+	) -> Result<Option<String>, PackageError> {
+		let source = source.canonicalize()?;
+		let manifest = PackageManifest::load(&source)?;
+
+		if manifest.name.trim().is_empty() {
+			return Err(PackageError::NoName);
+		}
+
+		let mut scripts: Vec<(ScriptKind, PocketPyScript)> = Vec::new();
+
+		for (kind, path) in manifest.scripts() {
+			let script = PocketPyScript::load(source.join(path))?;
+
+			if scripts
+				.iter()
+				.any(|(other_kind, other)| *other_kind == kind && other.name == script.name)
+			{
+				return Err(PackageError::Duplicate(kind.name(), script.name));
+			}
+
+			scripts.push((kind, script));
+		}
+
+		// Check every script before placing any, so that a conflict leaves nothing half installed
+		for (kind, script) in scripts.iter() {
+			if !dir_of(*kind)?.join(&script.name).with_extension("py").exists() {
+				continue;
+			}
+
+			let owner = self.packages.iter().find_map(|(name, package)| {
+				package
+					.scripts
+					.iter()
+					.any(|(owned_kind, owned)| owned_kind == kind && owned == &script.name)
+					.then_some(name)
+			});
+
+			if owner != Some(&manifest.name) {
+				return Err(PackageError::Conflict {
+					kind: kind.name(),
+					script: script.name.clone(),
+					owner: owner.cloned(),
+				});
+			}
+		}
+
+		for (kind, script) in scripts.iter() {
+			script.save(dir_of(*kind)?)?;
+
+			if *kind == ScriptKind::Library {
+				library::invalidate(script.name.as_str());
+			}
+		}
+
+		let installed = InstalledPackage {
+			version: manifest.version,
+			description: manifest.description,
+			source,
+			scripts: scripts
+				.into_iter()
+				.map(|(kind, script)| (kind, script.name))
+				.collect(),
+		};
+
+		let previous = self.packages.insert(manifest.name, installed.clone());
+
+		if let Some(previous) = &previous {
+			for (kind, name) in previous.scripts.iter() {
+				if !installed.scripts.contains(&(*kind, name.clone())) {
+					remove_script(&dir_of(*kind)?, *kind, name)?;
+				}
+			}
+		}
+
+		Ok(previous.map(|previous| previous.version))
+	}
+
+	fn uninstall_in(
+		&mut self,
+		name: &str,
+		dir_of: impl Fn(ScriptKind) -> Result<PathBuf, PackageError>,
+	) -> Result<(), PackageError> {
+		let package = self
+			.packages
+			.remove(name)
+			.ok_or_else(|| PackageError::NotInstalled(name.to_string()))?;
+
+		for (kind, script) in package.scripts.iter() {
+			remove_script(&dir_of(*kind)?, *kind, script)?;
+		}
+
+		Ok(())
+	}
+}
+
+/// Deletes a script, which may have been deleted by hand already. A library module stops being
+/// usable by the scripts that imported it.
+fn remove_script(dir: &Path, kind: ScriptKind, name: &str) -> Result<(), PackageError> {
+	match std::fs::remove_file(dir.join(name).with_extension("py")) {
+		Err(e) if e.kind() != std::io::ErrorKind::NotFound => Err(e.into()),
+		_ => {
+			if kind == ScriptKind::Library {
+				library::invalidate(name);
+			}
+
+			Ok(())
+		}
+	}
+}
+
+#[derive(Debug, thiserror::Error)]
+pub enum PackageError {
+	#[error("Could not access data directory: {0}")]
+	DataDirError(
+		#[from]
+		#[source]
+		&'static DataDirError,
+	),
+
+	#[error("IO error: {0}")]
+	IOError(
+		#[from]
+		#[source]
+		std::io::Error,
+	),
+
+	#[error("Parse error: {0}")]
+	ParseError(
+		#[from]
+		#[source]
+		ron::error::SpannedError,
+	),
+
+	#[error("Could not read script: {0}")]
+	Script(
+		#[from]
+		#[source]
+		PocketPyScriptError,
+	),
+
+	#[error("The manifest has no package name")]
+	NoName,
+
+	#[error("The package has two {0} scripts named {1}")]
+	Duplicate(&'static str, String),
+
+	#[error("{kind} script {script} already exists{}", .owner.as_ref().map(|owner| format!(" in package {}", owner)).unwrap_or_default())]
+	Conflict {
+		kind: &'static str,
+		script: String,
+		owner: Option<String>,
+	},
+
+	#[error("Package {0} is not installed")]
+	NotInstalled(String),
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	fn write_package(dir: &Path, version: &str, scripts: &[&str]) {
+		std::fs::create_dir_all(dir.join("filters")).unwrap();
+
+		for script in scripts {
+			std::fs::write(dir.join("filters").join(script).with_extension("py"), "return True")
+				.unwrap();
+		}
+
+		std::fs::write(
+			dir.join(MANIFEST_FILE),
+			format!(
+				"(name: \"test\", version: \"{}\", filters: [{}])",
+				version,
+				scripts
+					.iter()
+					.map(|script| format!("\"filters/{}.py\"", script))
+					.collect::<Vec<_>>()
+					.join(", ")
+			),
+		)
+		.unwrap();
+	}
+
+	#[test]
+	fn install_update_uninstall() {
+		let root = std::env::temp_dir().join(format!("adhd_materia_package_{}", std::process::id()));
+		let source = root.join("source");
+		let installed = root.join("installed");
+		std::fs::create_dir_all(&installed).unwrap();
+		let dir_of = |_| Ok(installed.clone());
+		let exists = |name: &str| installed.join(name).with_extension("py").exists();
+
+		let mut packages = PackageList::default();
+
+		write_package(&source, "1.0", &["a", "b"]);
+		assert_eq!(packages.install_in(&source, dir_of).unwrap(), None);
+		assert!(exists("a") && exists("b"));
+
+		write_package(&source, "1.1", &["a", "c"]);
+		std::fs::remove_file(source.join("filters/b.py")).unwrap();
+		assert_eq!(
+			packages.install_in(&source, dir_of).unwrap(),
+			Some(String::from("1.0"))
+		);
+		assert!(exists("a") && !exists("b") && exists("c"));
+
+		std::fs::write(installed.join("d.py"), "return False").unwrap();
+		write_package(&source, "1.2", &["d"]);
+		assert!(matches!(
+			packages.install_in(&source, dir_of),
+			Err(PackageError::Conflict { owner: None, .. })
+		));
+		assert!(exists("a"));
+
+		packages.uninstall_in("test", dir_of).unwrap();
+		assert!(!exists("a") && !exists("c") && exists("d"));
+
+		std::fs::remove_dir_all(&root).unwrap();
+	}
+}
@@ -0,0 +1,219 @@
+use std::{collections::HashMap, str::FromStr};
+
+use chrono::NaiveDate;
+
+use crate::tag::TagValue;
+
+use super::value::AnyIntoPocketPyValue;
+
+/// A parameter declared in the header of a script, like `# param tag: str = "work"`.
+#[derive(Debug, Clone, PartialEq)]
+pub struct ScriptParam {
+	pub name: String,
+	pub param_type: ScriptParamType,
+	pub default: TagValue,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ScriptParamType {
+	Str,
+	Int,
+	Float,
+	Bool,
+	Date,
+}
+
+impl ScriptParamType {
+	fn from_name(name: &str) -> Option<Self> {
+		match name {
+			"str" => Some(Self::Str),
+			"int" => Some(Self::Int),
+			"float" => Some(Self::Float),
+			"bool" => Some(Self::Bool),
+			"date" | "Date" => Some(Self::Date),
+			_ => None,
+		}
+	}
+
+	fn default_value(&self) -> TagValue {
+		match self {
+			Self::Str => TagValue::Text(String::new()),
+			Self::Int => TagValue::Int(0),
+			Self::Float => TagValue::Float(0.0),
+			Self::Bool => TagValue::Bool(false),
+			Self::Date => TagValue::Date(chrono::Local::now().date_naive()),
+		}
+	}
+
+	/// Converts `value` to this type if it is close enough, like an int for a float parameter.
+	pub fn coerce(&self, value: TagValue) -> Option<TagValue> {
+		match (self, value) {
+			(Self::Str, value @ TagValue::Text(_)) => Some(value),
+			(Self::Int, value @ TagValue::Int(_)) => Some(value),
+			(Self::Float, value @ TagValue::Float(_)) => Some(value),
+			(Self::Float, TagValue::Int(int)) => Some(TagValue::Float(int as f64)),
+			(Self::Bool, value @ TagValue::Bool(_)) => Some(value),
+			(Self::Date, value @ TagValue::Date(_)) => Some(value),
+			_ => None,
+		}
+	}
+
+	fn parse_value(&self, s: &str) -> Option<TagValue> {
+		match self {
+			Self::Bool => match s.to_lowercase().as_str() {
+				"true" => Some(TagValue::Bool(true)),
+				"false" => Some(TagValue::Bool(false)),
+				_ => None,
+			},
+			Self::Date => NaiveDate::from_str(s.trim_matches(|c| c == '"' || c == '\''))
+				.ok()
+				.map(TagValue::Date),
+			_ => self.coerce(TagValue::from_str(s).ok()?),
+		}
+	}
+}
+
+/// Reads the `# param name: type = default` lines at the start of a script.
+///
+/// Only the leading comment block is read, and lines that don't parse are ignored.
+pub fn parse_params(code: &str) -> Vec<ScriptParam> {
+	code.lines()
+		.map(str::trim)
+		.take_while(|line| line.is_empty() || line.starts_with('#'))
+		.filter_map(|line| {
+			let declaration = line.strip_prefix('#')?.trim().strip_prefix("param ")?;
+			let (name, rest) = declaration.split_once(':')?;
+			let name = name.trim();
+
+			if name.is_empty()
+				|| name.starts_with(|c: char| c.is_ascii_digit())
+				|| !name.chars().all(|c| c.is_ascii_alphanumeric() || c == '_')
+				|| name == "task"
+				|| name == "parent"
+			{
+				return None;
+			}
+
+			let (type_name, default) = match rest.split_once('=') {
+				Some((type_name, default)) => (type_name.trim(), Some(default.trim())),
+				None => (rest.trim(), None),
+			};
+
+			let param_type = ScriptParamType::from_name(type_name)?;
+
+			Some(ScriptParam {
+				name: name.to_string(),
+				param_type,
+				default: default
+					.and_then(|default| param_type.parse_value(default))
+					.unwrap_or_else(|| param_type.default_value()),
+			})
+		})
+		.collect()
+}
+
+/// Values for the parameters of a script, taken from `set_values` when they fit the declared type.
+pub fn resolve_values(
+	params: &[ScriptParam],
+	set_values: Option<&HashMap<String, TagValue>>,
+) -> Vec<(String, TagValue)> {
+	params
+		.iter()
+		.map(|param| {
+			let value = set_values
+				.and_then(|values| values.get(param.name.as_str()))
+				.and_then(|value| param.param_type.coerce(value.clone()))
+				.unwrap_or_else(|| param.default.clone());
+
+			(param.name.clone(), value)
+		})
+		.collect()
+}
+
+/// Turns script parameter values into arguments, repeating each value once per task.
+pub(super) fn param_args(
+	values: Vec<(String, TagValue)>,
+	count: usize,
+) -> impl Iterator<Item = (String, Vec<AnyIntoPocketPyValue>)> {
+	values.into_iter().map(move |(name, value)| {
+		(
+			name,
+			std::iter::repeat_n(value, count)
+				.map(|value| Box::new(value) as AnyIntoPocketPyValue)
+				.collect(),
+		)
+	})
+}
+
+/// Shows an editor for a parameter value, returns whether it changed.
+pub fn param_editor(ui: &mut egui::Ui, id: &str, value: &mut TagValue) -> bool {
+	match value {
+		TagValue::Text(text) => ui
+			.add(egui::TextEdit::singleline(text).desired_width(80.0))
+			.changed(),
+		TagValue::Int(int) => ui.add(egui::DragValue::new(int)).changed(),
+		TagValue::Float(float) => ui.add(egui::DragValue::new(float).speed(0.1)).changed(),
+		TagValue::Bool(b) => ui.add(egui::Checkbox::without_text(b)).changed(),
+		TagValue::Date(date) => ui
+			.add(egui_extras::DatePickerButton::new(date).id_source(id))
+			.changed(),
+		other => {
+			ui.label(other.to_string());
+			false
+		}
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	#[test]
+	fn parse() {
+		let params = parse_params(
+			"# Only show tasks with a given tag\n\
+			# param tag: str = \"work\"\n\
+			# param min_priority: float = 2\n\
+			#param urgent: bool = True\n\
+			# param since: date = 2024-01-10\n\
+			# param count: int\n\
+			# param task: int = 1\n\
+			# param bad name: int = 1\n\
+			# param other: list = []\n\
+			\n\
+			return True\n\
+			# param after_code: int = 1",
+		);
+
+		assert_eq!(
+			params,
+			vec![
+				ScriptParam {
+					name: String::from("tag"),
+					param_type: ScriptParamType::Str,
+					default: TagValue::Text(String::from("work")),
+				},
+				ScriptParam {
+					name: String::from("min_priority"),
+					param_type: ScriptParamType::Float,
+					default: TagValue::Float(2.0),
+				},
+				ScriptParam {
+					name: String::from("urgent"),
+					param_type: ScriptParamType::Bool,
+					default: TagValue::Bool(true),
+				},
+				ScriptParam {
+					name: String::from("since"),
+					param_type: ScriptParamType::Date,
+					default: TagValue::Date(NaiveDate::from_ymd_opt(2024, 1, 10).unwrap()),
+				},
+				ScriptParam {
+					name: String::from("count"),
+					param_type: ScriptParamType::Int,
+					default: TagValue::Int(0),
+				},
+			]
+		);
+	}
+}
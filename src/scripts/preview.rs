@@ -0,0 +1,202 @@
+use crate::{
+	session::Session,
+	task::{list::TaskList, Task, TaskPath},
+	views::{manual_positions, ManualOrders},
+};
+
+use super::{
+	badge::BadgeType,
+	decorator::{run_decorator_script, Decoration},
+	diagnostics::ScriptDiagnostic,
+	filter::run_filter_script,
+	params::{parse_params, resolve_values},
+	runner,
+	sorting::{run_sorting_script, SortKey},
+	PocketPyScript,
+};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum PreviewKind {
+	Filter,
+	Sorting,
+	Decorator,
+}
+
+struct PreviewRow {
+	task_name: String,
+	passes: bool,
+	key: SortKey,
+	decoration: Option<Decoration>,
+}
+
+struct PreviewError {
+	message: String,
+	line: Option<usize>,
+}
+
+/// Result of running a filter or sorting script against the current tasks, without enabling it.
+pub struct ScriptPreview {
+	kind: PreviewKind,
+	script_name: String,
+	result: Result<Vec<PreviewRow>, PreviewError>,
+}
+
+impl ScriptPreview {
+	/// Runs the script on the saved tasks, with the parameter values of its badge and the manual
+	/// orders of the current view.
+	pub fn run<T: BadgeType>(kind: PreviewKind, script: &PocketPyScript, manual_orders: &ManualOrders) -> Self {
+		Self {
+			kind,
+			script_name: script.name.clone(),
+			result: Self::run_rows::<T>(kind, script, manual_orders),
+		}
+	}
+
+	fn run_rows<T: BadgeType>(
+		kind: PreviewKind,
+		script: &PocketPyScript,
+		manual_orders: &ManualOrders,
+	) -> Result<Vec<PreviewRow>, PreviewError> {
+		if runner::is_running() {
+			return Err(PreviewError {
+				message: String::from("Wait for the running script to finish to preview"),
+				line: None,
+			});
+		}
+
+		let task_list = TaskList::new(TaskPath::Tasks)
+			.map(|(task_list, _)| task_list)
+			.map_err(|e| PreviewError {
+				message: format!("Could not load tasks: {}", e),
+				line: None,
+			})?;
+		let tasks: Vec<&Task> = task_list.tasks().collect();
+		let params = resolve_values(
+			&parse_params(script.code.as_str()),
+			T::get_session_badge_params(&Session::current()).get(script.name.as_str()),
+		);
+
+		let rows = match kind {
+			PreviewKind::Filter => run_filter_script(script, &tasks, None, params).map(|passes| {
+				tasks
+					.iter()
+					.zip(passes)
+					.map(|(task, passes)| PreviewRow {
+						task_name: task.name.clone(),
+						passes,
+						key: SortKey::None,
+						decoration: None,
+					})
+					.collect::<Vec<PreviewRow>>()
+			}),
+			PreviewKind::Sorting => run_sorting_script(
+				script,
+				&tasks,
+				&manual_positions(manual_orders, tasks.iter().copied()),
+				params,
+			)
+			.map(|keys| {
+				let mut rows: Vec<PreviewRow> = tasks
+					.iter()
+					.zip(keys)
+					.map(|(task, key)| PreviewRow {
+						task_name: task.name.clone(),
+						passes: true,
+						key,
+						decoration: None,
+					})
+					.collect();
+				rows.sort_by(|a, b| a.key.compare(&b.key));
+				rows
+			}),
+			PreviewKind::Decorator => run_decorator_script(script, &tasks, params).map(|decorations| {
+				tasks
+					.iter()
+					.zip(decorations)
+					.map(|(task, decoration)| PreviewRow {
+						task_name: task.name.clone(),
+						passes: true,
+						key: SortKey::None,
+						decoration,
+					})
+					.collect()
+			}),
+		};
+
+		rows.map_err(|e| PreviewError {
+			line: e.line(script.name.as_str()),
+			message: e.to_string(),
+		})
+	}
+
+	/// The error to mark in the editor, if the script failed.
+	pub fn diagnostic(&self) -> Option<ScriptDiagnostic> {
+		self.result.as_ref().err().map(|e| ScriptDiagnostic {
+			line: e.line,
+			message: e.message.lines().last().unwrap_or_default().to_string(),
+		})
+	}
+
+	pub fn show(&self, ui: &mut egui::Ui) {
+		match &self.result {
+			Ok(rows) => {
+				match self.kind {
+					PreviewKind::Filter => ui.label(format!(
+						"{} of {} tasks pass",
+						rows.iter().filter(|row| row.passes).count(),
+						rows.len()
+					)),
+					PreviewKind::Sorting => ui.label("Tasks by ascending key"),
+					PreviewKind::Decorator => ui.label(format!(
+						"{} of {} tasks get a chip",
+						rows.iter().filter(|row| row.decoration.is_some()).count(),
+						rows.len()
+					)),
+				};
+
+				egui::ScrollArea::vertical()
+					.max_height(240.0)
+					.auto_shrink([false, true])
+					.show(ui, |ui| {
+						egui::Grid::new("script_preview")
+							.num_columns(2)
+							.striped(true)
+							.show(ui, |ui| {
+								for row in rows {
+									match self.kind {
+										PreviewKind::Filter if row.passes => {
+											ui.colored_label(ui.visuals().widgets.active.text_color(), "✔")
+										}
+										PreviewKind::Filter => ui.weak("✖"),
+										PreviewKind::Sorting => ui.monospace(row.key.to_string()),
+										PreviewKind::Decorator => match &row.decoration {
+											Some(decoration) => decoration.show(ui, self.script_name.as_str()),
+											None => ui.weak("—"),
+										},
+									};
+
+									if row.passes {
+										ui.label(row.task_name.as_str());
+									} else {
+										ui.weak(row.task_name.as_str());
+									}
+
+									ui.end_row();
+								}
+							});
+					});
+			}
+			Err(e) => {
+				if let Some(line) = e.line {
+					ui.colored_label(ui.visuals().error_fg_color, format!("Error on line {}", line));
+				}
+
+				ui.label(
+					egui::RichText::new(e.message.as_str())
+						.monospace()
+						.color(ui.visuals().error_fg_color),
+				);
+			}
+		}
+	}
+}
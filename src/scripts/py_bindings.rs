@@ -7,7 +7,7 @@ use std::{
 	sync::Mutex,
 };
 
-use chrono::Datelike;
+use chrono::{Datelike, Timelike};
 use pocketpy_sys::*;
 
 use crate::{session::Session, task::Task};
@@ -85,6 +85,48 @@ pub unsafe fn initialize_bindings() {
 		Some(date____str_____repr__),
 	);
 
+	// Create the DateTime python type
+	let date_time_type = py_newtype(
+		c"DateTime".as_ptr(),
+		py_totype(py_getbuiltin(py_name(c"object".as_ptr()))),
+		null_mut(),
+		None,
+	);
+
+	py_setglobal(py_name(c"DateTime".as_ptr()), py_tpobject(date_time_type));
+	py_bindmethod(date_time_type, c"__new__".as_ptr(), Some(date_time____new__));
+	py_bindmethod(
+		date_time_type,
+		c"__str__".as_ptr(),
+		Some(date_time____str_____repr__),
+	);
+	py_bindmethod(
+		date_time_type,
+		c"__repr__".as_ptr(),
+		Some(date_time____str_____repr__),
+	);
+
+	// Create the Duration python type
+	let duration_type = py_newtype(
+		c"Duration".as_ptr(),
+		py_totype(py_getbuiltin(py_name(c"object".as_ptr()))),
+		null_mut(),
+		None,
+	);
+
+	py_setglobal(py_name(c"Duration".as_ptr()), py_tpobject(duration_type));
+	py_bindmethod(duration_type, c"__new__".as_ptr(), Some(duration____new__));
+	py_bindmethod(
+		duration_type,
+		c"__str__".as_ptr(),
+		Some(duration____str_____repr__),
+	);
+	py_bindmethod(
+		duration_type,
+		c"__repr__".as_ptr(),
+		Some(duration____str_____repr__),
+	);
+
 	// Create the TaskRef python type
 	let task_ref_type = py_newtype(
 		c"TaskRef".as_ptr(),
@@ -123,6 +165,13 @@ pub unsafe fn initialize_bindings() {
 	py_setglobal(py_name(c"today".as_ptr()), r0);
 	py_newnativefunc(r0, Some(add_task));
 	py_setglobal(py_name(c"add_task".as_ptr()), r0);
+	py_newnativefunc(r0, Some(set_progress));
+	py_setglobal(py_name(c"set_progress".as_ptr()), r0);
+	py_newnativefunc(r0, Some(super::sandbox::materia_tick));
+	py_setglobal(py_name(c"__materia_tick".as_ptr()), r0);
+
+	super::materia::initialize_module();
+	super::library::initialize();
 }
 
 unsafe extern "C" fn task____new__(argc: std::os::raw::c_int, argv: *mut py_TValue) -> bool {
@@ -149,6 +198,9 @@ unsafe extern "C" fn task____new__(argc: std::os::raw::c_int, argv: *mut py_TVal
 	py_setdict(py_retval(), py_name(c"description".as_ptr()), r0);
 	py_newlist(r0);
 	py_setdict(py_retval(), py_name(c"tags".as_ptr()), r0);
+	py_newlist(r0);
+	py_setdict(py_retval(), py_name(c"attachments".as_ptr()), r0);
+	py_setdict(py_retval(), py_name(c"ref".as_ptr()), py_None);
 	true
 }
 
@@ -381,6 +433,160 @@ pub fn new_py_date(out: *mut py_TValue, date: &chrono::NaiveDate) -> bool {
 	}
 }
 
+const DATE_TIME_FIELDS: [&CStr; 6] = [c"year", c"month", c"day", c"hour", c"minute", c"second"];
+
+unsafe extern "C" fn date_time____new__(
+	argc: std::os::raw::c_int,
+	argv: *mut py_TValue,
+) -> bool {
+	if !(4..=7).contains(&argc) {
+		py_newnone(py_retval());
+		return py_exception(
+			py_totype(py_getbuiltin(py_name(c"Exception".as_ptr()))),
+			c"Expected 3 to 6 arguments".as_ptr(),
+		);
+	}
+
+	let mut elements = [0; 6];
+
+	for (i, element) in elements.iter_mut().enumerate().take(argc as usize - 1) {
+		let arg = ((argv as usize) + size_of::<usize>() * 2 * (i + 1)) as *mut py_TValue;
+
+		if !py_istype(arg, py_totype(py_getbuiltin(py_name(c"int".as_ptr())))) {
+			py_newnone(py_retval());
+			return py_exception(
+				py_totype(py_getbuiltin(py_name(c"Exception".as_ptr()))),
+				c"DateTime expects integer arguments".as_ptr(),
+			);
+		}
+
+		*element = py_toint(arg);
+	}
+
+	new_py_date_time_elements(py_retval(), elements)
+}
+
+unsafe extern "C" fn date_time____str_____repr__(
+	argc: std::os::raw::c_int,
+	argv: *mut py_TValue,
+) -> bool {
+	if argc != 1 {
+		py_newnone(py_retval());
+		return py_exception(
+			py_totype(py_getbuiltin(py_name(c"Exception".as_ptr()))),
+			c"Expected 0 argument".as_ptr(),
+		);
+	}
+
+	let text = CString::new(match naive_date_time_from_py(argv) {
+		Ok(date_time) => crate::tag::format_date_time(&date_time),
+		Err(e) => e.to_string(),
+	})
+	.unwrap();
+
+	py_newstr(py_retval(), text.as_ptr());
+	true
+}
+
+fn new_py_date_time_elements(out: *mut py_TValue, elements: [i64; 6]) -> bool {
+	unsafe {
+		py_newobject(
+			out,
+			py_totype(py_getglobal(py_name(c"DateTime".as_ptr()))),
+			-1,
+			0,
+		);
+
+		spytvalue!(r0);
+
+		for (name, element) in DATE_TIME_FIELDS.into_iter().zip(elements) {
+			py_newint(r0, element);
+			py_setdict(out, py_name(name.as_ptr()), r0);
+		}
+
+		true
+	}
+}
+
+pub fn new_py_date_time(out: *mut py_TValue, date_time: &chrono::NaiveDateTime) -> bool {
+	new_py_date_time_elements(
+		out,
+		[
+			date_time.year() as i64,
+			date_time.month() as i64,
+			date_time.day() as i64,
+			date_time.hour() as i64,
+			date_time.minute() as i64,
+			date_time.second() as i64,
+		],
+	)
+}
+
+pub fn naive_date_time_from_py(
+	value: *mut py_TValue,
+) -> Result<chrono::NaiveDateTime, PocketPyScriptError> {
+	unsafe {
+		let [year, month, day, hour, minute, second] =
+			DATE_TIME_FIELDS.map(|name| py_toint(py_getdict(value, py_name(name.as_ptr()))));
+
+		chrono::NaiveDate::from_ymd_opt(year as i32, month as u32, day as u32)
+			.and_then(|date| date.and_hms_opt(hour as u32, minute as u32, second as u32))
+			.ok_or(PocketPyScriptError::DateOutOfBounds)
+	}
+}
+
+unsafe extern "C" fn duration____new__(argc: std::os::raw::c_int, argv: *mut py_TValue) -> bool {
+	let seconds = ((argv as usize) + size_of::<usize>() * 2) as *mut py_TValue;
+
+	if argc != 2 || !py_istype(seconds, py_totype(py_getbuiltin(py_name(c"int".as_ptr())))) {
+		py_newnone(py_retval());
+		return py_exception(
+			py_totype(py_getbuiltin(py_name(c"Exception".as_ptr()))),
+			c"Duration expects 1 integer argument, in seconds".as_ptr(),
+		);
+	}
+
+	new_py_duration(py_retval(), py_toint(seconds))
+}
+
+unsafe extern "C" fn duration____str_____repr__(
+	argc: std::os::raw::c_int,
+	argv: *mut py_TValue,
+) -> bool {
+	if argc != 1 {
+		py_newnone(py_retval());
+		return py_exception(
+			py_totype(py_getbuiltin(py_name(c"Exception".as_ptr()))),
+			c"Expected 0 argument".as_ptr(),
+		);
+	}
+
+	let text = CString::new(crate::tag::format_duration(py_toint(py_getdict(
+		argv,
+		py_name(c"seconds".as_ptr()),
+	))))
+	.unwrap();
+
+	py_newstr(py_retval(), text.as_ptr());
+	true
+}
+
+pub fn new_py_duration(out: *mut py_TValue, seconds: i64) -> bool {
+	unsafe {
+		py_newobject(
+			out,
+			py_totype(py_getglobal(py_name(c"Duration".as_ptr()))),
+			-1,
+			0,
+		);
+
+		spytvalue!(r0);
+		py_newint(r0, seconds);
+		py_setdict(out, py_name(c"seconds".as_ptr()), r0);
+		true
+	}
+}
+
 unsafe extern "C" fn task_ref____new__(argc: std::os::raw::c_int, argv: *mut py_TValue) -> bool {
 	if argc != 2 {
 		py_newnone(py_retval());
@@ -487,6 +693,23 @@ unsafe extern "C" fn run_standalone_script(
 	true
 }
 
+unsafe extern "C" fn set_progress(argc: std::os::raw::c_int, argv: *mut py_TValue) -> bool {
+	let mut progress = 0.0;
+
+	if argc != 1 || !py_castfloat(argv, &mut progress) {
+		py_newnone(py_retval());
+		return py_exception(
+			py_totype(py_getbuiltin(py_name(c"Exception".as_ptr()))),
+			c"Expected a number between 0 and 1".as_ptr(),
+		);
+	}
+
+	super::runner::set_progress(progress.clamp(0.0, 1.0) as f32);
+
+	py_newnone(py_retval());
+	true
+}
+
 unsafe extern "C" fn today(_argc: std::os::raw::c_int, _argv: *mut py_TValue) -> bool {
 	new_py_date(py_retval(), &chrono::Local::now().date_naive());
 	true
@@ -0,0 +1,99 @@
+use std::{
+	sync::{
+		atomic::{AtomicU32, Ordering},
+		Mutex,
+	},
+	thread::JoinHandle,
+	time::{Duration, Instant},
+};
+
+use super::{badge::BadgeType, standalone_script::StandaloneScriptBadgeType, PocketPyScript, PocketPyScriptError};
+
+struct RunningScript {
+	name: String,
+	started: Instant,
+	handle: JoinHandle<Result<(), PocketPyScriptError>>,
+}
+
+static RUNNING: Mutex<Option<RunningScript>> = Mutex::new(None);
+
+/// Progress reported by the running script through `set_progress`, as `f32` bits. NaN when unknown.
+static PROGRESS: AtomicU32 = AtomicU32::new(0x7FC0_0000);
+
+pub struct RunningScriptInfo {
+	pub name: String,
+	pub elapsed: Duration,
+	pub progress: Option<f32>,
+}
+
+/// Starts a standalone script on a worker thread. Returns `false` if another one is still running.
+pub fn spawn(name: String) -> bool {
+	let mut running = RUNNING.lock().unwrap();
+
+	if running.is_some() {
+		return false;
+	}
+
+	set_progress(f32::NAN);
+
+	let script_name = name.clone();
+	let handle = std::thread::spawn(move || {
+		let script = PocketPyScript::load(
+			StandaloneScriptBadgeType::get_path()?
+				.join(script_name.as_str())
+				.with_extension("py"),
+		)?;
+
+		script
+			.execute_function_for_with_budget::<()>(
+				crate::app::script_lock(),
+				"run",
+				Vec::<(&str, Vec<_>)>::new(),
+				None,
+			)
+			.map(|_| ())
+	});
+
+	*running = Some(RunningScript {
+		name,
+		started: Instant::now(),
+		handle,
+	});
+
+	true
+}
+
+pub fn is_running() -> bool {
+	RUNNING.lock().unwrap().is_some()
+}
+
+pub fn running_info() -> Option<RunningScriptInfo> {
+	RUNNING.lock().unwrap().as_ref().map(|running| RunningScriptInfo {
+		name: running.name.clone(),
+		elapsed: running.started.elapsed(),
+		progress: Some(f32::from_bits(PROGRESS.load(Ordering::Relaxed))).filter(|p| !p.is_nan()),
+	})
+}
+
+/// Returns the name and result of the script that was running, once it has finished.
+pub fn poll_finished() -> Option<(String, Result<(), PocketPyScriptError>)> {
+	let mut running = RUNNING.lock().unwrap();
+
+	if !running.as_ref()?.handle.is_finished() {
+		return None;
+	}
+
+	let finished = running.take().expect("checked above");
+
+	Some((
+		finished.name,
+		finished
+			.handle
+			.join()
+			.unwrap_or_else(|_| Err(PocketPyScriptError::PocketPyError(String::from("Script thread panicked")))),
+	))
+}
+
+pub(super) fn set_progress(progress: f32) {
+	PROGRESS.store(progress.to_bits(), Ordering::Relaxed);
+}
@@ -0,0 +1,378 @@
+use std::{
+	sync::{
+		atomic::{AtomicBool, Ordering},
+		Mutex,
+	},
+	time::{Duration, Instant},
+};
+
+use pocketpy_sys::*;
+
+/// Name of the native function called at the start of every loop iteration of user code.
+const TICK_FUNCTION: &str = "__materia_tick";
+
+/// Condition added to comprehensions without one, so that they tick on every item.
+const COMPREHENSION_TICK: &str = " if not __materia_tick()";
+
+/// Added in front of the condition of comprehensions that have one, pocketpy allowing a single
+/// condition per comprehension.
+const CONDITION_TICK: &str = " not __materia_tick() and";
+
+static BUDGET: Mutex<Option<(Instant, Duration)>> = Mutex::new(None);
+static CANCEL_REQUESTED: AtomicBool = AtomicBool::new(false);
+static INTERRUPTION: Mutex<Option<Interruption>> = Mutex::new(None);
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(super) enum Interruption {
+	Timeout(Duration),
+	Cancelled,
+}
+
+/// Starts the budget of a script invocation. `None` lets the script run until it is cancelled.
+pub(super) fn start(time_limit: Option<Duration>) {
+	*BUDGET.lock().unwrap() = time_limit.map(|limit| (Instant::now(), limit));
+	*INTERRUPTION.lock().unwrap() = None;
+	CANCEL_REQUESTED.store(false, Ordering::SeqCst);
+}
+
+pub(super) fn take_interruption() -> Option<Interruption> {
+	INTERRUPTION.lock().unwrap().take()
+}
+
+/// Asks the running script to stop at its next loop iteration.
+pub fn cancel() {
+	CANCEL_REQUESTED.store(true, Ordering::SeqCst);
+}
+
+pub(super) unsafe extern "C" fn materia_tick(
+	_argc: std::os::raw::c_int,
+	_argv: *mut py_TValue,
+) -> bool {
+	let interruption = if CANCEL_REQUESTED.load(Ordering::SeqCst) {
+		Some(Interruption::Cancelled)
+	} else {
+		BUDGET
+			.lock()
+			.unwrap()
+			.filter(|(start, limit)| start.elapsed() > *limit)
+			.map(|(_, limit)| Interruption::Timeout(limit))
+	};
+
+	match interruption {
+		Some(interruption) => {
+			*INTERRUPTION.lock().unwrap() = Some(interruption);
+			py_newnone(py_retval());
+			py_exception(
+				py_totype(py_getbuiltin(py_name(c"Exception".as_ptr()))),
+				c"Script interrupted".as_ptr(),
+			)
+		}
+		None => {
+			py_newnone(py_retval());
+			true
+		}
+	}
+}
+
+/// Lexer state carried from one line to the next.
+#[derive(Default)]
+pub(super) struct LineScanner {
+	depth: i32,
+	triple_quote: Option<char>,
+}
+
+impl LineScanner {
+	/// Whether the next line continues a statement, inside brackets or a multi-line string.
+	pub fn in_continuation(&self) -> bool {
+		self.depth > 0 || self.triple_quote.is_some()
+	}
+
+	/// Scans a line, returns the byte offset of the first colon at bracket depth 0, outside strings and comments.
+	pub fn scan(&mut self, line: &str) -> Option<usize> {
+		let mut colon = None;
+		let mut chars = line.char_indices().peekable();
+		let bytes = line.as_bytes();
+
+		while let Some((i, c)) = chars.next() {
+			if let Some(quote) = self.triple_quote {
+				if c == quote && line[i..].starts_with(&quote.to_string().repeat(3)) {
+					self.triple_quote = None;
+					chars.next();
+					chars.next();
+				}
+
+				continue;
+			}
+
+			match c {
+				'#' => break,
+				'"' | '\'' => {
+					if line[i..].starts_with(&c.to_string().repeat(3)) {
+						self.triple_quote = Some(c);
+						chars.next();
+						chars.next();
+					} else {
+						// Skip a single line string
+						while let Some((_, s)) = chars.next() {
+							if s == '\\' {
+								chars.next();
+							} else if s == c {
+								break;
+							}
+						}
+					}
+				}
+				'(' | '[' | '{' => self.depth += 1,
+				')' | ']' | '}' => self.depth -= 1,
+				':' if self.depth == 0 && colon.is_none() && bytes.get(i + 1) != Some(&b'=') => {
+					colon = Some(i);
+				}
+				_ => {}
+			}
+		}
+
+		colon
+	}
+}
+
+/// Code with calls to the tick function, and the line of the original code each of its lines comes from.
+pub(super) struct InstrumentedCode {
+	pub code: String,
+	line_map: Vec<usize>,
+}
+
+impl InstrumentedCode {
+	/// Maps a 1-based line of the instrumented code to a 1-based line of the original code.
+	pub fn original_line(&self, line: usize) -> Option<usize> {
+		self.line_map.get(line.checked_sub(1)?).copied()
+	}
+}
+
+/// Removes the tick calls inserted on loop header lines and in comprehensions from a line of
+/// source, like the ones quoted in tracebacks.
+pub(super) fn strip_ticks(source: &str) -> String {
+	source
+		.replace(&format!(" {}();", TICK_FUNCTION), "")
+		.replace(CONDITION_TICK, "")
+		.replace(COMPREHENSION_TICK, "")
+}
+
+/// Where the scan of a comprehension is, in the brackets that contain it.
+#[derive(Clone, Copy, PartialEq, Eq)]
+enum ComprehensionPart {
+	Element,
+	Target,
+	Iterable,
+	Condition,
+}
+
+/// Makes list, dict and set comprehensions call the tick function for every item, through their
+/// condition. Lines are kept as they are, only extended.
+fn instrument_comprehensions(code: &str) -> String {
+	let mut insertions: Vec<(usize, &str)> = Vec::new();
+	let mut brackets: Vec<ComprehensionPart> = Vec::new();
+	let mut chars = code.char_indices().peekable();
+
+	while let Some((i, c)) = chars.next() {
+		match c {
+			'#' => {
+				while chars.next_if(|(_, c)| *c != '\n').is_some() {}
+			}
+			'"' | '\'' => {
+				let triple = code[i..].starts_with(&c.to_string().repeat(3));
+
+				if triple {
+					chars.next();
+					chars.next();
+				}
+
+				while let Some((j, s)) = chars.next() {
+					if s == '\\' {
+						chars.next();
+					} else if s == c && (!triple || code[j..].starts_with(&c.to_string().repeat(3))) {
+						if triple {
+							chars.next();
+							chars.next();
+						}
+
+						break;
+					} else if s == '\n' && !triple {
+						break;
+					}
+				}
+			}
+			'(' | '[' | '{' => brackets.push(ComprehensionPart::Element),
+			')' | ']' | '}' if brackets.pop() == Some(ComprehensionPart::Iterable) => {
+				insertions.push((i, COMPREHENSION_TICK));
+			}
+			c if c.is_alphanumeric() || c == '_' => {
+				let mut end = i + c.len_utf8();
+
+				while let Some((j, w)) = chars.next_if(|(_, w)| w.is_alphanumeric() || *w == '_') {
+					end = j + w.len_utf8();
+				}
+
+				let Some(part) = brackets.last_mut() else {
+					continue;
+				};
+
+				match (&code[i..end], *part) {
+					("for", ComprehensionPart::Element) => *part = ComprehensionPart::Target,
+					("in", ComprehensionPart::Target) => *part = ComprehensionPart::Iterable,
+					("if", ComprehensionPart::Iterable) => {
+						insertions.push((end, CONDITION_TICK));
+						*part = ComprehensionPart::Condition;
+					}
+					_ => {}
+				}
+			}
+			_ => {}
+		}
+	}
+
+	let mut result = String::with_capacity(code.len() + insertions.len() * COMPREHENSION_TICK.len());
+	let mut copied = 0;
+
+	for (position, insertion) in insertions {
+		result.push_str(&code[copied..position]);
+		result.push_str(insertion);
+		copied = position;
+	}
+
+	result.push_str(&code[copied..]);
+	result
+}
+
+/// Inserts a call to the tick function at the start of every `while` and `for` loop body, and
+/// in the condition of every comprehension, so that infinite loops can be interrupted.
+pub(super) fn instrument_loops(code: &str) -> InstrumentedCode {
+	let code = instrument_comprehensions(code);
+	let lines: Vec<&str> = code.lines().collect();
+	let mut result: Vec<String> = Vec::with_capacity(lines.len());
+	let mut line_map: Vec<usize> = Vec::with_capacity(lines.len());
+	let mut scanner = LineScanner::default();
+
+	// Loops whose header is not finished yet, because it spans multiple lines
+	let mut pending_header = false;
+	let mut pending_body = false;
+
+	for (line_idx, line) in lines.iter().enumerate() {
+		let trimmed = line.trim_start();
+		let in_string = scanner.triple_quote.is_some();
+		let starts_header = !in_string
+			&& scanner.depth == 0
+			&& (trimmed.starts_with("while ")
+				|| trimmed.starts_with("while(")
+				|| trimmed.starts_with("for ")
+				|| trimmed.starts_with("async for "));
+
+		if pending_body && !in_string && !trimmed.is_empty() && !trimmed.starts_with('#') {
+			let indent = &line[..line.len() - trimmed.len()];
+			result.push(format!("{}{}()", indent, TICK_FUNCTION));
+			line_map.push(line_idx + 1);
+			pending_body = false;
+		}
+
+		let colon = scanner.scan(line);
+
+		if starts_header || pending_header {
+			match colon {
+				Some(colon) => {
+					pending_header = false;
+					let body = line[colon + 1..].trim();
+
+					if body.is_empty() || body.starts_with('#') {
+						pending_body = true;
+						result.push(line.to_string());
+					} else {
+						result.push(format!(
+							"{} {}(); {}",
+							&line[..=colon],
+							TICK_FUNCTION,
+							line[colon + 1..].trim_start()
+						));
+					}
+				}
+				None => {
+					pending_header = true;
+					result.push(line.to_string());
+				}
+			}
+		} else {
+			result.push(line.to_string());
+		}
+
+		line_map.resize(result.len(), line_idx + 1);
+	}
+
+	InstrumentedCode {
+		code: result.join("\n"),
+		line_map,
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	#[test]
+	fn blocks() {
+		let instrumented = instrument_loops(
+			"x = 0\nwhile True:\n\tx += 1\n\tfor i in range(3):  # comment\n\n\t\tx -= 1\nreturn x",
+		);
+
+		assert_eq!(
+			instrumented.code,
+			"x = 0\nwhile True:\n\t__materia_tick()\n\tx += 1\n\tfor i in range(3):  # comment\n\n\t\t__materia_tick()\n\t\tx -= 1\nreturn x"
+		);
+		assert_eq!(
+			(1..=10)
+				.map(|line| instrumented.original_line(line))
+				.collect::<Vec<_>>(),
+			vec![Some(1), Some(2), Some(3), Some(3), Some(4), Some(5), Some(6), Some(6), Some(7), None]
+		);
+	}
+
+	#[test]
+	fn inline_and_multiline() {
+		assert_eq!(
+			instrument_loops("while True: pass").code,
+			"while True: __materia_tick(); pass"
+		);
+		assert_eq!(
+			instrument_loops("for k in {'a': 1,\n\t\t'b': 2}:\n\tprint(k)").code,
+			"for k in {'a': 1,\n\t\t'b': 2}:\n\t__materia_tick()\n\tprint(k)"
+		);
+		assert_eq!(
+			instrument_loops("while (n := n - 1) > 0:\n\tpass").code,
+			"while (n := n - 1) > 0:\n\t__materia_tick()\n\tpass"
+		);
+	}
+
+	#[test]
+	fn comprehensions() {
+		for (code, instrumented) in [
+			("[x for x in xs]", "[x for x in xs if not __materia_tick()]"),
+			("{k: v for k, v in d.items() if v > 0}", "{k: v for k, v in d.items() if not __materia_tick() and v > 0}"),
+			("sum([x for x in range(3)])", "sum([x for x in range(3) if not __materia_tick()])"),
+			("[[y for y in x] for x in xs]", "[[y for y in x if not __materia_tick()] for x in xs if not __materia_tick()]"),
+			("[a if b else c for\n\tx in xs\n]", "[a if b else c for\n\tx in xs\n if not __materia_tick()]"),
+			("f(x in xs, [1, 2])", "f(x in xs, [1, 2])"),
+			("['for x in y' for y in ys]  # [x for x in y]", "['for x in y' for y in ys if not __materia_tick()]  # [x for x in y]"),
+		] {
+			assert_eq!(instrument_loops(code).code, instrumented);
+			assert_eq!(strip_ticks(instrumented), code);
+		}
+
+		assert_eq!(
+			instrument_loops("for x in [y for y in ys]:\n\tpass").code,
+			"for x in [y for y in ys if not __materia_tick()]:\n\t__materia_tick()\n\tpass"
+		);
+	}
+
+	#[test]
+	fn strings_are_left_alone() {
+		let code = "s = \"\"\"\nwhile True:\n\"\"\"\nt = 'for x in y:'\nreturn s";
+		assert_eq!(instrument_loops(code).code, code);
+	}
+}
@@ -1,8 +1,21 @@
-use std::sync::LazyLock;
+use std::{collections::HashMap, cmp::Ordering, sync::LazyLock};
+
+use chrono::{NaiveDate, NaiveDateTime};
+use pocketpy_sys::*;
+use uuid::Uuid;
+
+use crate::{
+	spytvalue,
+	tag::{format_date_time, format_duration, TagValue},
+	task::Task,
+};
 
 use super::{
-	badge::{BadgeList, BadgeType},
-	PocketPyScript,
+	badge::{BadgeList, BadgeParams, BadgeType},
+	params::param_args,
+	preview::PreviewKind,
+	value::{AnyIntoPocketPyValue, IntoPocketPyValue},
+	PocketPyScript, PocketPyScriptError,
 };
 
 pub static DEFAULT_SORTING_SCRIPT: LazyLock<PocketPyScript> = LazyLock::new(|| PocketPyScript {
@@ -30,6 +43,295 @@ impl BadgeType for SortingBadgeType {
 	fn get_session_badge_list_mut(session: &mut crate::session::Session) -> &mut Vec<String> {
 		&mut session.set_sortings
 	}
+
+	fn get_session_badge_params(session: &crate::session::Session) -> &BadgeParams {
+		&session.sorting_params
+	}
+
+	fn get_session_badge_params_mut(session: &mut crate::session::Session) -> &mut BadgeParams {
+		&mut session.sorting_params
+	}
+
+	fn preview_kind() -> Option<PreviewKind> {
+		Some(PreviewKind::Sorting)
+	}
+
+	fn get_session_descending(session: &crate::session::Session) -> Option<&Vec<String>> {
+		Some(&session.descending_sortings)
+	}
+
+	fn get_session_descending_mut(
+		session: &mut crate::session::Session,
+	) -> Option<&mut Vec<String>> {
+		Some(&mut session.descending_sortings)
+	}
 }
 
 pub type SortingList = BadgeList<SortingBadgeType>;
+
+/// The key a sorting script returns for a task.
+///
+/// Keys of different types are ordered numbers, then durations, dates, date-times, texts and
+/// tuples.
+#[derive(Debug, Clone, PartialEq)]
+pub enum SortKey {
+	/// Integers, floats and booleans
+	Number(f64),
+	/// In seconds
+	Duration(i64),
+	Date(NaiveDate),
+	DateTime(NaiveDateTime),
+	Text(String),
+	/// Compared element by element, a shorter tuple first when one starts the other
+	Tuple(Vec<SortKey>),
+	/// `None`, and numbers that are not a number, sorted after everything else
+	None,
+}
+
+impl SortKey {
+	const NONE_RANK: u8 = 6;
+
+	fn rank(&self) -> u8 {
+		match self {
+			Self::Number(n) if n.is_nan() => Self::NONE_RANK,
+			Self::Number(_) => 0,
+			Self::Duration(_) => 1,
+			Self::Date(_) => 2,
+			Self::DateTime(_) => 3,
+			Self::Text(_) => 4,
+			Self::Tuple(_) => 5,
+			Self::None => Self::NONE_RANK,
+		}
+	}
+
+	fn is_none(&self) -> bool {
+		self.rank() == Self::NONE_RANK
+	}
+
+	/// Texts are compared ignoring case first, so that sorting by name is alphabetical.
+	pub fn compare(&self, other: &Self) -> Ordering {
+		self.compare_directed(other, false)
+	}
+
+	/// Like `compare`, reversed when `descending`, except that `None` keys stay last, also as
+	/// elements of tuples.
+	pub fn compare_directed(&self, other: &Self, descending: bool) -> Ordering {
+		let directed = |ordering: Ordering| if descending { ordering.reverse() } else { ordering };
+
+		match (self, other) {
+			_ if self.is_none() || other.is_none() => self.rank().cmp(&other.rank()),
+			(Self::Number(a), Self::Number(b)) => directed(a.total_cmp(b)),
+			(Self::Duration(a), Self::Duration(b)) => directed(a.cmp(b)),
+			(Self::Date(a), Self::Date(b)) => directed(a.cmp(b)),
+			(Self::DateTime(a), Self::DateTime(b)) => directed(a.cmp(b)),
+			(Self::Text(a), Self::Text(b)) => directed(
+				a.to_lowercase()
+					.cmp(&b.to_lowercase())
+					.then_with(|| a.cmp(b)),
+			),
+			(Self::Tuple(a), Self::Tuple(b)) => a
+				.iter()
+				.zip(b.iter())
+				.map(|(a, b)| a.compare_directed(b, descending))
+				.find(|ordering| ordering.is_ne())
+				.unwrap_or_else(|| directed(a.len().cmp(&b.len()))),
+			_ => directed(self.rank().cmp(&other.rank())),
+		}
+	}
+}
+
+impl std::fmt::Display for SortKey {
+	fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+		match self {
+			Self::Number(n) => write!(f, "{:.2}", n),
+			Self::Duration(seconds) => write!(f, "{}", format_duration(*seconds)),
+			Self::Date(date) => write!(f, "{}", date),
+			Self::DateTime(date_time) => write!(f, "{}", format_date_time(date_time)),
+			Self::Text(text) => write!(f, "{:?}", text),
+			Self::Tuple(keys) => write!(
+				f,
+				"({})",
+				keys.iter()
+					.map(ToString::to_string)
+					.collect::<Vec<_>>()
+					.join(", ")
+			),
+			Self::None => write!(f, "None"),
+		}
+	}
+}
+
+/// A task given to sorting scripts, with its place among its siblings in the manual order of the
+/// view as a `position` attribute.
+struct PositionedTask {
+	task: Task,
+	position: i64,
+}
+
+impl IntoPocketPyValue for PositionedTask {
+	fn into_pocketpy_value(&self, out: *mut py_TValue) {
+		self.task.into_pocketpy_value(out);
+
+		unsafe {
+			spytvalue!(r0);
+
+			py_newint(r0, self.position);
+			py_setdict(out, py_name(c"position".as_ptr()), r0);
+		}
+	}
+
+	fn from_pocketpy_value_ptr(value: *mut py_TValue) -> Result<Self, PocketPyScriptError>
+	where
+		Self: Sized,
+	{
+		unsafe {
+			let position = py_getdict(value, py_name(c"position".as_ptr()));
+
+			Ok(Self {
+				task: Task::from_pocketpy_value_ptr(value)?,
+				position: if position.is_null() {
+					0
+				} else {
+					i64::from_pocketpy_value_ptr(position)?
+				},
+			})
+		}
+	}
+}
+
+/// Runs a sorting script on every task, returns the key of each of them.
+///
+/// `positions` are the places of the tasks among their siblings in the manual order, see
+/// [`crate::views::manual_positions`].
+pub fn run_sorting_script(
+	script: &PocketPyScript,
+	tasks: &[&Task],
+	positions: &HashMap<Uuid, usize>,
+	params: Vec<(String, TagValue)>,
+) -> Result<Vec<SortKey>, PocketPyScriptError> {
+	script.execute_function_for::<SortKey>(
+		crate::app::script_lock(),
+		script.name.as_str(),
+		[(
+			"task",
+			tasks
+				.iter()
+				.map(|task| {
+					Box::new(PositionedTask {
+						task: (*task).clone(),
+						position: positions.get(task.get_uuid()).copied().unwrap_or_default() as i64,
+					}) as AnyIntoPocketPyValue
+				})
+				.collect(),
+		)]
+		.into_iter()
+		.map(|(arg_name, values)| (String::from(arg_name), values))
+		.chain(param_args(params, tasks.len())),
+	)
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use crate::scripts::PocketPyLock;
+
+	#[test]
+	fn sort_keys() {
+		let date = |day| SortKey::Date(NaiveDate::from_ymd_opt(2024, 1, day).unwrap());
+		let text = |text: &str| SortKey::Text(text.to_string());
+		let date_time = |hour| SortKey::DateTime(NaiveDate::from_ymd_opt(2024, 1, 1).unwrap().and_hms_opt(hour, 0, 0).unwrap());
+
+		let mut keys = vec![
+			SortKey::None,
+			date_time(12),
+			SortKey::Duration(60),
+			text("banana"),
+			SortKey::Tuple(vec![SortKey::Number(1.0), text("b")]),
+			date(2),
+			SortKey::Number(f64::NAN),
+			text("Apple"),
+			SortKey::Number(f64::INFINITY),
+			SortKey::Tuple(vec![SortKey::Number(1.0)]),
+			date(1),
+			SortKey::Duration(-30),
+			date_time(9),
+			SortKey::Number(-3.0),
+		];
+		keys.sort_by(SortKey::compare);
+
+		assert_eq!(
+			keys[..12],
+			[
+				SortKey::Number(-3.0),
+				SortKey::Number(f64::INFINITY),
+				SortKey::Duration(-30),
+				SortKey::Duration(60),
+				date(1),
+				date(2),
+				date_time(9),
+				date_time(12),
+				text("Apple"),
+				text("banana"),
+				SortKey::Tuple(vec![SortKey::Number(1.0)]),
+				SortKey::Tuple(vec![SortKey::Number(1.0), text("b")]),
+			]
+		);
+		assert!(keys[12..].iter().all(SortKey::is_none));
+
+		assert_eq!(text("a").compare_directed(&text("b"), true), Ordering::Greater);
+		assert_eq!(SortKey::None.compare_directed(&text("b"), true), Ordering::Greater);
+		assert_eq!(SortKey::Duration(1).compare_directed(&SortKey::Duration(2), true), Ordering::Greater);
+		assert_eq!(date_time(9).compare_directed(&date_time(12), true), Ordering::Greater);
+
+		let tuple = |second| SortKey::Tuple(vec![SortKey::Number(1.0), second]);
+		let mut keys = vec![tuple(SortKey::Number(2.0)), tuple(SortKey::None), tuple(SortKey::Number(3.0))];
+		keys.sort_by(|a, b| a.compare_directed(b, true));
+
+		assert_eq!(
+			keys,
+			[tuple(SortKey::Number(3.0)), tuple(SortKey::Number(2.0)), tuple(SortKey::None)]
+		);
+	}
+
+	#[test]
+	fn returned_keys() {
+		let lock = PocketPyLock::new();
+
+		let keys = PocketPyScript {
+			name: "test_sort_keys".to_string(),
+			code: "if x == 0:\n\treturn None\nif x == 1:\n\treturn ('a', Date(2024, 1, 2), True)\nif x == 2:\n\treturn (DateTime(2024, 1, 2, 9, 30), Duration(90))\nreturn 'b'"
+				.to_string(),
+		}
+		.execute_function_for::<SortKey>(
+			lock.lock(),
+			"sort_keys",
+			[(
+				"x",
+				vec![
+					Box::new(0i64) as AnyIntoPocketPyValue,
+					Box::new(1i64),
+					Box::new(2i64),
+					Box::new(3i64),
+				],
+			)],
+		)
+		.unwrap();
+
+		assert_eq!(
+			keys,
+			vec![
+				SortKey::None,
+				SortKey::Tuple(vec![
+					SortKey::Text(String::from("a")),
+					SortKey::Date(NaiveDate::from_ymd_opt(2024, 1, 2).unwrap()),
+					SortKey::Number(1.0),
+				]),
+				SortKey::Tuple(vec![
+					SortKey::DateTime(NaiveDate::from_ymd_opt(2024, 1, 2).unwrap().and_hms_opt(9, 30, 0).unwrap()),
+					SortKey::Duration(90),
+				]),
+				SortKey::Text(String::from("b")),
+			]
+		);
+	}
+}
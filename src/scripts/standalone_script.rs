@@ -1,8 +1,7 @@
 use std::sync::LazyLock;
 
-use crate::toast_error;
 
-use super::{badge::BadgeType, list::ScriptEditorStateKind, PocketPyScript};
+use super::{badge::{BadgeParams, BadgeType}, list::ScriptEditorStateKind, PocketPyScript};
 
 pub static DEFAULT_STANDALONE_SCRIPT: LazyLock<PocketPyScript> = LazyLock::new(|| PocketPyScript {
 	name: "new_script".to_string(),
@@ -30,6 +29,14 @@ impl BadgeType for StandaloneScriptBadgeType {
 		unimplemented!("Standalone scripts don't have badges")
 	}
 
+	fn get_session_badge_params(_session: &crate::session::Session) -> &BadgeParams {
+		unimplemented!("Standalone scripts don't have badges")
+	}
+
+	fn get_session_badge_params_mut(_session: &mut crate::session::Session) -> &mut BadgeParams {
+		unimplemented!("Standalone scripts don't have badges")
+	}
+
 	fn draw_ui_titlebar(
 		ui: &mut egui::Ui,
 		state: ScriptEditorStateKind,
@@ -37,10 +44,7 @@ impl BadgeType for StandaloneScriptBadgeType {
 	) {
 		if let ScriptEditorStateKind::DisplayMode = state {
 			if ui.button("Run").clicked() {
-				if let Err(e) = script.execute_function::<()>(crate::app::script_lock(), "run", [])
-				{
-					toast_error!("Error running script: {}", e);
-				}
+				crate::app::push_script_to_waitlist(script.name.clone());
 			}
 		}
 	}
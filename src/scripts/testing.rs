@@ -0,0 +1,267 @@
+use std::{collections::HashMap, ffi::CString, path::Path, ptr::null_mut, time::Duration};
+
+use pocketpy_sys::*;
+
+use crate::settings::Settings;
+
+use super::{
+	badge::BadgeType,
+	console,
+	decorator::DecoratorBadgeType,
+	filter::FilterBadgeType,
+	params::parse_params,
+	sandbox::{self, InstrumentedCode, Interruption},
+	sorting::SortingBadgeType,
+	value::IntoPocketPyValue,
+	PocketPyLock, PocketPyScript, PocketPyScriptError, WrappedScript,
+};
+
+/// Functions of a test file starting with this are run as tests.
+const TEST_PREFIX: &str = "test_";
+
+/// Lines of the code executed for the tests, by file name, to remap tracebacks.
+#[derive(Default)]
+struct LineMaps {
+	scripts: HashMap<String, WrappedScript>,
+	test_files: HashMap<String, InstrumentedCode>,
+}
+
+impl LineMaps {
+	fn original_line(&self, file: &str, line: usize) -> Option<usize> {
+		match self.scripts.get(file) {
+			Some(wrapped) => wrapped.original_line(line),
+			None => self.test_files.get(file)?.original_line(line),
+		}
+	}
+}
+
+/// Name under which a script can be called from tests, like `filter_high_priority`.
+fn script_function_name(prefix: &str, script_name: &str) -> String {
+	format!(
+		"{}_{}",
+		prefix,
+		script_name
+			.chars()
+			.map(|c| if c.is_ascii_alphanumeric() { c } else { '_' })
+			.collect::<String>()
+	)
+}
+
+fn load_scripts(path: &Path) -> Result<Vec<PocketPyScript>, PocketPyScriptError> {
+	let mut scripts = Vec::new();
+
+	for entry in std::fs::read_dir(path)? {
+		let path = entry?.path();
+
+		if path.extension().is_some_and(|extension| extension == "py") {
+			scripts.push(PocketPyScript::load(path)?);
+		}
+	}
+
+	scripts.sort_by(|a, b| a.name.cmp(&b.name));
+	Ok(scripts)
+}
+
+/// Defines every script of a type as a function, with its parameters as keyword arguments.
+/// PocketPy only allows literals as default arguments, so they default to `None`, which is then
+/// replaced by the declared default.
+unsafe fn define_scripts<T: BadgeType>(
+	prefix: &str,
+	fixed_args: &str,
+	line_maps: &mut LineMaps,
+) -> Result<(), PocketPyScriptError> {
+	for script in load_scripts(T::get_path()?)? {
+		let function_name = script_function_name(prefix, script.name.as_str());
+		let mut signature = String::from(fixed_args);
+		let mut prologue = Vec::new();
+
+		for param in parse_params(script.code.as_str()) {
+			let default_name = format!("__materia_default_{}_{}", function_name, param.name);
+			let default_name_c = CString::new(default_name.as_str()).expect("names are identifiers");
+
+			py_pushnil();
+			param.default.into_pocketpy_value(py_peek(-1));
+			py_setglobal(py_name(default_name_c.as_ptr()), py_peek(-1));
+			py_pop();
+
+			signature.push_str(format!(", {}=None", param.name).as_str());
+			prologue.push(format!(
+				"if {} is None: {} = {}",
+				param.name, param.name, default_name
+			));
+		}
+
+		let wrapped =
+			script.wrap_with_prologue(function_name.as_str(), signature.as_str(), &prologue);
+		let file_name = CString::new(function_name.as_str()).expect("names are identifiers");
+
+		if !py_exec(
+			wrapped.code.as_ptr(),
+			file_name.as_ptr(),
+			py_CompileMode_EXEC_MODE,
+			null_mut(),
+		) {
+			line_maps.scripts.insert(function_name, wrapped);
+			return Err(PocketPyScriptError::PocketPyError(console::take_traceback(|file, line| {
+				line_maps.original_line(file, line)
+			})));
+		}
+
+		line_maps.scripts.insert(function_name, wrapped);
+	}
+
+	Ok(())
+}
+
+/// Runs a function of a test file, within the script time limit.
+unsafe fn run_test(name: &str, line_maps: &LineMaps) -> Result<(), String> {
+	let call = CString::new(format!("{}()", name)).expect("names are identifiers");
+
+	console::set_source(name);
+	sandbox::start(Some(Duration::from_millis(
+		Settings::get().script_time_limit_ms,
+	)));
+
+	if py_exec(
+		call.as_ptr(),
+		c"<test>".as_ptr(),
+		py_CompileMode_EXEC_MODE,
+		null_mut(),
+	) {
+		return Ok(());
+	}
+
+	match sandbox::take_interruption() {
+		Some(interruption) => {
+			py_clearexc(null_mut());
+
+			Err(match interruption {
+				Interruption::Timeout(limit) => PocketPyScriptError::Timeout(limit),
+				Interruption::Cancelled => PocketPyScriptError::Cancelled,
+			}
+			.to_string())
+		}
+		None => Err(console::take_traceback(|file, line| {
+			line_maps.original_line(file, line)
+		})),
+	}
+}
+
+/// Runs the `test_` functions of the python files in the `script_tests` directory, and prints
+/// a report. In these files, filter, sorting and decorator scripts are available as
+/// `filter_<name>`, `sorting_<name>` and `decorator_<name>` functions. Returns whether every test
+/// passed.
+pub fn run_script_tests() -> bool {
+	let data_dir = match crate::data_dir() {
+		Ok(data_dir) => data_dir,
+		Err(e) => {
+			println!("Could not open the data directory: {}", e);
+			return false;
+		}
+	};
+
+	let lock = PocketPyLock::new();
+	let _guard = lock.lock();
+	let mut line_maps = LineMaps::default();
+
+	if let Err(e) = unsafe {
+		define_scripts::<FilterBadgeType>("filter", "task, parent=None", &mut line_maps)
+			.and_then(|_| define_scripts::<SortingBadgeType>("sorting", "task", &mut line_maps))
+			.and_then(|_| define_scripts::<DecoratorBadgeType>("decorator", "task", &mut line_maps))
+	} {
+		println!("Could not load scripts: {}", e);
+		return false;
+	}
+
+	let test_files = match load_scripts(data_dir.script_tests()) {
+		Ok(test_files) => test_files,
+		Err(e) => {
+			println!("Could not load script tests: {}", e);
+			return false;
+		}
+	};
+
+	let mut passed = 0;
+	let mut failures: Vec<(String, String)> = Vec::new();
+
+	println!("running script tests from {}", data_dir.script_tests().display());
+
+	for test_file in test_files {
+		let instrumented = sandbox::instrument_loops(test_file.code.as_str());
+		let file_name = format!("{}.py", test_file.name);
+		let file_name_c = CString::new(file_name.as_str()).expect("file names have no 0 byte");
+		let code = match CString::new(instrumented.code.as_str()) {
+			Ok(code) => code,
+			Err(_) => {
+				failures.push((file_name, String::from("File contains a 0 byte")));
+				continue;
+			}
+		};
+
+		line_maps.test_files.insert(file_name.clone(), instrumented);
+
+		if !unsafe {
+			py_exec(
+				code.as_ptr(),
+				file_name_c.as_ptr(),
+				py_CompileMode_EXEC_MODE,
+				null_mut(),
+			)
+		} {
+			println!("test {} ... FAILED", file_name);
+			failures.push((
+				file_name,
+				unsafe { console::take_traceback(|file, line| line_maps.original_line(file, line)) },
+			));
+			continue;
+		}
+
+		let test_names = test_file.code.lines().filter_map(|line| {
+			let name = line.strip_prefix("def ")?.split('(').next()?.trim();
+			name.starts_with(TEST_PREFIX).then(|| name.to_string())
+		});
+
+		for test_name in test_names {
+			let full_name = format!("{}::{}", test_file.name, test_name);
+
+			match unsafe { run_test(test_name.as_str(), &line_maps) } {
+				Ok(()) => {
+					println!("test {} ... ok", full_name);
+					passed += 1;
+				}
+				Err(e) => {
+					println!("test {} ... FAILED", full_name);
+					failures.push((full_name, e));
+				}
+			}
+		}
+	}
+
+	if !failures.is_empty() {
+		println!("\nfailures:");
+
+		for (name, error) in failures.iter() {
+			println!("\n---- {} ----\n{}", name, error);
+		}
+	}
+
+	println!(
+		"\ntest result: {}. {} passed; {} failed",
+		if failures.is_empty() { "ok" } else { "FAILED" },
+		passed,
+		failures.len()
+	);
+
+	failures.is_empty()
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	#[test]
+	fn function_names() {
+		assert_eq!(script_function_name("filter", "high_priority"), "filter_high_priority");
+		assert_eq!(script_function_name("sorting", "due date-2"), "sorting_due_date_2");
+	}
+}
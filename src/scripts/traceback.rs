@@ -0,0 +1,69 @@
+use super::sandbox;
+
+const FRAME_PREFIX: &str = "  File \"";
+const LINE_SEPARATOR: &str = "\", line ";
+
+/// Splits a frame line like `  File "name", line 3, in f` into its file, line and the rest.
+fn parse_frame(line: &str) -> Option<(&str, usize, &str)> {
+	let rest = line.strip_prefix(FRAME_PREFIX)?;
+	let (file, rest) = rest.split_once(LINE_SEPARATOR)?;
+	let digits = rest.find(|c: char| !c.is_ascii_digit()).unwrap_or(rest.len());
+
+	Some((file, rest[..digits].parse().ok()?, &rest[digits..]))
+}
+
+/// Rewrites the line numbers of a traceback with `map(file, line)`, and removes the tick calls
+/// inserted in the quoted source. Frames that `map` doesn't know are left as is.
+pub(super) fn remap(traceback: &str, map: impl Fn(&str, usize) -> Option<usize>) -> String {
+	traceback
+		.lines()
+		.map(|line| match parse_frame(line) {
+			Some((file, line_number, rest)) => match map(file, line_number) {
+				Some(mapped) => format!("{}{}{}{}{}", FRAME_PREFIX, file, LINE_SEPARATOR, mapped, rest),
+				None => line.to_string(),
+			},
+			None => sandbox::strip_ticks(line),
+		})
+		.collect::<Vec<String>>()
+		.join("\n")
+}
+
+/// Line of the innermost frame of a traceback that is in `file`.
+pub fn error_line(traceback: &str, file: &str) -> Option<usize> {
+	traceback
+		.lines()
+		.filter_map(parse_frame)
+		.rev()
+		.find(|(frame_file, _, _)| *frame_file == file)
+		.map(|(_, line, _)| line)
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	#[test]
+	fn remap_lines() {
+		let traceback = "Traceback (most recent call last):\n  \
+			File \"tests\", line 4, in test_a\n    \
+			assert filter_a(t)\n  \
+			File \"a\", line 3, in filter_a\n    \
+			while x: __materia_tick(); x = y\n\
+			NameError: name 'y' is not defined";
+
+		let remapped = remap(traceback, |file, line| (file == "a").then(|| line - 1));
+
+		assert_eq!(
+			remapped,
+			"Traceback (most recent call last):\n  \
+			File \"tests\", line 4, in test_a\n    \
+			assert filter_a(t)\n  \
+			File \"a\", line 2, in filter_a\n    \
+			while x: x = y\n\
+			NameError: name 'y' is not defined"
+		);
+		assert_eq!(error_line(&remapped, "a"), Some(2));
+		assert_eq!(error_line(&remapped, "tests"), Some(4));
+		assert_eq!(error_line(&remapped, "b"), None);
+	}
+}
@@ -1,8 +1,10 @@
-use crate::{settings::Settings, toast_error};
+use crate::{settings::Settings, toast_error, views::ManualOrders};
 
 use super::{
 	badge::BadgeType,
 	list::{ScriptEditor, ScriptEditorDeletionState, ScriptEditorStateKind},
+	diagnostics::mark_line,
+	preview::ScriptPreview,
 };
 
 pub struct ScriptWidget<'script, T> {
@@ -10,14 +12,19 @@ pub struct ScriptWidget<'script, T> {
 }
 
 impl<'script, T: BadgeType> ScriptWidget<'script, T> {
-	pub fn show(self, ui: &mut egui::Ui) -> egui::Response {
+	/// Sorting previews use the manual orders of the current view for `task.position`.
+	pub fn show(self, ui: &mut egui::Ui, manual_orders: &ManualOrders) -> egui::Response {
 		let mut switch_to_display = false;
 		let mut mark_for_delete = false;
+		let mut to_preview = None;
+		let mut clear_diagnostic = false;
 
 		let response = ui
 			.group(|ui| {
 				ui.vertical(|ui| {
 					let theme = egui_extras::syntax_highlighting::CodeTheme::from_style(ui.style());
+					let error_line = self.script.diagnostic.as_ref().and_then(|d| d.line);
+					let error_color = ui.visuals().error_fg_color;
 
 					let mut layouter = |ui: &egui::Ui, string: &str, wrap_width: f32| {
 						let mut layout_job = egui_extras::syntax_highlighting::highlight(
@@ -27,6 +34,11 @@ impl<'script, T: BadgeType> ScriptWidget<'script, T> {
 							"py",
 						);
 						layout_job.wrap.max_width = wrap_width;
+
+						if let Some(line) = error_line {
+							mark_line(&mut layout_job, line, error_color);
+						}
+
 						ui.fonts(|f| f.layout_job(layout_job))
 					};
 
@@ -63,17 +75,26 @@ impl<'script, T: BadgeType> ScriptWidget<'script, T> {
 									ScriptEditorStateKind::EditMode,
 									&mut self.script.script,
 								);
+
+								if preview_button::<T>(ui) {
+									to_preview = Some(edited_script.clone());
+								}
 							});
 
 							ui.with_layout(
 								egui::Layout::left_to_right(egui::Align::LEFT)
 									.with_main_justify(true),
 								|ui| {
-									ui.add(
-										egui::TextEdit::multiline(&mut edited_script.code)
-											.code_editor()
-											.layouter(&mut layouter),
-									);
+									if ui
+										.add(
+											egui::TextEdit::multiline(&mut edited_script.code)
+												.code_editor()
+												.layouter(&mut layouter),
+										)
+										.changed()
+									{
+										clear_diagnostic = true;
+									}
 								},
 							);
 						}
@@ -99,6 +120,10 @@ impl<'script, T: BadgeType> ScriptWidget<'script, T> {
 									ScriptEditorStateKind::DisplayMode,
 									&mut self.script.script,
 								);
+
+								if preview_button::<T>(ui) {
+									to_preview = Some(self.script.script.clone());
+								}
 							});
 
 							ui.separator();
@@ -118,10 +143,44 @@ impl<'script, T: BadgeType> ScriptWidget<'script, T> {
 							);
 						}
 					}
+
+					if let Some(diagnostic) = self.script.diagnostic.as_ref() {
+						ui.colored_label(error_color, format!("⚠ {}", diagnostic));
+					}
+
+					let mut close_preview = false;
+
+					if let Some(preview) = self.script.preview.as_ref() {
+						ui.separator();
+
+						ui.horizontal(|ui| {
+							ui.strong("Preview");
+
+							if ui.small_button("✖").clicked() {
+								close_preview = true;
+							}
+						});
+
+						ui.push_id(self.script.script.name.as_str(), |ui| preview.show(ui));
+					}
+
+					if close_preview {
+						self.script.preview = None;
+					}
 				});
 			})
 			.response;
 
+		if clear_diagnostic {
+			self.script.diagnostic = None;
+		}
+
+		if let (Some(script), Some(kind)) = (to_preview, T::preview_kind()) {
+			let preview = ScriptPreview::run::<T>(kind, &script, manual_orders);
+			self.script.diagnostic = preview.diagnostic();
+			self.script.preview = Some(preview);
+		}
+
 		if mark_for_delete {
 			self.script.deletion_state = ScriptEditorDeletionState::Pending;
 		} else if switch_to_display {
@@ -133,3 +192,12 @@ impl<'script, T: BadgeType> ScriptWidget<'script, T> {
 		response
 	}
 }
+
+/// Shows the preview button if scripts of this type can be previewed, returns whether it was clicked.
+fn preview_button<T: BadgeType>(ui: &mut egui::Ui) -> bool {
+	T::preview_kind().is_some()
+		&& ui
+			.button("👁")
+			.on_hover_text("Preview on the current tasks, without enabling the script")
+			.clicked()
+}
@@ -9,11 +9,16 @@ use uuid::Uuid;
 use crate::{
 	spytvalue,
 	tag::{Tag, TagValue},
-	task::Task,
+	task::{attachment::Attachment, Note, Task},
 };
 
 use super::{
-	py_bindings::{naive_date_from_py_date, new_py_date},
+	decorator::Decoration,
+	py_bindings::{
+		naive_date_from_py_date, naive_date_time_from_py, new_py_date, new_py_date_time,
+		new_py_duration,
+	},
+	sorting::SortKey,
 	PocketPyScriptError,
 };
 
@@ -48,6 +53,28 @@ impl IntoPocketPyValue for () {
 	}
 }
 
+impl<T: IntoPocketPyValue> IntoPocketPyValue for Option<T> {
+	fn into_pocketpy_value(&self, out: *mut py_TValue) {
+		match self {
+			Some(value) => value.into_pocketpy_value(out),
+			None => unsafe { py_newnone(out) },
+		}
+	}
+
+	fn from_pocketpy_value_ptr(value: *mut py_TValue) -> Result<Self, PocketPyScriptError>
+	where
+		Self: Sized,
+	{
+		unsafe {
+			if py_isidentical(value, py_None) {
+				Ok(None)
+			} else {
+				T::from_pocketpy_value_ptr(value).map(Some)
+			}
+		}
+	}
+}
+
 impl IntoPocketPyValue for i64 {
 	fn into_pocketpy_value(&self, out: *mut py_TValue) {
 		unsafe {
@@ -150,6 +177,40 @@ impl IntoPocketPyValue for Task {
 			}
 
 			py_setdict(out, py_name(c"tags".as_ptr()), r0);
+
+			py_newlistn(r0, self.attachments.len() as i32);
+
+			for (i, attachment) in self.attachments.iter().enumerate() {
+				spytvalue!(attachment_val);
+				py_newstr(
+					attachment_val,
+					CString::new(attachment.to_string())
+						.unwrap_or_default()
+						.as_ptr(),
+				);
+				py_list_setitem(r0, i as i32, attachment_val);
+			}
+
+			py_setdict(out, py_name(c"attachments".as_ptr()), r0);
+
+			py_newlistn(r0, self.notes.len() as i32);
+
+			for (i, note) in self.notes.iter().enumerate() {
+				spytvalue!(note_val);
+				spytvalue!(item_val);
+
+				py_newtuple(note_val, 2);
+				TagValue::DateTime(note.time).into_pocketpy_value(item_val);
+				py_tuple_setitem(note_val, 0, item_val);
+				TagValue::Text(note.text.clone()).into_pocketpy_value(item_val);
+				py_tuple_setitem(note_val, 1, item_val);
+				py_list_setitem(r0, i as i32, note_val);
+			}
+
+			py_setdict(out, py_name(c"notes".as_ptr()), r0);
+
+			self.get_uuid().into_pocketpy_value(r0);
+			py_setdict(out, py_name(c"ref".as_ptr()), r0);
 		}
 	}
 
@@ -171,7 +232,12 @@ impl IntoPocketPyValue for Task {
 				.to_string_lossy()
 				.to_string();
 
+				// `py_len` writes to the return value, which `value` may point to, so the lists are
+				// read from the task first
 				let tag_list = py_getdict(value, py_name(c"tags".as_ptr()));
+				let attachment_list = py_getdict(value, py_name(c"attachments".as_ptr()));
+				let note_list = py_getdict(value, py_name(c"notes".as_ptr()));
+
 				py_len(tag_list);
 				let len = py_toint(py_retval()) as i32;
 				task.tags = Vec::with_capacity(len as usize);
@@ -181,6 +247,47 @@ impl IntoPocketPyValue for Task {
 						.push(Tag::from_pocketpy_value_ptr(py_list_getitem(tag_list, i))?);
 				}
 
+				// Paths and URLs, as `str`
+				if !attachment_list.is_null() {
+					py_len(attachment_list);
+					let len = py_toint(py_retval()) as i32;
+
+					for i in 0..len {
+						let item = py_list_getitem(attachment_list, i);
+
+						if !py_istype(item, py_totype(py_getbuiltin(py_name(c"str".as_ptr())))) {
+							return Err(PocketPyScriptError::WrongType);
+						}
+
+						task.attachments.push(Attachment::from_text(
+							CStr::from_ptr(py_tostr(item)).to_string_lossy().as_ref(),
+						));
+					}
+				}
+
+				// `(DateTime, str)` tuples, see `Task::append_notes` for the ones hooks can change
+				if !note_list.is_null() {
+					py_len(note_list);
+					let len = py_toint(py_retval()) as i32;
+
+					for i in 0..len {
+						let item = py_list_getitem(note_list, i);
+
+						if !py_istype(item, py_totype(py_getbuiltin(py_name(c"tuple".as_ptr())))) {
+							return Err(PocketPyScriptError::WrongType);
+						}
+
+						let (TagValue::DateTime(time), TagValue::Text(text)) = (
+							TagValue::from_pocketpy_value_ptr(py_tuple_getitem(item, 0))?,
+							TagValue::from_pocketpy_value_ptr(py_tuple_getitem(item, 1))?,
+						) else {
+							return Err(PocketPyScriptError::WrongType);
+						};
+
+						task.notes.push(Note { time, text });
+					}
+				}
+
 				Ok(task)
 			} else {
 				Err(PocketPyScriptError::WrongType)
@@ -189,6 +296,133 @@ impl IntoPocketPyValue for Task {
 	}
 }
 
+impl IntoPocketPyValue for Decoration {
+	fn into_pocketpy_value(&self, out: *mut py_TValue) {
+		unsafe {
+			spytvalue!(r0);
+
+			py_newtuple(out, 2);
+			TagValue::Text(self.text.clone()).into_pocketpy_value(r0);
+			py_tuple_setitem(out, 0, r0);
+
+			match &self.color {
+				Some(color) => TagValue::Text(color.clone()).into_pocketpy_value(r0),
+				None => py_newnone(r0),
+			}
+
+			py_tuple_setitem(out, 1, r0);
+		}
+	}
+
+	/// Either a `str`, or a `(text, color)` tuple.
+	fn from_pocketpy_value_ptr(value: *mut py_TValue) -> Result<Self, PocketPyScriptError>
+	where
+		Self: Sized,
+	{
+		unsafe {
+			if py_istype(value, py_totype(py_getbuiltin(py_name(c"tuple".as_ptr())))) {
+				if py_tuple_len(value) != 2 {
+					return Err(PocketPyScriptError::WrongType);
+				}
+
+				let TagValue::Text(text) = TagValue::from_pocketpy_value_ptr(py_tuple_getitem(value, 0))? else {
+					return Err(PocketPyScriptError::WrongType);
+				};
+
+				let color = match Option::<TagValue>::from_pocketpy_value_ptr(py_tuple_getitem(value, 1))? {
+					Some(TagValue::Text(color)) => Some(color),
+					None => None,
+					Some(_) => return Err(PocketPyScriptError::WrongType),
+				};
+
+				Ok(Self { text, color })
+			} else {
+				match TagValue::from_pocketpy_value_ptr(value)? {
+					TagValue::Text(text) => Ok(Self { text, color: None }),
+					_ => Err(PocketPyScriptError::WrongType),
+				}
+			}
+		}
+	}
+}
+
+impl IntoPocketPyValue for SortKey {
+	fn into_pocketpy_value(&self, out: *mut py_TValue) {
+		unsafe {
+			spytvalue!(r0);
+
+			match self {
+				SortKey::Number(n) => py_newfloat(out, *n),
+				SortKey::Duration(seconds) => {
+					new_py_duration(out, *seconds);
+				}
+				SortKey::Date(d) => {
+					new_py_date(out, d);
+				}
+				SortKey::DateTime(date_time) => {
+					new_py_date_time(out, date_time);
+				}
+				SortKey::Text(t) => {
+					py_newstr(out, CString::new(t.as_str()).unwrap_or_default().as_ptr())
+				}
+				SortKey::Tuple(keys) => {
+					py_newtuple(out, keys.len() as i32);
+
+					for (i, key) in keys.iter().enumerate() {
+						key.into_pocketpy_value(r0);
+						py_tuple_setitem(out, i as i32, r0);
+					}
+				}
+				SortKey::None => py_newnone(out),
+			}
+		}
+	}
+
+	fn from_pocketpy_value_ptr(value: *mut py_TValue) -> Result<Self, PocketPyScriptError>
+	where
+		Self: Sized,
+	{
+		unsafe {
+			if py_isidentical(value, py_None) {
+				Ok(Self::None)
+			} else if py_istype(value, py_totype(py_getbuiltin(py_name(c"bool".as_ptr())))) {
+				Ok(Self::Number(if py_tobool(value) { 1.0 } else { 0.0 }))
+			} else if py_istype(value, py_totype(py_getbuiltin(py_name(c"int".as_ptr())))) {
+				Ok(Self::Number(py_toint(value) as f64))
+			} else if py_istype(value, py_totype(py_getbuiltin(py_name(c"float".as_ptr())))) {
+				Ok(Self::Number(py_tofloat(value)))
+			} else if py_istype(value, py_totype(py_getglobal(py_name(c"Date".as_ptr())))) {
+				Ok(Self::Date(naive_date_from_py_date(value)?))
+			} else if py_istype(value, py_totype(py_getglobal(py_name(c"DateTime".as_ptr())))) {
+				Ok(Self::DateTime(naive_date_time_from_py(value)?))
+			} else if py_istype(value, py_totype(py_getglobal(py_name(c"Duration".as_ptr())))) {
+				Ok(Self::Duration(py_toint(py_getdict(
+					value,
+					py_name(c"seconds".as_ptr()),
+				))))
+			} else if py_istype(value, py_totype(py_getbuiltin(py_name(c"str".as_ptr())))) {
+				Ok(Self::Text(
+					CStr::from_ptr(py_tostr(value))
+						.to_string_lossy()
+						.to_string(),
+				))
+			} else if py_istype(value, py_totype(py_getbuiltin(py_name(c"tuple".as_ptr())))) {
+				(0..py_tuple_len(value))
+					.map(|i| Self::from_pocketpy_value_ptr(py_tuple_getitem(value, i)))
+					.collect::<Result<Vec<_>, _>>()
+					.map(Self::Tuple)
+			} else if py_istype(value, py_totype(py_getbuiltin(py_name(c"list".as_ptr())))) {
+				(0..py_list_len(value))
+					.map(|i| Self::from_pocketpy_value_ptr(py_list_getitem(value, i)))
+					.collect::<Result<Vec<_>, _>>()
+					.map(Self::Tuple)
+			} else {
+				Err(PocketPyScriptError::WrongType)
+			}
+		}
+	}
+}
+
 impl IntoPocketPyValue for Tag {
 	fn into_pocketpy_value(&self, out: *mut py_TValue) {
 		unsafe {
@@ -255,6 +489,12 @@ impl IntoPocketPyValue for TagValue {
 				TagValue::Date(d) => {
 					new_py_date(out, d);
 				}
+				TagValue::DateTime(date_time) => {
+					new_py_date_time(out, date_time);
+				}
+				TagValue::Duration(seconds) => {
+					new_py_duration(out, *seconds);
+				}
 				TagValue::Text(t) => {
 					py_newstr(out, CString::new(t.as_str()).unwrap_or_default().as_ptr())
 				}
@@ -301,6 +541,13 @@ impl IntoPocketPyValue for TagValue {
 				Ok(Self::Float(py_tofloat(value)))
 			} else if py_istype(value, py_totype(py_getglobal(py_name(c"Date".as_ptr())))) {
 				Ok(Self::Date(naive_date_from_py_date(value)?))
+			} else if py_istype(value, py_totype(py_getglobal(py_name(c"DateTime".as_ptr())))) {
+				Ok(Self::DateTime(naive_date_time_from_py(value)?))
+			} else if py_istype(value, py_totype(py_getglobal(py_name(c"Duration".as_ptr())))) {
+				Ok(Self::Duration(py_toint(py_getdict(
+					value,
+					py_name(c"seconds".as_ptr()),
+				))))
 			} else if py_istype(value, py_totype(py_getbuiltin(py_name(c"str".as_ptr())))) {
 				Ok(Self::Text(
 					CStr::from_ptr(py_tostr(value))
@@ -0,0 +1,322 @@
+use std::collections::HashMap;
+
+use uuid::Uuid;
+
+use crate::{
+	handle_toast_error,
+	ok_cancel_dialog::{OkCancelDialog, OkCancelResult},
+	tag::{suggestions_popup, Tag, TagIndex, TagSchemas},
+	task::list::TaskList,
+	toast_error, toast_success,
+};
+
+/// Tasks selected with ctrl and shift clicks in the task list, to act on all of them at once.
+#[derive(Default)]
+pub struct Selection {
+	/// In the order they were selected
+	tasks: Vec<Uuid>,
+	/// Task a shift click selects from
+	anchor: Option<Uuid>,
+	/// Tag to add, set or remove, as typed
+	tag_text: String,
+	/// Task to move the selected tasks under, `None` to make them root tasks
+	parent: Option<Uuid>,
+	pending_delete: bool,
+}
+
+#[derive(Default)]
+pub struct SelectionResponse {
+	/// Tasks were changed, the display lists need an update
+	pub changed: bool,
+	/// The selected tasks should be moved to the scheduled tasks
+	pub schedule: bool,
+}
+
+impl Selection {
+	pub fn is_empty(&self) -> bool {
+		self.tasks.is_empty()
+	}
+
+	pub fn contains(&self, uuid: &Uuid) -> bool {
+		self.tasks.contains(uuid)
+	}
+
+	pub fn tasks(&self) -> &[Uuid] {
+		&self.tasks
+	}
+
+	pub fn clear(&mut self) {
+		self.tasks.clear();
+		self.anchor = None;
+	}
+
+	pub fn select_all(&mut self, visible: &[Uuid]) {
+		for uuid in visible {
+			if !self.tasks.contains(uuid) {
+				self.tasks.push(*uuid);
+			}
+		}
+	}
+
+	/// Handles a click on a task of the list: ctrl toggles the task, shift selects every visible
+	/// task from the last toggled one. Returns false for clicks without these modifiers, which
+	/// open the task instead.
+	pub fn click(&mut self, uuid: Uuid, modifiers: egui::Modifiers, visible: &[Uuid]) -> bool {
+		if modifiers.shift {
+			let position = |uuid: &Uuid| visible.iter().position(|other| other == uuid);
+
+			match (self.anchor.as_ref().and_then(position), position(&uuid)) {
+				(Some(from), Some(to)) => {
+					self.select_all(&visible[from.min(to)..=from.max(to)]);
+				}
+				_ => {
+					self.select_all(&[uuid]);
+					self.anchor = Some(uuid);
+				}
+			}
+
+			true
+		} else if modifiers.command || modifiers.ctrl {
+			if let Some(i) = self.tasks.iter().position(|other| *other == uuid) {
+				self.tasks.remove(i);
+			} else {
+				self.tasks.push(uuid);
+			}
+
+			self.anchor = Some(uuid);
+			true
+		} else {
+			false
+		}
+	}
+
+	/// Shows the bulk actions on the selected tasks, if there are any.
+	pub fn show(
+		&mut self,
+		ui: &mut egui::Ui,
+		task_list: &mut TaskList,
+		task_names: &HashMap<Uuid, String>,
+		visible: &[Uuid],
+		interactable: &mut bool,
+	) -> SelectionResponse {
+		let mut response = SelectionResponse::default();
+
+		self.tasks.retain(|uuid| task_list.get(uuid).is_some());
+
+		if self.tasks.is_empty() {
+			return response;
+		}
+
+		ui.horizontal_wrapped(|ui| {
+			ui.strong(format!("{} selected", self.tasks.len()));
+
+			if ui.button("Select All Visible").clicked() {
+				self.select_all(visible);
+			}
+
+			if ui.button("Clear Selection").clicked() {
+				self.clear();
+			}
+
+			ui.separator();
+
+			if ui.button("✅ Done").clicked() {
+				match task_list.mark_done(&self.tasks) {
+					Ok(count) => {
+						toast_success!("Marked {} tasks done", count);
+					}
+					Err(e) => {
+						toast_error!("Could not mark tasks done: {}", e);
+					}
+				}
+
+				response.changed = true;
+			}
+
+			if ui
+				.button(egui::RichText::new("🗑 Delete").color(ui.visuals().error_fg_color))
+				.clicked()
+			{
+				self.pending_delete = true;
+			}
+
+			response.schedule = ui
+				.button("🕗 Schedule…")
+				.on_hover_text("Move the tasks and their subtasks to the scheduled tasks")
+				.clicked();
+		});
+
+		ui.horizontal_wrapped(|ui| {
+			ui.label("Tag:");
+
+			let text_response = ui.add(
+				egui::TextEdit::singleline(&mut self.tag_text)
+					.code_editor()
+					.hint_text("name(value)")
+					.desired_width(160.0),
+			);
+
+			if let Some(name) = suggestions_popup(ui, &text_response, || {
+				TagIndex::get().suggest_names(self.tag_text.trim(), 8)
+			}) {
+				self.tag_text = name;
+			}
+
+			let tag = self.tag_text.parse::<Tag>().and_then(|mut tag| {
+				let schemas = TagSchemas::get();
+				schemas.validate(&tag)?;
+				schemas.fill_default(&mut tag);
+				Ok(tag)
+			});
+
+			if let Err(e) = &tag {
+				if !self.tag_text.trim().is_empty() {
+					text_response.on_hover_text(e.to_string());
+				}
+			}
+
+			let tag = tag.ok();
+
+			if ui.add_enabled(tag.is_some(), egui::Button::new("Add")).clicked() {
+				let tag = tag.as_ref().expect("button is enabled");
+
+				match task_list.add_tag(&self.tasks, tag) {
+					Ok(count) => {
+						toast_success!("Added {} to {} tasks", tag.to_string(), count);
+					}
+					Err(e) => {
+						toast_error!("Could not add tag: {}", e);
+					}
+				}
+
+				response.changed = true;
+			}
+
+			if ui
+				.add_enabled(
+					tag.as_ref().is_some_and(|tag| tag.value.is_some()),
+					egui::Button::new("Set Value"),
+				)
+				.on_hover_text("Change the value of the tag with this name, or add it")
+				.clicked()
+			{
+				let tag = tag.as_ref().expect("button is enabled");
+
+				match task_list.set_tag(&self.tasks, tag) {
+					Ok(count) => {
+						toast_success!("Set {} on {} tasks", tag.to_string(), count);
+					}
+					Err(e) => {
+						toast_error!("Could not set tag: {}", e);
+					}
+				}
+
+				response.changed = true;
+			}
+
+			let name = self.tag_text.trim();
+
+			if ui
+				.add_enabled(Tag::is_valid_name(name), egui::Button::new("Remove"))
+				.on_hover_text("Remove every tag with this name")
+				.clicked()
+			{
+				match task_list.remove_tag(&self.tasks, name) {
+					Ok(count) => {
+						toast_success!("Removed {} from {} tasks", name, count);
+					}
+					Err(e) => {
+						toast_error!("Could not remove tag: {}", e);
+					}
+				}
+
+				response.changed = true;
+			}
+
+			ui.separator();
+			ui.label("Move under:");
+
+			let mut parents: Vec<(&Uuid, &String)> = task_names
+				.iter()
+				.filter(|(uuid, _)| !self.tasks.contains(uuid))
+				.collect();
+			parents.sort_by(|a, b| a.1.cmp(b.1));
+
+			egui::ComboBox::from_id_source("selection_parent")
+				.selected_text(
+					self.parent
+						.and_then(|parent| task_names.get(&parent))
+						.map_or("No parent", String::as_str),
+				)
+				.show_ui(ui, |ui| {
+					ui.selectable_value(&mut self.parent, None, "No parent");
+
+					for (uuid, name) in parents {
+						ui.selectable_value(&mut self.parent, Some(*uuid), name.as_str());
+					}
+				});
+
+			if ui.button("Move").clicked() {
+				match task_list.move_under(&self.tasks, self.parent) {
+					Ok(count) => {
+						toast_success!("Moved {} tasks", count);
+					}
+					Err(e) => {
+						toast_error!("Could not move tasks: {}", e);
+					}
+				}
+
+				response.changed = true;
+			}
+		});
+
+		if self.pending_delete {
+			*interactable = false;
+
+			if let Some(result) = OkCancelDialog::default()
+				.with_title(format!("Delete {} tasks?", self.tasks.len()))
+				.with_subtext("Their subtasks are deleted too. You cannot undo this action.")
+				.with_ok_text("Delete")
+				.with_ok_color(ui.style().visuals.error_fg_color)
+				.show(ui.ctx())
+			{
+				*interactable = true;
+				self.pending_delete = false;
+
+				if matches!(result, OkCancelResult::Ok) {
+					handle_toast_error!("Could not delete task: {}", task_list.delete_tasks(&self.tasks));
+					self.clear();
+					response.changed = true;
+				}
+			}
+		}
+
+		response
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	#[test]
+	fn clicks() {
+		let visible: Vec<Uuid> = (0..5).map(|_| Uuid::new_v4()).collect();
+		let mut selection = Selection::default();
+
+		assert!(!selection.click(visible[0], egui::Modifiers::NONE, &visible));
+		assert!(selection.is_empty());
+
+		assert!(selection.click(visible[1], egui::Modifiers::CTRL, &visible));
+		assert!(selection.click(visible[3], egui::Modifiers::SHIFT, &visible));
+		assert_eq!(selection.tasks, visible[1..=3]);
+
+		assert!(selection.click(visible[2], egui::Modifiers::CTRL, &visible));
+		assert!(selection.click(visible[0], egui::Modifiers::SHIFT, &visible));
+		assert_eq!(selection.tasks, [visible[1], visible[3], visible[0], visible[2]]);
+
+		selection.clear();
+		selection.select_all(&visible);
+		assert_eq!(selection.tasks, visible);
+	}
+}
@@ -1,19 +1,29 @@
 use std::{
+	collections::HashMap,
+	path::{Path, PathBuf},
 	str::FromStr,
 	sync::{LazyLock, Mutex, MutexGuard},
 };
 
-use crate::data_dir::DataDirError;
+use crate::{data_dir::DataDirError, tag::TagValue};
 
 static SESSION: LazyLock<Mutex<Session>> =
 	LazyLock::new(|| Mutex::new(Session::load().unwrap_or_default()));
 
-#[derive(Debug, Clone, PartialEq, Eq, serde::Serialize, serde::Deserialize)]
+#[derive(Debug, Clone, PartialEq, serde::Serialize, serde::Deserialize)]
 #[serde(default)]
 pub struct Session {
 	pub last_session: chrono::NaiveDate,
 	pub set_filters: Vec<String>,
 	pub set_sortings: Vec<String>,
+	pub set_decorators: Vec<String>,
+	pub filter_params: HashMap<String, HashMap<String, TagValue>>,
+	pub sorting_params: HashMap<String, HashMap<String, TagValue>>,
+	pub decorator_params: HashMap<String, HashMap<String, TagValue>>,
+	/// Sorting scripts set to descending order
+	pub descending_sortings: Vec<String>,
+	pub current_view: Option<String>,
+	pub timed_out_scripts: HashMap<PathBuf, u64>,
 	pub current_done_counter: i32,
 	pub past_done_counters: Vec<i32>,
 }
@@ -24,6 +34,13 @@ impl Default for Session {
 			last_session: chrono::Local::now().date_naive(),
 			set_filters: Vec::new(),
 			set_sortings: Vec::new(),
+			set_decorators: Vec::new(),
+			filter_params: HashMap::new(),
+			sorting_params: HashMap::new(),
+			decorator_params: HashMap::new(),
+			descending_sortings: Vec::new(),
+			current_view: None,
+			timed_out_scripts: HashMap::new(),
 			current_done_counter: 0,
 			past_done_counters: Vec::new(),
 		}
@@ -68,6 +85,11 @@ impl Session {
 		Ok(())
 	}
 
+	/// Whether the script at `path` went over its time limit, and was not edited since.
+	pub fn is_timed_out(&self, path: &Path, code_hash: u64) -> bool {
+		self.timed_out_scripts.get(path) == Some(&code_hash)
+	}
+
 	pub fn current() -> MutexGuard<'static, Self> {
 		SESSION.lock().expect("session should be lockable")
 	}
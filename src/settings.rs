@@ -7,7 +7,11 @@ use std::{
 use chrono::Datelike;
 use convert_case::Casing;
 
-use crate::{data_dir::DataDirError, task::Task};
+use crate::{
+	data_dir::DataDirError,
+	keybindings::{KeyAction, Keybinding},
+	task::Task,
+};
 
 pub const DEFAULT_SCHEDULED_TASK_TAG: &str = "scheduled_on($DATE)";
 pub const DEFAULT_DATE_FORMAT: &str = "%a. %-d %b. %Y";
@@ -30,6 +34,8 @@ pub struct Settings {
 	pub sprint_end_reference: chrono::NaiveDate,
 	pub sprint_end: SprintFrequency,
 	pub color_associations: HashMap<String, egui::Color32>,
+	pub keybindings: HashMap<KeyAction, Keybinding>,
+	pub script_time_limit_ms: u64,
 }
 
 impl Default for Settings {
@@ -64,6 +70,11 @@ impl Default for Settings {
 				);
 				map
 			},
+			keybindings: KeyAction::ALL
+				.into_iter()
+				.map(|action| (action, action.default_binding()))
+				.collect(),
+			script_time_limit_ms: 2000,
 		}
 	}
 }
@@ -258,6 +269,15 @@ impl Settings {
 	}
 }
 
+impl Settings {
+	pub fn keybinding(&self, action: KeyAction) -> Keybinding {
+		self.keybindings
+			.get(&action)
+			.copied()
+			.unwrap_or_else(|| action.default_binding())
+	}
+}
+
 impl FromStr for Settings {
 	type Err = ron::error::SpannedError;
 
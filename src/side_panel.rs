@@ -3,21 +3,26 @@ use std::{collections::HashMap, time::Duration};
 use uuid::Uuid;
 
 use crate::{
-	data_dir::DataDirError, help_string, ok_cancel_dialog::{OkCancelDialog, OkCancelResult}, scripts::{
+	data_dir::DataDirError, handle_toast_error, help_string, ok_cancel_dialog::{OkCancelDialog, OkCancelResult}, scripts::{
 		badge::BadgeType,
+		decorator::{DecoratorBadgeType, DEFAULT_DECORATOR_SCRIPT},
 		filter::{FilterBadgeType, DEFAULT_FILTER_SCRIPT},
+		library::{LibraryBadgeType, DEFAULT_LIBRARY_SCRIPT},
+		package::{PackageError, PackageList},
 		list::{ScriptEditorDeletionState, ScriptList},
 		sorting::{SortingBadgeType, DEFAULT_SORTING_SCRIPT},
 		standalone_script::{StandaloneScriptBadgeType, DEFAULT_STANDALONE_SCRIPT},
-		PocketPyScript,
-	}, session::Session, settings::{
+		console::{self, ConsoleEntryKind},
+		runner, PocketPyScript,
+	}, keybindings::{KeyAction, Keybinding}, session::Session, settings::{
 		self, AdhdMateriaTheme, Settings, SprintFrequency, SprintFrequencyKind,
 		DEFAULT_SCHEDULED_TASK_TAG,
-	}, task::{
+	}, tag::{suggestions_popup, Tag, TagIndex, TagSchemas, TagValue}, task::{
+		attachment::Attachment,
 		list::{TaskList, TaskListError},
 		scheduled::ScheduledTask,
 		TaskPath,
-	}, toast_error
+	}, toast_error, views::ManualOrders
 };
 
 macro_rules! open_scripts {
@@ -54,15 +59,44 @@ pub enum SidePanel {
 		script_list: Result<ScriptList<SortingBadgeType>, &'static DataDirError>,
 		interactable: bool,
 	},
+	DecoratorScripts {
+		script_list: Result<ScriptList<DecoratorBadgeType>, &'static DataDirError>,
+		interactable: bool,
+	},
 	Scripts {
 		script_list: Result<ScriptList<StandaloneScriptBadgeType>, &'static DataDirError>,
 		interactable: bool,
 	},
+	LibraryScripts {
+		script_list: Result<ScriptList<LibraryBadgeType>, &'static DataDirError>,
+		interactable: bool,
+	},
+	Packages {
+		package_list: Result<PackageList, PackageError>,
+		/// Directory of the package to install, as typed
+		install_path: String,
+		/// Package waiting for the uninstall confirmation
+		pending_uninstall: Option<String>,
+	},
+	TagManager {
+		/// Tag being renamed, and its new name as typed
+		renaming: Option<(String, String)>,
+		/// Rename waiting for the confirmation
+		pending_rename: Option<(String, String)>,
+	},
 	CompletedTasks {
 		total_completed_tasks: i32,
 	},
+	Console {
+		input: String,
+		/// Lines of a block being typed, evaluated once an empty line is entered
+		block: Vec<String>,
+		history: Vec<String>,
+		history_index: Option<usize>,
+	},
 	Settings {
 		color_associations_cache: Vec<(String, egui::epaint::Hsva)>,
+		recording_keybinding: Option<KeyAction>,
 	},
 }
 
@@ -73,8 +107,13 @@ impl SidePanelKind {
 			SidePanelKind::ScheduledTasks => "Scheduled Tasks",
 			SidePanelKind::FilterScripts => "Filter Scripts",
 			SidePanelKind::SortingScripts => "Sorting Scripts",
+			SidePanelKind::DecoratorScripts => "Decorator Scripts",
 			SidePanelKind::Scripts => "Scripts",
+			SidePanelKind::LibraryScripts => "Library Scripts",
+			SidePanelKind::Packages => "Script Packages",
+			SidePanelKind::TagManager => "Tag Manager",
 			SidePanelKind::CompletedTasks => "Completed Tasks",
+			SidePanelKind::Console => "Console",
 			SidePanelKind::Settings => "Settings",
 		}
 	}
@@ -87,6 +126,7 @@ impl SidePanel {
 		task_names: &HashMap<Uuid, String>,
 		scroll_to: &mut Option<Uuid>,
 		selected_task: &mut Option<Uuid>,
+		manual_orders: &ManualOrders,
 	) {
 		ui.add_space(8.0);
 
@@ -102,6 +142,7 @@ impl SidePanel {
 
 				help_string!(ui, "scheduled_task_list");
 				help_string!(ui, "scheduled_task_list_2");
+				help_string!(ui, "scheduled_task_list_3");
 
 				ui.add_space(8.0);
 
@@ -117,13 +158,34 @@ impl SidePanel {
 							ui.with_layout(
 								egui::Layout::top_down_justified(egui::Align::Min),
 								|ui| {
+									let mut task_names = task_names.clone();
+									task_names.extend(
+										task_list
+											.tasks()
+											.map(|task| (*task.get_uuid(), task.name.clone())),
+									);
+
+									let mut new_subtask_of = None;
+
 									egui::Grid::new("task_grid")
 										.num_columns(1)
 										.spacing((40.0, 4.0))
 										.striped(true)
 										.show(ui, |ui| {
-											for task in task_list.tasks_mut() {
-												task.widget().show(ui, task_names, scroll_to, selected_task);
+											for (uuid, depth) in task_list.tree_order() {
+												let task = task_list.get_mut(&uuid).expect("tree order only has valid uuids");
+
+												ui.horizontal_top(|ui| {
+													ui.add_space(24.0 * depth as f32);
+
+													ui.vertical(|ui| {
+														task.widget().show(ui, &task_names, depth > 0, scroll_to, selected_task);
+
+														if ui.small_button("New Subtask").clicked() {
+															new_subtask_of = Some(uuid);
+														}
+													});
+												});
 												ui.end_row();
 
 												if task.is_pending_delete() {
@@ -154,6 +216,23 @@ impl SidePanel {
 											}
 										});
 
+									if let Some(parent) = new_subtask_of {
+										let mut new_task = Settings::get()
+											.default_task
+											.clone()
+											.convert(ScheduledTask::default());
+										new_task.new_uuid();
+										new_task.tags.push(Tag::new(
+											String::from("subtask_of"),
+											Some(TagValue::TaskReference(parent)),
+										));
+										new_task.edit();
+
+										if let Err(e) = task_list.add_task(new_task) {
+											toast_error!("Could not create scheduled task: {}", e);
+										}
+									}
+
 									ui.add_space(16.0);
 
 									if ui.button("New Task").clicked() {
@@ -227,6 +306,7 @@ impl SidePanel {
 					"Filter",
 					&DEFAULT_FILTER_SCRIPT,
 					interactable,
+					manual_orders,
 				);
 			}
 			Self::SortingScripts {
@@ -256,6 +336,34 @@ impl SidePanel {
 					"Sorting",
 					&DEFAULT_SORTING_SCRIPT,
 					interactable,
+					manual_orders,
+				);
+			}
+			Self::DecoratorScripts {
+				script_list,
+				interactable,
+			} => {
+				ui.heading("Decorator Scripts");
+				ui.separator();
+				ui.add_space(8.0);
+
+				help_string!(ui, "decorator_scripts");
+				help_string!(ui, "decorator_scripts_2");
+
+				if let Some(0) = help_string!(ui, "other_scripts") {
+					self.open(SidePanelKind::Scripts);
+					return;
+				}
+
+				ui.add_space(8.0);
+
+				show_scripts(
+					ui,
+					script_list,
+					"Decorator",
+					&DEFAULT_DECORATOR_SCRIPT,
+					interactable,
+					manual_orders,
 				);
 			}
 			Self::Scripts {
@@ -275,6 +383,18 @@ impl SidePanel {
 						self.open(SidePanelKind::SortingScripts);
 						return;
 					}
+					Some(2) => {
+						self.open(SidePanelKind::DecoratorScripts);
+						return;
+					}
+					Some(3) => {
+						self.open(SidePanelKind::LibraryScripts);
+						return;
+					}
+					Some(4) => {
+						self.open(SidePanelKind::Packages);
+						return;
+					}
 					_ => {}
 				}
 
@@ -287,6 +407,14 @@ impl SidePanel {
 					_ => {}
 				}
 
+				let help_messages = Settings::get().help_messages;
+
+				if help_messages {
+					ui.collapsing("materia module reference", |ui| {
+						help_string!(ui, "materia_reference");
+					});
+				}
+
 				ui.add_space(8.0);
 
 				show_scripts(
@@ -295,8 +423,61 @@ impl SidePanel {
 					"Standalone",
 					&DEFAULT_STANDALONE_SCRIPT,
 					interactable,
+					manual_orders,
+				);
+			}
+			Self::LibraryScripts {
+				script_list,
+				interactable,
+			} => {
+				ui.heading("Library Scripts");
+				ui.separator();
+				ui.add_space(8.0);
+
+				help_string!(ui, "library_scripts");
+
+				if let Some(0) = help_string!(ui, "other_scripts") {
+					self.open(SidePanelKind::Scripts);
+					return;
+				}
+
+				ui.add_space(8.0);
+
+				show_scripts(
+					ui,
+					script_list,
+					"Library",
+					&DEFAULT_LIBRARY_SCRIPT,
+					interactable,
+					manual_orders,
 				);
 			}
+			Self::Packages {
+				package_list,
+				install_path,
+				pending_uninstall,
+			} => {
+				ui.heading("Script Packages");
+				ui.separator();
+				ui.add_space(8.0);
+
+				help_string!(ui, "packages");
+
+				ui.add_space(8.0);
+
+				show_packages(ui, package_list, install_path, pending_uninstall);
+			}
+			Self::TagManager { renaming, pending_rename } => {
+				ui.heading("Tag Manager");
+				ui.separator();
+				ui.add_space(8.0);
+
+				help_string!(ui, "tag_manager");
+
+				ui.add_space(8.0);
+
+				show_tag_manager(ui, renaming, pending_rename);
+			}
 			Self::CompletedTasks { total_completed_tasks } => {
 				ui.horizontal(|ui| {
 					ui.heading("Total Completed Tasks:");
@@ -334,7 +515,31 @@ impl SidePanel {
 						});
 				});
 			}
-			Self::Settings { color_associations_cache } => {
+			Self::Console {
+				input,
+				block,
+				history,
+				history_index,
+			} => {
+				ui.horizontal(|ui| {
+					ui.heading("Console");
+
+					ui.with_layout(egui::Layout::right_to_left(egui::Align::Center), |ui| {
+						if ui.button("Clear").clicked() {
+							console::clear();
+						}
+					});
+				});
+				ui.separator();
+				ui.add_space(8.0);
+
+				help_string!(ui, "console");
+
+				ui.add_space(8.0);
+
+				show_console(ui, input, block, history, history_index);
+			}
+			Self::Settings { color_associations_cache, recording_keybinding } => {
 				ui.heading("Settings");
 				ui.separator();
 				ui.add_space(8.0);
@@ -571,6 +776,108 @@ impl SidePanel {
 								}
 							});
 						});
+						ui.end_row();
+
+						ui.label("Tag schemas").on_hover_text(
+							"Known tags and the values they take, checked when a tag is edited. Other tags take any value. Edit them in tag_schemas.ron, then reload."
+						);
+						ui.collapsing("Known tags", |ui| {
+							egui::Grid::new("tag_schemas")
+								.striped(true)
+								.num_columns(2)
+								.show(ui, |ui| {
+									for (name, schema) in TagSchemas::get().iter() {
+										let label = ui.colored_label(
+											schema.color.unwrap_or_else(|| settings.get_color(name)),
+											name,
+										);
+
+										if !schema.description.is_empty() {
+											label.on_hover_text(schema.description.as_str());
+										}
+
+										ui.weak(schema.value_type.describe());
+										ui.end_row();
+									}
+								});
+
+							ui.vertical_centered_justified(|ui| {
+								if ui.button("✏ Edit").on_hover_text("Opens tag_schemas.ron, written with the built-in schemas the first time").clicked() {
+									match TagSchemas::edit_file() {
+										Ok(path) => handle_toast_error!("Could not open tag schemas: {}", Attachment::File(path).open(ui.ctx())),
+										Err(e) => {
+											toast_error!("Could not write tag schemas: {}", e);
+										}
+									}
+								}
+
+								if ui.button("⟳ Reload").clicked() {
+									handle_toast_error!("Could not load tag schemas: {}", TagSchemas::reload());
+								}
+							});
+						});
+						ui.end_row();
+
+						ui.label("Script time limit").on_hover_text(
+							"Filter and sorting scripts taking longer than this are stopped and disabled until edited."
+						);
+						ui.add(egui::DragValue::new(&mut settings.script_time_limit_ms).range(50..=60000).suffix(" ms"));
+						ui.end_row();
+
+						ui.label("Keybindings").on_hover_text(
+							"Click a shortcut, then press the new key combination. Escape cancels."
+						);
+						ui.collapsing("Shortcuts", |ui| {
+							egui::Grid::new("keybindings")
+								.striped(true)
+								.num_columns(3)
+								.show(ui, |ui| {
+									for action in KeyAction::ALL {
+										let recording = *recording_keybinding == Some(action);
+
+										ui.label(action.name());
+
+										if ui
+											.selectable_label(
+												recording,
+												if recording {
+													String::from("Press keys...")
+												} else {
+													settings.keybinding(action).to_string()
+												},
+											)
+											.clicked()
+										{
+											*recording_keybinding = Some(action);
+										}
+
+										if ui.small_button("⟲").on_hover_text("Reset to default").clicked() {
+											settings.keybindings.insert(action, action.default_binding());
+										}
+
+										ui.end_row();
+									}
+								});
+						});
+
+						if let Some(action) = *recording_keybinding {
+							let pressed = ui.input(|i| {
+								i.events.iter().find_map(|event| match event {
+									egui::Event::Key { key, pressed: true, modifiers, .. } => Some((*key, *modifiers)),
+									_ => None,
+								})
+							});
+
+							if let Some((key, modifiers)) = pressed {
+								ui.input_mut(|i| i.consume_key(modifiers, key));
+
+								if key != egui::Key::Escape {
+									settings.keybindings.insert(action, Keybinding::new(modifiers, key));
+								}
+
+								*recording_keybinding = None;
+							}
+						}
 					});
 			}
 		}
@@ -609,15 +916,33 @@ impl SidePanel {
 			}
 			SidePanelKind::FilterScripts => open_scripts!(FilterBadgeType, FilterScripts),
 			SidePanelKind::SortingScripts => open_scripts!(SortingBadgeType, SortingScripts),
+			SidePanelKind::DecoratorScripts => open_scripts!(DecoratorBadgeType, DecoratorScripts),
 			SidePanelKind::Scripts => open_scripts!(StandaloneScriptBadgeType, Scripts),
+			SidePanelKind::LibraryScripts => open_scripts!(LibraryBadgeType, LibraryScripts),
+			SidePanelKind::Packages => Self::Packages {
+				package_list: PackageList::load(),
+				install_path: String::new(),
+				pending_uninstall: None,
+			},
+			SidePanelKind::TagManager => Self::TagManager {
+				renaming: None,
+				pending_rename: None,
+			},
 			SidePanelKind::CompletedTasks => {
 				let session = Session::current();
 				Self::CompletedTasks {
 					total_completed_tasks: session.past_done_counters.iter().copied().sum::<i32>() + session.current_done_counter,
 				}
 			},
+			SidePanelKind::Console => Self::Console {
+				input: String::new(),
+				block: Vec::new(),
+				history: Vec::new(),
+				history_index: None,
+			},
 			SidePanelKind::Settings => Self::Settings {
 				color_associations_cache: Settings::get().color_associations.iter().map(|(name, color)| (name.clone(), egui::epaint::Hsva::from_srgba_premultiplied(color.to_array()))).collect(),
+				recording_keybinding: None,
 			},
 			SidePanelKind::Hidden => Self::Hidden,
 		}
@@ -640,11 +965,20 @@ impl SidePanel {
 			Self::SortingScripts { script_list, .. } => {
 				close_scripts(script_list, "Sorting");
 			}
+			Self::DecoratorScripts { script_list, .. } => {
+				close_scripts(script_list, "Decorator");
+			}
 			Self::Scripts { script_list, .. } => {
 				close_scripts(script_list, "Standalone");
 			}
+			Self::LibraryScripts { script_list, .. } => {
+				close_scripts(script_list, "Library");
+			}
+			Self::Packages { .. } => {}
+			Self::TagManager { .. } => {}
 			Self::CompletedTasks { .. } => {}
-			Self::Settings { color_associations_cache } => {
+			Self::Console { .. } => {}
+			Self::Settings { color_associations_cache, .. } => {
 				let mut settings = Settings::get();
 				settings.default_task.apply_tags();
 
@@ -687,6 +1021,7 @@ fn show_scripts<T: BadgeType>(
 	script_name: &'static str,
 	default_script: &std::sync::LazyLock<PocketPyScript>,
 	interactable: &mut bool,
+	manual_orders: &ManualOrders,
 ) {
 	ui.add_enabled_ui(*interactable, |ui| {
 		match script_list {
@@ -696,7 +1031,7 @@ fn show_scripts<T: BadgeType>(
 					.show(ui, |ui| {
 						ui.vertical_centered_justified(|ui| {
 							for script in script_list.scripts_mut() {
-								script.widget().show(ui);
+								script.widget().show(ui, manual_orders);
 
 								if script.deletion_state == ScriptEditorDeletionState::Pending {
 									*interactable = false;
@@ -748,6 +1083,221 @@ fn show_scripts<T: BadgeType>(
 	});
 }
 
+fn show_packages(
+	ui: &mut egui::Ui,
+	package_list: &mut Result<PackageList, PackageError>,
+	install_path: &mut String,
+	pending_uninstall: &mut Option<String>,
+) {
+	let package_list = match package_list {
+		Ok(package_list) => package_list,
+		Err(e) => {
+			ui.label(
+				egui::RichText::new(format!("Couldn't load packages: {}", e))
+					.color(ui.style().visuals.error_fg_color)
+					.heading(),
+			);
+			return;
+		}
+	};
+
+	ui.add_enabled_ui(pending_uninstall.is_none(), |ui| {
+		ui.horizontal(|ui| {
+			let install = ui.button("Install").clicked();
+
+			ui.add(
+				egui::TextEdit::singleline(install_path)
+					.hint_text("Package directory")
+					.desired_width(f32::INFINITY),
+			);
+
+			if install && !install_path.trim().is_empty() {
+				match package_list.install(std::path::Path::new(install_path.trim())) {
+					Ok(_) => install_path.clear(),
+					Err(e) => {
+						toast_error!("Couldn't install package: {}", e);
+					}
+				}
+			}
+		});
+
+		ui.add_space(8.0);
+
+		let mut to_update = None;
+
+		egui::ScrollArea::vertical()
+			.auto_shrink(false)
+			.show(ui, |ui| {
+				for (name, package) in package_list.iter() {
+					ui.group(|ui| {
+						ui.horizontal(|ui| {
+							ui.strong(name.as_str());
+							ui.weak(package.version.as_str());
+
+							ui.with_layout(egui::Layout::right_to_left(egui::Align::Center), |ui| {
+								if ui.button("Uninstall").clicked() {
+									*pending_uninstall = Some(name.clone());
+								}
+
+								if let Some(version) = package_list.available_update(name) {
+									if ui.button(format!("Update to {}", version)).clicked() {
+										to_update = Some(name.clone());
+									}
+								}
+							});
+						});
+
+						if !package.description.is_empty() {
+							ui.label(package.description.as_str());
+						}
+
+						for (kind, script) in package.scripts.iter() {
+							ui.weak(format!("{} script {}", kind.name(), script));
+						}
+					});
+				}
+			});
+
+		if let Some(name) = to_update {
+			handle_toast_error!("Couldn't update package: {}", package_list.update(name.as_str()));
+		}
+	});
+
+	if let Some(name) = pending_uninstall.clone() {
+		if let Some(result) = OkCancelDialog::default()
+			.with_title(format!("Uninstall package {}?", name))
+			.with_subtext("Its scripts will be deleted, including changes made to them.")
+			.with_ok_text("Uninstall")
+			.with_ok_color(ui.style().visuals.error_fg_color)
+			.show(ui.ctx())
+		{
+			if let OkCancelResult::Ok = result {
+				handle_toast_error!("Couldn't uninstall package: {}", package_list.uninstall(name.as_str()));
+			}
+
+			*pending_uninstall = None;
+		}
+	}
+}
+
+fn show_tag_manager(
+	ui: &mut egui::Ui,
+	renaming: &mut Option<(String, String)>,
+	pending_rename: &mut Option<(String, String)>,
+) {
+	ui.add_enabled_ui(pending_rename.is_none(), |ui| {
+		egui::ScrollArea::vertical()
+			.auto_shrink(false)
+			.show(ui, |ui| {
+				let index = TagIndex::get();
+
+				for (name, usage) in index.by_usage() {
+					ui.group(|ui| {
+						ui.horizontal(|ui| {
+							let color = TagSchemas::get()
+								.schema(name)
+								.and_then(|schema| schema.color)
+								.unwrap_or_else(|| Settings::get().get_color(name));
+
+							ui.colored_label(color, egui::RichText::new(name.as_str()).strong());
+							ui.weak(format!(
+								"{} task{}",
+								usage.task_count,
+								if usage.task_count == 1 { "" } else { "s" }
+							));
+
+							if renaming.is_none() {
+								ui.with_layout(egui::Layout::right_to_left(egui::Align::Center), |ui| {
+									if ui.button("Rename").clicked() {
+										*renaming = Some((name.clone(), name.clone()));
+									}
+								});
+							}
+						});
+
+						let values = usage.common_values();
+
+						if !values.is_empty() {
+							ui.weak(
+								values
+									.iter()
+									.take(5)
+									.map(|(value, count)| format!("\"{}\" ×{}", value, count))
+									.collect::<Vec<_>>()
+									.join(", "),
+							);
+						}
+
+						let Some((_, new_name)) = renaming.as_mut().filter(|(from, _)| from == name) else {
+							return;
+						};
+
+						let mut cancel = false;
+
+						ui.horizontal(|ui| {
+							let response = ui.add(
+								egui::TextEdit::singleline(new_name)
+									.code_editor()
+									.desired_width(160.0),
+							);
+
+							if let Some(suggestion) =
+								suggestions_popup(ui, &response, || index.suggest_names(new_name, 8))
+							{
+								*new_name = suggestion;
+							}
+
+							let valid = new_name != name
+								&& Tag::is_valid_name(new_name);
+							let merge = index.usage(new_name).is_some();
+
+							if ui
+								.add_enabled(valid, egui::Button::new(if merge { "Merge" } else { "Rename" }))
+								.clicked()
+							{
+								*pending_rename = Some((name.clone(), new_name.clone()));
+							}
+
+							cancel = ui.button("Cancel").clicked();
+						});
+
+						if cancel {
+							*renaming = None;
+						}
+					});
+				}
+			});
+	});
+
+	if let Some((from, to)) = pending_rename.clone() {
+		let (count, merge) = {
+			let index = TagIndex::get();
+			(
+				index.usage(&from).map_or(0, |usage| usage.task_count),
+				index.usage(&to).is_some(),
+			)
+		};
+
+		if let Some(result) = OkCancelDialog::default()
+			.with_title(if merge {
+				format!("Merge tag {} into {}?", from, to)
+			} else {
+				format!("Rename tag {} to {}?", from, to)
+			})
+			.with_subtext(format!("{} tasks will be changed.", count))
+			.with_ok_text(if merge { "Merge" } else { "Rename" })
+			.show(ui.ctx())
+		{
+			if let OkCancelResult::Ok = result {
+				crate::app::push_tag_rename(from, to);
+				*renaming = None;
+			}
+
+			*pending_rename = None;
+		}
+	}
+}
+
 fn close_scripts<T: BadgeType>(
 	script_list: &mut Result<ScriptList<T>, &DataDirError>,
 	script_name: &'static str,
@@ -758,3 +1308,116 @@ fn close_scripts<T: BadgeType>(
 		});
 	}
 }
+
+fn show_console(
+	ui: &mut egui::Ui,
+	input: &mut String,
+	block: &mut Vec<String>,
+	history: &mut Vec<String>,
+	history_index: &mut Option<usize>,
+) {
+	let input_height = ui.spacing().interact_size.y + 16.0;
+
+	egui::ScrollArea::vertical()
+		.max_height(ui.available_height() - input_height)
+		.auto_shrink([false, false])
+		.stick_to_bottom(true)
+		.show(ui, |ui| {
+			for entry in console::entries().iter() {
+				ui.horizontal_top(|ui| {
+					ui.weak(format!(
+						"{} {}",
+						entry.timestamp.format("%H:%M:%S"),
+						entry.source
+					));
+
+					let text = entry.text.trim_end_matches('\n');
+					let text = match entry.kind {
+						ConsoleEntryKind::Output => egui::RichText::new(text),
+						ConsoleEntryKind::Error => {
+							egui::RichText::new(text).color(ui.visuals().error_fg_color)
+						}
+						ConsoleEntryKind::Input => egui::RichText::new(
+							text.lines()
+								.enumerate()
+								.map(|(i, line)| format!("{} {}", if i == 0 { ">>>" } else { "..." }, line))
+								.collect::<Vec<String>>()
+								.join("\n"),
+						)
+						.color(ui.visuals().weak_text_color()),
+					};
+
+					ui.add(egui::Label::new(text.monospace()).wrap());
+				});
+			}
+		});
+
+	ui.add_space(8.0);
+
+	ui.horizontal(|ui| {
+		ui.monospace(if block.is_empty() { ">>>" } else { "..." });
+
+		let response = ui.add(
+			egui::TextEdit::singleline(input)
+				.code_editor()
+				.desired_width(f32::INFINITY)
+				.hint_text("Python, try Session.get()"),
+		);
+
+		if response.has_focus() && !history.is_empty() {
+			let (up, down) = ui.input(|i| {
+				(
+					i.key_pressed(egui::Key::ArrowUp),
+					i.key_pressed(egui::Key::ArrowDown),
+				)
+			});
+
+			if up || down {
+				let index = match (*history_index, up) {
+					(None, true) => Some(history.len() - 1),
+					(None, false) => None,
+					(Some(index), true) => Some(index.saturating_sub(1)),
+					(Some(index), false) => Some(index + 1).filter(|index| *index < history.len()),
+				};
+
+				*history_index = index;
+				*input = index.map(|index| history[index].clone()).unwrap_or_default();
+			}
+		}
+
+		if response.lost_focus() && ui.input(|i| i.key_pressed(egui::Key::Enter)) {
+			response.request_focus();
+
+			if runner::is_running() {
+				toast_error!("Wait for the running script to finish before using the console");
+				return;
+			}
+
+			*history_index = None;
+
+			let line = std::mem::take(input);
+
+			if !line.trim().is_empty() && history.last() != Some(&line) {
+				history.push(line.clone());
+			}
+
+			// Like the python REPL, a line ending with a colon starts a block that ends with an
+			// empty line
+			if !block.is_empty() || line.trim_end().ends_with(':') {
+				if !line.trim().is_empty() {
+					block.push(line);
+					return;
+				}
+			} else if line.trim().is_empty() {
+				return;
+			} else {
+				block.push(line);
+			}
+
+			let code = std::mem::take(block).join("\n");
+
+			// Errors are already written to the console
+			let _ = console::eval(crate::app::script_lock(), code.as_str());
+		}
+	});
+}
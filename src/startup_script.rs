@@ -1,7 +1,10 @@
 use std::str::FromStr;
 
+use uuid::Uuid;
+
 use crate::{
-	handle_toast_error, scripts::{badge::BadgeType, standalone_script::StandaloneScriptBadgeType, PocketPyScript}, session::{Session, SessionError}, settings::Settings, tag::Tag, task::{
+	handle_toast_error, scripts::{badge::BadgeType, hooks::{self, HookEvent}, standalone_script::StandaloneScriptBadgeType, PocketPyScript}, session::{Session, SessionError}, settings::Settings, tag::Tag, task::{
+		attachment,
 		list::{TaskList, TaskListError},
 		scheduled::{RepeatMode, ScheduledTask},
 		NormalTaskData, TaskError, TaskPath,
@@ -35,6 +38,8 @@ impl StartupScript {
 					.insert(0, session.current_done_counter);
 				session.current_done_counter = 0;
 			}));
+
+			hooks::trigger(HookEvent::SprintEnded, None);
 		}
 
 		drop(settings);
@@ -44,7 +49,16 @@ impl StartupScript {
 			TaskList::<ScheduledTask>::new(TaskPath::Scheduled)?;
 		let (mut task_list, _) = TaskList::<NormalTaskData>::new(TaskPath::Tasks)?;
 
-		for task in scheduled_task_list.tasks_mut() {
+		// Only root scheduled tasks are spawned on their own, their subtasks are spawned with them
+		let roots: Vec<Uuid> = scheduled_task_list
+			.tasks()
+			.filter(|task| scheduled_task_list.is_root(task))
+			.map(|task| *task.get_uuid())
+			.collect();
+
+		for root in roots {
+			let task = scheduled_task_list.get(&root).expect("collected from this list");
+
 			let spawn_count = match Settings::get().repeatable_rewind {
 				crate::settings::RepeatableRewind::One => i32::min(task.type_data.spawn_count(), 1),
 				crate::settings::RepeatableRewind::All => task.type_data.spawn_count(),
@@ -53,30 +67,46 @@ impl StartupScript {
 			let today = chrono::Local::now().date_naive();
 
 			for _ in 0..spawn_count {
-				let mut new_task = task.clone().convert(NormalTaskData::default());
-				new_task.new_uuid();
+				let mut new_tasks = scheduled_task_list.clone_tree::<NormalTaskData>(&root);
 
 				if let Some(scheduled_task_tag) = &Settings::get().scheduled_task_tag {
 					if let Ok(tag) = Tag::from_str(
 						&scheduled_task_tag.replace("$DATE", today.to_string().as_str()),
 					) {
-						new_task.tags.push(tag);
+						if let Some((_, new_root)) = new_tasks.first_mut() {
+							new_root.tags.push(tag);
+						}
 					}
 				}
 
-				if let Err(e) = task_list.add_task(new_task) {
-					errors.push(e);
+				for (original, new_task) in new_tasks {
+					let (new_uuid, snapshot) = (*new_task.get_uuid(), new_task.hook_snapshot());
+
+					match task_list.add_task(new_task) {
+						Ok(()) => {
+							handle_toast_error!("Could not copy attachments: {}", attachment::copy_task_files(&original, &new_uuid));
+							hooks::trigger(HookEvent::ScheduledSpawned, Some(snapshot));
+						}
+						Err(e) => errors.push(e),
+					}
 				}
 			}
 
+			// The subtasks were spawned along with the root, they must not stay behind as roots
+			// that spawn again on their own
 			if spawn_count > 0 && task.type_data.repeat_mode == RepeatMode::Never {
-				task.mark_for_delete();
+				for (uuid, _) in scheduled_task_list.subtree_order(&root) {
+					if let Some(task) = scheduled_task_list.get_mut(&uuid) {
+						task.mark_for_delete();
+					}
+				}
 			}
 		}
 
 		scheduled_task_list.cleanup_marked_for_delete();
 
 		Session::mutate(|session| session.last_session = chrono::Local::now().date_naive())?;
+		hooks::trigger(HookEvent::Startup, None);
 		Ok(errors)
 	}
 }
@@ -97,3 +127,120 @@ pub enum StartupError {
 		SessionError,
 	),
 }
+
+#[cfg(test)]
+mod tests {
+	use std::sync::{Mutex, PoisonError};
+
+	use super::*;
+	use crate::{tag::TagValue, task::Task};
+
+	/// Held by the tests spawning the scheduled tasks of the test data directory.
+	static SCHEDULED: Mutex<()> = Mutex::new(());
+
+	fn scheduled_names() -> Vec<String> {
+		TaskList::<ScheduledTask>::new(TaskPath::Scheduled)
+			.unwrap()
+			.0
+			.tasks()
+			.map(|task| task.name.clone())
+			.collect()
+	}
+
+	/// Runs the startup script as the first launch of the day, and returns the spawned tasks
+	/// named with `prefix`, removing them from the task list.
+	fn run_and_take_spawned(prefix: &str) -> Vec<Task> {
+		Session::mutate(|session| {
+			session.last_session = chrono::Local::now().date_naive().pred_opt().unwrap();
+		})
+		.unwrap();
+
+		StartupScript::run().unwrap();
+
+		let (mut task_list, _) = TaskList::<NormalTaskData>::new(TaskPath::Tasks).unwrap();
+		let spawned: Vec<Task> = task_list
+			.tasks()
+			.filter(|task| task.name.starts_with(prefix))
+			.cloned()
+			.collect();
+
+		for task in &spawned {
+			task_list.delete_task(task.get_uuid()).unwrap();
+		}
+
+		spawned
+	}
+
+	#[test]
+	fn spawn_never_tree() {
+		let _lock = SCHEDULED.lock().unwrap_or_else(PoisonError::into_inner);
+
+		let mut root = Task::<ScheduledTask>::default();
+		root.name = String::from("never root");
+
+		let mut subtask = Task::<ScheduledTask>::default();
+		subtask.name = String::from("never subtask");
+		subtask.tags.push(Tag::new(
+			String::from("subtask_of"),
+			Some(TagValue::TaskReference(*root.get_uuid())),
+		));
+
+		root.save(TaskPath::Scheduled).unwrap();
+		subtask.save(TaskPath::Scheduled).unwrap();
+
+		let spawned = run_and_take_spawned("never ");
+		assert_eq!(spawned.len(), 2);
+
+		let spawned_root = spawned.iter().find(|task| task.name == "never root").unwrap();
+		let spawned_subtask = spawned.iter().find(|task| task.name == "never subtask").unwrap();
+		assert!(spawned_subtask.is_subtask_of(spawned_root.get_uuid()));
+
+		assert!(scheduled_names().iter().all(|name| !name.starts_with("never ")));
+	}
+
+	#[test]
+	fn snooze_tree() {
+		let _lock = SCHEDULED.lock().unwrap_or_else(PoisonError::into_inner);
+
+		let (mut task_list, _) = TaskList::<NormalTaskData>::new(TaskPath::Tasks).unwrap();
+
+		let mut parent = Task::default();
+		parent.name = String::from("snoozed parent");
+		let parent_uuid = *parent.get_uuid();
+
+		let mut subtask = Task::default();
+		subtask.name = String::from("snoozed subtask");
+		subtask.set_parent(Some(parent_uuid));
+		let subtask_uuid = *subtask.get_uuid();
+
+		task_list.add_task(parent).unwrap();
+		task_list.add_task(subtask).unwrap();
+
+		// Snoozing until today, the way the snooze dialog schedules tasks
+		let queue = hooks::TEST_QUEUE.lock().unwrap_or_else(PoisonError::into_inner);
+		let snoozed = task_list
+			.convert_to_scheduled(&[parent_uuid], &ScheduledTask::default())
+			.unwrap();
+		assert_eq!(snoozed, 2);
+		assert!(task_list.tasks().all(|task| !task.name.starts_with("snoozed ")));
+
+		// Hooks see a snooze, not a deletion
+		for uuid in [parent_uuid, subtask_uuid] {
+			assert_eq!(hooks::queued_events(&uuid), vec![HookEvent::TaskSnoozed]);
+		}
+
+		drop(queue);
+
+		let spawned = run_and_take_spawned("snoozed ");
+		assert_eq!(spawned.len(), 2);
+
+		let spawned_parent = spawned.iter().find(|task| task.name == "snoozed parent").unwrap();
+		let spawned_subtask = spawned.iter().find(|task| task.name == "snoozed subtask").unwrap();
+		assert!(spawned_subtask.is_subtask_of(spawned_parent.get_uuid()));
+
+		assert!(scheduled_names().iter().all(|name| !name.starts_with("snoozed ")));
+
+		// Nothing is left to come back on a later day
+		assert!(run_and_take_spawned("snoozed ").is_empty());
+	}
+}
@@ -0,0 +1,406 @@
+use std::collections::HashMap;
+
+use chrono::{NaiveDateTime, NaiveTime, Timelike};
+use uuid::Uuid;
+
+use crate::command_palette::fuzzy_match;
+
+use super::{
+	schema::TagType,
+	suggestions_popup,
+	time::{format_duration, parse_duration},
+	Tag, TagIndex, TagSchemas, TagValue, TagValueKind,
+};
+
+/// Edits the name and value of a tag with fields matching the type of its value. Returns whether
+/// anything changed.
+pub(super) fn tag_editor(
+	ui: &mut egui::Ui,
+	id: egui::Id,
+	tag: &mut Tag,
+	task_names: &HashMap<Uuid, String>,
+) -> bool {
+	let response = ui.add(
+		egui::TextEdit::singleline(&mut tag.name)
+			.code_editor()
+			.desired_width(80.0),
+	);
+	let mut changed = response.changed();
+
+	if let Some(name) = suggestions_popup(ui, &response, || {
+		TagIndex::get().suggest_names(&tag.name, 8)
+	}) {
+		tag.name = name;
+		changed = true;
+	}
+
+	let (value_type, default) = TagSchemas::get()
+		.schema(&tag.name)
+		.map(|schema| (Some(schema.value_type.clone()), schema.default.clone()))
+		.unwrap_or_default();
+
+	let mut kind = tag.value.as_ref().map(TagValue::kind);
+
+	if kind_selector(ui, id.with("kind"), &mut kind, true) {
+		tag.value = kind.map(|kind| new_value(kind, value_type.as_ref(), default.as_ref()));
+		changed = true;
+	}
+
+	match &mut tag.value {
+		// Free text gets the values other tasks use for this tag as suggestions
+		Some(TagValue::Text(text)) if !matches!(value_type, Some(TagType::Enum(_))) => {
+			let response = ui.add(egui::TextEdit::singleline(text).desired_width(80.0));
+			changed |= response.changed();
+
+			if let Some(value) = suggestions_popup(ui, &response, || {
+				TagIndex::get().suggest_values(&tag.name, text, 8)
+			}) {
+				*text = value;
+				changed = true;
+			}
+		}
+		Some(value) => {
+			changed |= value_editor(ui, id.with("value"), value, value_type.as_ref(), task_names);
+		}
+		None => {}
+	}
+
+	changed
+}
+
+/// A combo box of value kinds, with a "No value" choice if `optional`. Returns whether the kind
+/// was changed.
+fn kind_selector(
+	ui: &mut egui::Ui,
+	id: egui::Id,
+	kind: &mut Option<TagValueKind>,
+	optional: bool,
+) -> bool {
+	let before = *kind;
+
+	egui::ComboBox::from_id_source(id)
+		.width(0.0)
+		.selected_text(kind.map_or(String::from("No value"), |kind| kind.to_string()))
+		.show_ui(ui, |ui| {
+			if optional {
+				ui.selectable_value(kind, None, "No value");
+			}
+
+			for value_kind in TagValueKind::all() {
+				ui.selectable_value(kind, Some(*value_kind), value_kind.to_string());
+			}
+		});
+
+	*kind != before
+}
+
+/// A value of this kind to start editing from: the default of the schema if it is of this kind,
+/// or an empty one.
+fn new_value(kind: TagValueKind, value_type: Option<&TagType>, default: Option<&TagValue>) -> TagValue {
+	if let Some(default) = default.filter(|default| default.kind() == kind) {
+		return default.clone();
+	}
+
+	match kind {
+		TagValueKind::Bool => TagValue::Bool(false),
+		TagValueKind::Int => TagValue::Int(0),
+		TagValueKind::Float => TagValue::Float(0.0),
+		TagValueKind::Date => TagValue::Date(chrono::Local::now().date_naive()),
+		TagValueKind::DateTime => TagValue::DateTime(
+			chrono::Local::now()
+				.naive_local()
+				.with_second(0)
+				.and_then(|date_time| date_time.with_nanosecond(0))
+				.expect("0 is a valid second and nanosecond"),
+		),
+		TagValueKind::Duration => TagValue::Duration(0),
+		TagValueKind::Text => TagValue::Text(match value_type {
+			Some(TagType::Enum(values)) => values.first().cloned().unwrap_or_default(),
+			_ => String::new(),
+		}),
+		TagValueKind::List => TagValue::List(Vec::new()),
+		TagValueKind::Dictionary => TagValue::Dictionary(HashMap::new()),
+		TagValueKind::Tag => TagValue::Tag(Box::default()),
+		TagValueKind::TaskReference => TagValue::TaskReference(Uuid::nil()),
+	}
+}
+
+fn value_editor(
+	ui: &mut egui::Ui,
+	id: egui::Id,
+	value: &mut TagValue,
+	value_type: Option<&TagType>,
+	task_names: &HashMap<Uuid, String>,
+) -> bool {
+	match value {
+		TagValue::Bool(b) => ui.add(egui::Checkbox::without_text(b)).changed(),
+		TagValue::Int(int) => ui.add(egui::DragValue::new(int)).changed(),
+		TagValue::Float(float) => ui.add(egui::DragValue::new(float).speed(0.1)).changed(),
+		TagValue::Date(date) => ui
+			.add(egui_extras::DatePickerButton::new(date).id_source(id.value().to_string().as_str()))
+			.changed(),
+		TagValue::DateTime(date_time) => date_time_editor(ui, id, date_time),
+		TagValue::Duration(seconds) => ui
+			.add(
+				egui::DragValue::new(seconds)
+					.speed(60.0)
+					.custom_formatter(|seconds, _| format_duration(seconds as i64))
+					.custom_parser(|text| parse_duration(text).map(|seconds| seconds as f64)),
+			)
+			.changed(),
+		TagValue::Text(text) => match value_type {
+			Some(TagType::Enum(values)) => {
+				let before = text.clone();
+
+				egui::ComboBox::from_id_source(id)
+					.selected_text(text.as_str())
+					.show_ui(ui, |ui| {
+						for value in values {
+							ui.selectable_value(text, value.clone(), value.as_str());
+						}
+					});
+
+				*text != before
+			}
+			_ => ui
+				.add(egui::TextEdit::singleline(text).desired_width(80.0))
+				.changed(),
+		},
+		TagValue::List(list) => list_editor(ui, id, list, value_type, task_names),
+		TagValue::Dictionary(dict) => dictionary_editor(ui, id, dict, task_names),
+		TagValue::Tag(tag) => {
+			ui.group(|ui| {
+				ui.horizontal(|ui| tag_editor(ui, id, tag, task_names)).inner
+			})
+			.inner
+		}
+		TagValue::TaskReference(uuid) => task_picker(ui, id, uuid, task_names),
+	}
+}
+
+/// A date picker followed by the hours and minutes. Seconds are kept as they are.
+fn date_time_editor(ui: &mut egui::Ui, id: egui::Id, date_time: &mut NaiveDateTime) -> bool {
+	let mut date = date_time.date();
+	let mut hour = date_time.hour();
+	let mut minute = date_time.minute();
+
+	let changed = ui
+		.add(egui_extras::DatePickerButton::new(&mut date).id_source(id.value().to_string().as_str()))
+		.changed()
+		| ui.add(egui::DragValue::new(&mut hour).range(0..=23)).changed()
+		| ui.add(egui::DragValue::new(&mut minute).range(0..=59)).changed();
+
+	if let Some(time) = NaiveTime::from_hms_opt(hour, minute, date_time.second()) {
+		*date_time = date.and_time(time);
+	}
+
+	changed
+}
+
+fn list_editor(
+	ui: &mut egui::Ui,
+	id: egui::Id,
+	list: &mut Vec<TagValue>,
+	value_type: Option<&TagType>,
+	task_names: &HashMap<Uuid, String>,
+) -> bool {
+	let element_type = match value_type {
+		Some(TagType::List(element_type)) => Some(element_type.as_ref()),
+		_ => None,
+	};
+
+	ui.group(|ui| {
+		ui.vertical(|ui| {
+			let mut changed = false;
+			let mut to_remove = None;
+
+			for (i, element) in list.iter_mut().enumerate() {
+				let id = id.with(i);
+
+				ui.horizontal(|ui| {
+					changed |= element_kind_selector(ui, id, element, element_type);
+					changed |= value_editor(ui, id.with("value"), element, element_type, task_names);
+
+					if ui.small_button("🗑").clicked() {
+						to_remove = Some(i);
+					}
+				});
+			}
+
+			if let Some(i) = to_remove {
+				list.remove(i);
+				changed = true;
+			}
+
+			if ui.small_button("+").clicked() {
+				let kind = element_type
+					.and_then(TagType::value_kind)
+					.or_else(|| list.last().map(TagValue::kind))
+					.unwrap_or(TagValueKind::Text);
+
+				list.push(new_value(kind, element_type, None));
+				changed = true;
+			}
+
+			changed
+		})
+		.inner
+	})
+	.inner
+}
+
+fn dictionary_editor(
+	ui: &mut egui::Ui,
+	id: egui::Id,
+	dict: &mut HashMap<String, TagValue>,
+	task_names: &HashMap<Uuid, String>,
+) -> bool {
+	let mut keys: Vec<String> = dict.keys().cloned().collect();
+	keys.sort();
+
+	ui.group(|ui| {
+		ui.vertical(|ui| {
+			let mut changed = false;
+			let mut to_remove = None;
+			let mut to_rename = None;
+
+			for key in keys.iter() {
+				let id = id.with(key);
+
+				ui.horizontal(|ui| {
+					// Renamed once done typing, as renaming reorders the keys
+					let key_id = id.with("key");
+					let mut new_key: String = ui
+						.data_mut(|data| data.get_temp(key_id))
+						.unwrap_or_else(|| key.clone());
+					let response = ui.add(
+						egui::TextEdit::singleline(&mut new_key)
+							.id(key_id)
+							.desired_width(60.0),
+					);
+
+					if response.lost_focus() {
+						ui.data_mut(|data| data.remove::<String>(key_id));
+
+						if !new_key.is_empty() && !dict.contains_key(&new_key) {
+							to_rename = Some((key.clone(), new_key));
+						}
+					} else if response.changed() {
+						ui.data_mut(|data| data.insert_temp(key_id, new_key));
+					}
+
+					ui.weak(":");
+
+					if let Some(value) = dict.get_mut(key) {
+						changed |= element_kind_selector(ui, id, value, None);
+						changed |= value_editor(ui, id.with("value"), value, None, task_names);
+					}
+
+					if ui.small_button("🗑").clicked() {
+						to_remove = Some(key.clone());
+					}
+				});
+			}
+
+			if let Some((key, new_key)) = to_rename {
+				if let Some(value) = dict.remove(&key) {
+					dict.insert(new_key, value);
+					changed = true;
+				}
+			}
+
+			if let Some(key) = to_remove {
+				dict.remove(&key);
+				changed = true;
+			}
+
+			if ui.small_button("+").clicked() {
+				let key = (1..)
+					.map(|i| if i == 1 { String::from("key") } else { format!("key_{}", i) })
+					.find(|key| !dict.contains_key(key))
+					.expect("there is always a free key");
+
+				dict.insert(key, TagValue::Text(String::new()));
+				changed = true;
+			}
+
+			changed
+		})
+		.inner
+	})
+	.inner
+}
+
+/// Changes the kind of an element of a list or dictionary, which always has a value.
+fn element_kind_selector(
+	ui: &mut egui::Ui,
+	id: egui::Id,
+	value: &mut TagValue,
+	value_type: Option<&TagType>,
+) -> bool {
+	let mut kind = Some(value.kind());
+
+	if kind_selector(ui, id.with("kind"), &mut kind, false) {
+		if let Some(kind) = kind {
+			*value = new_value(kind, value_type, None);
+			return true;
+		}
+	}
+
+	false
+}
+
+/// A button with the name of the referenced task, opening a searchable list of the tasks.
+fn task_picker(
+	ui: &mut egui::Ui,
+	id: egui::Id,
+	uuid: &mut Uuid,
+	task_names: &HashMap<Uuid, String>,
+) -> bool {
+	let popup_id = id.with("task_picker");
+	let response = ui.button(
+		task_names
+			.get(uuid)
+			.map(String::as_str)
+			.unwrap_or("Pick a task"),
+	);
+
+	if response.clicked() {
+		ui.memory_mut(|memory| memory.toggle_popup(popup_id));
+	}
+
+	let mut changed = false;
+
+	egui::popup_below_widget(
+		ui,
+		popup_id,
+		&response,
+		egui::PopupCloseBehavior::CloseOnClickOutside,
+		|ui| {
+			ui.set_min_width(200.0);
+
+			let mut search: String = ui.data_mut(|data| data.get_temp(popup_id).unwrap_or_default());
+			ui.add(egui::TextEdit::singleline(&mut search).hint_text("Search tasks"));
+
+			let mut matching: Vec<(&Uuid, &String)> = task_names
+				.iter()
+				.filter(|(_, name)| fuzzy_match(search.as_str(), name.as_str()))
+				.collect();
+			matching.sort_by(|a, b| a.1.cmp(b.1));
+
+			egui::ScrollArea::vertical().max_height(200.0).show(ui, |ui| {
+				for (task_uuid, name) in matching {
+					if ui.selectable_label(task_uuid == uuid, name.as_str()).clicked() {
+						*uuid = *task_uuid;
+						changed = true;
+						ui.memory_mut(|memory| memory.close_popup());
+					}
+				}
+			});
+
+			ui.data_mut(|data| data.insert_temp(popup_id, search));
+		},
+	);
+
+	changed
+}
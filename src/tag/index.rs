@@ -0,0 +1,181 @@
+use std::{
+	collections::{BTreeMap, HashMap},
+	sync::{LazyLock, Mutex, MutexGuard},
+};
+
+use uuid::Uuid;
+
+use crate::{command_palette::fuzzy_match, task::Task};
+
+use super::{TagSchemas, TagValue};
+
+static TAG_INDEX: LazyLock<Mutex<TagIndex>> = LazyLock::new(|| Mutex::new(TagIndex::default()));
+
+/// How a tag name is used across the task list.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct TagUsage {
+	/// Tasks with at least one tag of this name
+	pub task_count: usize,
+	/// Text values of the tags of this name, with how many tags have them
+	values: HashMap<String, usize>,
+}
+
+impl TagUsage {
+	/// The text values of the tag, the most used first.
+	pub fn common_values(&self) -> Vec<(&str, usize)> {
+		let mut values: Vec<(&str, usize)> = self
+			.values
+			.iter()
+			.map(|(value, count)| (value.as_str(), *count))
+			.collect();
+		values.sort_by(|a, b| b.1.cmp(&a.1).then(a.0.cmp(b.0)));
+		values
+	}
+}
+
+/// The tag names used by the tasks, rebuilt when the task list changes. Used to suggest names
+/// and values while typing.
+#[derive(Debug, Default)]
+pub struct TagIndex {
+	names: BTreeMap<String, TagUsage>,
+	/// Task ids by name, to resolve `@"Task name"` references
+	tasks: HashMap<String, Uuid>,
+}
+
+impl TagIndex {
+	pub fn new<'a>(tasks: impl Iterator<Item = &'a Task>) -> Self {
+		let mut names: BTreeMap<String, TagUsage> = BTreeMap::new();
+		let mut task_ids = HashMap::new();
+
+		for task in tasks {
+			task_ids.insert(task.name.clone(), *task.get_uuid());
+			let mut counted = Vec::new();
+
+			for tag in task.tags.iter() {
+				let usage = names.entry(tag.name.clone()).or_default();
+
+				if !counted.contains(&tag.name) {
+					usage.task_count += 1;
+					counted.push(tag.name.clone());
+				}
+
+				if let Some(TagValue::Text(text)) = &tag.value {
+					*usage.values.entry(text.clone()).or_default() += 1;
+				}
+			}
+		}
+
+		Self {
+			names,
+			tasks: task_ids,
+		}
+	}
+
+	pub fn get() -> MutexGuard<'static, Self> {
+		TAG_INDEX.lock().expect("Tag index should be lockable")
+	}
+
+	pub fn rebuild<'a>(tasks: impl Iterator<Item = &'a Task>) {
+		*Self::get() = Self::new(tasks);
+	}
+
+	/// The used tag names, the most used first.
+	pub fn by_usage(&self) -> Vec<(&String, &TagUsage)> {
+		let mut names: Vec<(&String, &TagUsage)> = self.names.iter().collect();
+		names.sort_by(|a, b| b.1.task_count.cmp(&a.1.task_count).then(a.0.cmp(b.0)));
+		names
+	}
+
+	pub fn usage(&self, name: &str) -> Option<&TagUsage> {
+		self.names.get(name)
+	}
+
+	/// The task with this exact name, or else with this name ignoring case.
+	pub fn task_by_name(&self, name: &str) -> Option<Uuid> {
+		self.tasks.get(name).copied().or_else(|| {
+			self.tasks
+				.iter()
+				.find(|(task_name, _)| task_name.to_lowercase() == name.to_lowercase())
+				.map(|(_, uuid)| *uuid)
+		})
+	}
+
+	/// Tag names matching what is typed, names starting with it first, then the most used ones.
+	/// Names with a schema are suggested even when no task uses them.
+	pub fn suggest_names(&self, typed: &str, max: usize) -> Vec<String> {
+		let schemas = TagSchemas::get();
+		let mut candidates: Vec<(&str, usize)> = self
+			.names
+			.iter()
+			.map(|(name, usage)| (name.as_str(), usage.task_count))
+			.collect();
+
+		for (name, _) in schemas.iter() {
+			if !self.names.contains_key(name) {
+				candidates.push((name.as_str(), 0));
+			}
+		}
+
+		rank(candidates, typed, max)
+	}
+
+	/// Text values of a tag matching what is typed, ranked like names.
+	pub fn suggest_values(&self, name: &str, typed: &str, max: usize) -> Vec<String> {
+		self.usage(name).map_or_else(Vec::new, |usage| {
+			rank(usage.common_values(), typed, max)
+		})
+	}
+}
+
+fn rank(candidates: Vec<(&str, usize)>, typed: &str, max: usize) -> Vec<String> {
+	let mut matching: Vec<(&str, usize)> = candidates
+		.into_iter()
+		.filter(|(candidate, _)| *candidate != typed && fuzzy_match(typed, candidate))
+		.collect();
+
+	matching.sort_by(|a, b| {
+		b.0.starts_with(typed)
+			.cmp(&a.0.starts_with(typed))
+			.then(b.1.cmp(&a.1))
+			.then(a.0.cmp(b.0))
+	});
+
+	matching
+		.into_iter()
+		.take(max)
+		.map(|(candidate, _)| candidate.to_string())
+		.collect()
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	#[test]
+	fn suggestions() {
+		let mut tasks = vec![Task::default(), Task::default(), Task::default()];
+		let tags: [&[&str]; 3] = [
+			&["priority(\"A\")", "project(\"home\")", "project(\"work\")"],
+			&["priority(\"B\")", "project(\"work\")", "prototype"],
+			&["project(\"work\")"],
+		];
+
+		for (task, tags) in tasks.iter_mut().zip(tags) {
+			task.tags = tags.iter().map(|tag| tag.parse().unwrap()).collect();
+		}
+
+		let index = TagIndex::new(tasks.iter());
+
+		assert_eq!(index.usage("project").unwrap().task_count, 3);
+		assert_eq!(
+			index.usage("project").unwrap().common_values(),
+			vec![("work", 3), ("home", 1)]
+		);
+		assert_eq!(index.by_usage()[0].0, "project");
+
+		assert_eq!(index.suggest_names("pro", 2), vec!["project", "prototype"]);
+		assert_eq!(index.suggest_names("prty", 1), vec!["priority"]);
+		assert_eq!(index.suggest_values("project", "", 5), vec!["work", "home"]);
+		assert!(index.suggest_values("project", "work", 5).is_empty());
+	}
+}
@@ -3,12 +3,19 @@ use std::{
 	str::{Chars, FromStr},
 };
 
-use chrono::NaiveDate;
+use chrono::{NaiveDate, NaiveDateTime};
 use ui::TagWidget;
 
+mod editor;
+mod index;
+mod schema;
+mod time;
 mod ui;
 
-pub use ui::TagSwapRequest;
+pub use index::TagIndex;
+pub use schema::TagSchemas;
+pub use time::{format_date_time, format_duration};
+pub use ui::{suggestions_popup, TagSwapRequest};
 use uuid::Uuid;
 
 #[derive(Debug, Clone, serde::Serialize, serde::Deserialize)]
@@ -20,12 +27,15 @@ pub struct Tag {
 	editing_text: Option<String>,
 }
 
-#[derive(Debug, Clone, PartialEq, serde::Serialize, serde::Deserialize)]
+#[derive(Debug, Clone, PartialEq, serde::Serialize, serde::Deserialize, kinded::Kinded)]
 pub enum TagValue {
 	Bool(bool),
 	Int(i64),
 	Float(f64),
 	Date(NaiveDate),
+	DateTime(NaiveDateTime),
+	/// In seconds
+	Duration(i64),
 	Text(String),
 	List(Vec<TagValue>),
 	Dictionary(HashMap<String, TagValue>),
@@ -182,6 +192,14 @@ impl Tag {
 		})
 	}
 
+	/// Whether `name` can be the name of a tag, without a value.
+	pub fn is_valid_name(name: &str) -> bool {
+		name.starts_with(|c: char| c.is_ascii_lowercase() || c == '_')
+			&& name
+				.chars()
+				.all(|c| c.is_ascii_lowercase() || c.is_ascii_digit() || c == '_')
+	}
+
 	pub fn widget(&mut self, edit_mode: bool) -> TagWidget {
 		TagWidget::new(self, edit_mode)
 	}
@@ -196,11 +214,27 @@ impl Tag {
 		}
 	}
 
+	pub fn is_editing_text(&self) -> bool {
+		self.editing_text.is_some()
+	}
+
+	/// Applies the text typed in raw mode, then checks the tag against its schema. Text that
+	/// doesn't parse or validate is dropped, but values edited with the fields are kept so that
+	/// they can be fixed.
 	pub fn apply_text(&mut self) -> Result<(), TagError> {
-		if let Some(text) = self.editing_text.take() {
-			*self = text.parse()?;
+		// Parsed before locking the schemas, as `@"name"` references lock the tag index
+		let parsed = self.editing_text.take().map(|text| text.parse::<Tag>()).transpose()?;
+		let schemas = TagSchemas::get();
+
+		match parsed {
+			Some(tag) => {
+				schemas.validate(&tag)?;
+				*self = tag;
+			}
+			None => schemas.validate(self)?,
 		}
 
+		schemas.fill_default(self);
 		Ok(())
 	}
 
@@ -229,14 +263,14 @@ impl TagValue {
 			'"' | '\'' => Ok(Self::Text(
 				Self::parse_string(chars).expect("peek returned \" or '")?,
 			)),
-			'0'..='9' | '.' | '-' => {
+			'0'..='9' | '.' | '-' | '+' => {
 				let mut s: String = String::new();
 
 				let mut started = false;
 
 				while let Some(c) = chars.peek_with_whitespace() {
 					match c {
-						'0'..='9' | '.' | '-' | 'a'..='f' | 'A'..='F' => {
+						'0'..='9' | '.' | '-' | '+' | ':' | 'a'..='z' | 'A'..='Z' => {
 							chars.next_with_whitespace().expect("peek returned some");
 							s.push(c);
 							started = true;
@@ -252,18 +286,51 @@ impl TagValue {
 
 				if let Ok(date) = s.parse::<NaiveDate>() {
 					Ok(Self::Date(date))
+				} else if let Some(date_time) = time::parse_date_time(s.as_str()) {
+					Ok(Self::DateTime(date_time))
 				} else if let Ok(uuid) = s.parse::<Uuid>() {
 					Ok(Self::TaskReference(uuid))
+				} else if let Some(date) =
+					time::parse_relative_date(s.as_str(), chrono::Local::now().date_naive())
+				{
+					Ok(Self::Date(date))
+				} else if let Ok(int) = s.parse::<i64>() {
+					Ok(Self::Int(int))
 				} else {
-					if let Ok(int) = s.parse::<i64>() {
-						Ok(Self::Int(int))
+					match s.parse::<f64>() {
+						Ok(float) => Ok(Self::Float(float)),
+						Err(e) => time::parse_duration(s.as_str())
+							.map(Self::Duration)
+							.ok_or(TagError::CantParseNumber(e)),
+					}
+				}
+			}
+			'@' => {
+				chars.next().expect("peek returned some");
+
+				if let Some(name) = Self::parse_string(chars) {
+					let name = name?;
+
+					return TagIndex::get()
+						.task_by_name(name.as_str())
+						.map(Self::TaskReference)
+						.ok_or(TagError::UnknownTask(name));
+				}
+
+				let mut s = String::new();
+
+				while let Some(c) = chars.peek_with_whitespace() {
+					if c.is_ascii_hexdigit() || c == '-' {
+						chars.next_with_whitespace().expect("peek returned some");
+						s.push(c);
 					} else {
-						match s.parse::<f64>() {
-							Ok(float) => Ok(Self::Float(float)),
-							Err(e) => Err(TagError::CantParseNumber(e)),
-						}
+						break;
 					}
 				}
+
+				s.parse::<Uuid>()
+					.map(Self::TaskReference)
+					.map_err(|_| TagError::InvalidTaskReference(s))
 			}
 			'[' => {
 				chars.next().expect("peek returned some");
@@ -364,28 +431,51 @@ impl TagValue {
 					});
 
 				let text = text.unwrap_or_else(|e| e);
+				let today = chrono::Local::now().date_naive();
+
+				// Dates like `next monday` continue with a second word
+				let weekday_date = || {
+					let after = &chars.chars.as_str()[text.len() - 1..];
+					let trimmed = after.trim_start();
+					let weekday: String =
+						trimmed.chars().take_while(char::is_ascii_alphabetic).collect();
+
+					time::parse_weekday_date(text.as_str(), weekday.as_str(), today).map(|date| {
+						(
+							date,
+							text.len() + after.chars().count() - trimmed.chars().count()
+								+ weekday.len(),
+						)
+					})
+				};
 
-				let (result, not_consumed) = if let Ok(uuid) = text.parse::<Uuid>() {
-					(Ok(Self::TaskReference(uuid)), true)
+				// The value and how many characters it takes, when it isn't a tag
+				let literal = if let Ok(uuid) = text.parse::<Uuid>() {
+					Some((Self::TaskReference(uuid), text.len()))
+				} else if let Some(date) = time::parse_date_word(text.as_str(), today) {
+					Some((Self::Date(date), text.len()))
 				} else {
 					match text.as_str() {
-						"true" => (Ok(Self::Bool(true)), true),
-						"false" => (Ok(Self::Bool(false)), true),
-						_ => (Ok(Self::Tag(Box::new(Tag::parse(chars)?))), false),
+						"true" => Some((Self::Bool(true), text.len())),
+						"false" => Some((Self::Bool(false), text.len())),
+						_ => weekday_date().map(|(date, length)| (Self::Date(date), length)),
 					}
 				};
 
-				if not_consumed {
-					chars.next().expect("peek returned some");
+				match literal {
+					Some((value, length)) => {
+						chars.next().expect("peek returned some");
 
-					for _ in 0..(text.len() - 1) {
-						chars
-							.next_with_whitespace()
-							.expect("text is at least this large");
+						for _ in 0..(length - 1) {
+							chars
+								.next_with_whitespace()
+								.expect("text is at least this large");
+						}
+
+						Ok(value)
 					}
+					None => Ok(Self::Tag(Box::new(Tag::parse(chars)?))),
 				}
-
-				result
 			}
 			other => Err(TagError::InvalidFirstValueChar(other)),
 		}
@@ -468,6 +558,8 @@ impl ToString for TagValue {
 			Self::Int(int) => int.to_string(),
 			Self::Float(float) => float.to_string(),
 			Self::Date(date) => date.to_string(),
+			Self::DateTime(date_time) => time::format_date_time(date_time),
+			Self::Duration(seconds) => time::format_duration(*seconds),
 			Self::List(list) => {
 				let mut inner = list
 					.iter()
@@ -495,7 +587,7 @@ impl ToString for TagValue {
 				format!("{{{}}}", inner)
 			}
 			Self::Tag(tag) => tag.to_string(),
-			Self::TaskReference(uuid) => uuid.to_string(),
+			Self::TaskReference(uuid) => format!("@{}", uuid),
 		}
 	}
 }
@@ -511,7 +603,7 @@ pub enum TagError {
 	#[error("Tag name must begin with a letter or _, but here started with `{0}`")]
 	InvalidFirstChar(char),
 
-	#[error("Tag value must begin with a letter or _ for tags, a number, or \"/' for text, but here started with `{0}`")]
+	#[error("Tag value must begin with a letter or _ for tags, a number, @ for task references, or \"/' for text, but here started with `{0}`")]
 	InvalidFirstValueChar(char),
 
 	#[error("Tag name must only contain lowercase letters, numbers and _")]
@@ -546,6 +638,32 @@ pub enum TagError {
 
 	#[error("Expected end of tag, but found character `{0}`")]
 	ExpectedEnd(char),
+
+	#[error("`@{0}` is not a task reference, write `@` followed by a task id or a quoted task name")]
+	InvalidTaskReference(String),
+
+	#[error("There is no task named `{0}`")]
+	UnknownTask(String),
+
+	#[error("Tag `{0}` takes no value")]
+	UnexpectedValue(String),
+
+	#[error("Tag `{tag}` needs a value: {expected}")]
+	MissingValue { tag: String, expected: String },
+
+	#[error("Tag `{tag}` expects {expected}, but got `{value}`")]
+	WrongValueType {
+		tag: String,
+		expected: String,
+		value: String,
+	},
+
+	#[error("`{value}` is not an allowed value of tag `{tag}`, which allows {allowed}")]
+	NotAllowedValue {
+		tag: String,
+		value: String,
+		allowed: String,
+	},
 }
 
 #[cfg(test)]
@@ -561,6 +679,9 @@ mod tests {
 			"empty_list_value([])",
 			"list([\"haha\", 4.5])",
 			"nested([[\"lol\", 23], othertag([])])",
+			"due(2024-01-31T14:30)",
+			"estimate([2h30m, 45s, -25h])",
+			"blocked_by(@67e55044-10b1-426f-9247-bb680e5fe0c8)",
 		];
 
 		let dict_test = "dict({\"lol\": 4, \"haha\": inner_tag(4.53)})";
@@ -582,6 +703,33 @@ mod tests {
 		assert!(new_dict == dict_test || new_dict == dict_test_alt);
 	}
 
+	#[test]
+	fn date_literals() {
+		let today = chrono::Local::now().date_naive();
+		let value = |text: &str| text.parse::<Tag>().unwrap().value.unwrap();
+
+		assert_eq!(
+			value("due(+3d)"),
+			TagValue::Date(today + chrono::Days::new(3))
+		);
+		assert_eq!(value("due(tomorrow)"), TagValue::Date(today.succ_opt().unwrap()));
+		assert_eq!(
+			value("due([next   monday, yesterday])"),
+			TagValue::List(vec![
+				TagValue::Date(time::parse_weekday_date("next", "monday", today).unwrap()),
+				TagValue::Date(today.pred_opt().unwrap()),
+			])
+		);
+		assert_eq!(
+			value("due(nextweek)"),
+			TagValue::Tag(Box::new(Tag::new(String::from("nextweek"), None)))
+		);
+		assert_eq!(
+			"ref(@nope)".parse::<Tag>().unwrap_err(),
+			TagError::InvalidTaskReference(String::new())
+		);
+	}
+
 	#[test]
 	fn errors() {
 		let base = [
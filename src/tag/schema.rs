@@ -0,0 +1,371 @@
+use std::{
+	collections::BTreeMap,
+	path::PathBuf,
+	sync::{LazyLock, Mutex, MutexGuard},
+};
+
+use crate::data_dir::DataDirError;
+
+use super::{Tag, TagError, TagValue, TagValueKind};
+
+static TAG_SCHEMAS: LazyLock<Mutex<TagSchemas>> =
+	LazyLock::new(|| Mutex::new(TagSchemas::load().unwrap_or_default()));
+
+/// The kind of value a known tag takes.
+#[derive(Debug, Clone, Default, PartialEq, serde::Serialize, serde::Deserialize)]
+pub enum TagType {
+	/// The tag takes no value, like `done`
+	#[default]
+	Flag,
+	Bool,
+	Int,
+	/// Integers are accepted too
+	Float,
+	Date,
+	DateTime,
+	Duration,
+	Text,
+	/// Text among these values
+	Enum(Vec<String>),
+	/// A list whose elements are all of this type
+	List(Box<TagType>),
+	TaskRef,
+}
+
+impl TagType {
+	pub fn matches(&self, value: &TagValue) -> bool {
+		match (self, value) {
+			(Self::Bool, TagValue::Bool(_))
+			| (Self::Int, TagValue::Int(_))
+			| (Self::Float, TagValue::Int(_) | TagValue::Float(_))
+			| (Self::Date, TagValue::Date(_))
+			| (Self::DateTime, TagValue::DateTime(_))
+			| (Self::Duration, TagValue::Duration(_))
+			| (Self::Text, TagValue::Text(_))
+			| (Self::TaskRef, TagValue::TaskReference(_)) => true,
+			(Self::Enum(values), TagValue::Text(text)) => values.contains(text),
+			(Self::List(element), TagValue::List(list)) => {
+				list.iter().all(|value| element.matches(value))
+			}
+			_ => false,
+		}
+	}
+
+	/// The kind of value that matches this type, `None` for flags.
+	pub fn value_kind(&self) -> Option<TagValueKind> {
+		match self {
+			Self::Flag => None,
+			Self::Bool => Some(TagValueKind::Bool),
+			Self::Int => Some(TagValueKind::Int),
+			Self::Float => Some(TagValueKind::Float),
+			Self::Date => Some(TagValueKind::Date),
+			Self::DateTime => Some(TagValueKind::DateTime),
+			Self::Duration => Some(TagValueKind::Duration),
+			Self::Text | Self::Enum(_) => Some(TagValueKind::Text),
+			Self::List(_) => Some(TagValueKind::List),
+			Self::TaskRef => Some(TagValueKind::TaskReference),
+		}
+	}
+
+	/// What values of this type look like, to complete "expects ..." messages.
+	pub fn describe(&self) -> String {
+		match self {
+			Self::Flag => String::from("no value"),
+			Self::Bool => String::from("`true` or `false`"),
+			Self::Int => String::from("an integer"),
+			Self::Float => String::from("a number"),
+			Self::Date => String::from("a date like `2024-12-31`, `+3d` or `next monday`"),
+			Self::DateTime => String::from("a date and time like `2024-12-31T18:30`"),
+			Self::Duration => String::from("a duration like `1h30m`"),
+			Self::Text => String::from("a quoted text"),
+			Self::Enum(values) => format!(
+				"one of {}",
+				values
+					.iter()
+					.map(|value| format!("`\"{}\"`", value))
+					.collect::<Vec<_>>()
+					.join(", ")
+			),
+			Self::List(element) => format!("a list where each element is {}", element.describe()),
+			Self::TaskRef => String::from("a task reference"),
+		}
+	}
+}
+
+/// What is known about a tag name: the values it takes, its default, and how it is shown.
+#[derive(Debug, Clone, Default, PartialEq, serde::Serialize, serde::Deserialize)]
+#[serde(default)]
+pub struct TagSchema {
+	pub value_type: TagType,
+	/// Values the tag can take on top of matching its type, any value when empty
+	pub allowed: Vec<TagValue>,
+	/// Value given to the tag when it is written without one
+	pub default: Option<TagValue>,
+	/// Overrides the color associated to the name in the settings
+	pub color: Option<egui::Color32>,
+	pub description: String,
+}
+
+impl TagSchema {
+	pub fn validate(&self, tag: &Tag) -> Result<(), TagError> {
+		match (&tag.value, &self.value_type) {
+			(None, TagType::Flag) => Ok(()),
+			(None, _) if self.default.is_some() => Ok(()),
+			(None, value_type) => Err(TagError::MissingValue {
+				tag: tag.name.clone(),
+				expected: value_type.describe(),
+			}),
+			(Some(_), TagType::Flag) => Err(TagError::UnexpectedValue(tag.name.clone())),
+			(Some(value), value_type) if !value_type.matches(value) => {
+				Err(TagError::WrongValueType {
+					tag: tag.name.clone(),
+					expected: value_type.describe(),
+					value: value.to_string(),
+				})
+			}
+			(Some(value), _) if !self.allowed.is_empty() && !self.allowed.contains(value) => {
+				Err(TagError::NotAllowedValue {
+					tag: tag.name.clone(),
+					value: value.to_string(),
+					allowed: self
+						.allowed
+						.iter()
+						.map(|value| format!("`{}`", value.to_string()))
+						.collect::<Vec<_>>()
+						.join(", "),
+				})
+			}
+			(Some(_), _) => Ok(()),
+		}
+	}
+}
+
+/// The known tags by name, saved in the data directory. Tags that aren't in here take any value.
+#[derive(Debug, Clone, PartialEq, serde::Serialize, serde::Deserialize)]
+#[serde(transparent)]
+pub struct TagSchemas {
+	schemas: BTreeMap<String, TagSchema>,
+}
+
+impl Default for TagSchemas {
+	fn default() -> Self {
+		let mut schemas = BTreeMap::new();
+
+		schemas.insert(
+			String::from("done"),
+			TagSchema {
+				description: String::from("The task is completed"),
+				..Default::default()
+			},
+		);
+		schemas.insert(
+			String::from("subtask_of"),
+			TagSchema {
+				value_type: TagType::TaskRef,
+				description: String::from("The task is a subtask of the referenced task"),
+				..Default::default()
+			},
+		);
+		schemas.insert(
+			String::from("scheduled_on"),
+			TagSchema {
+				value_type: TagType::Date,
+				description: String::from("Date at which a scheduled task created this task"),
+				..Default::default()
+			},
+		);
+		schemas.insert(
+			String::from("priority"),
+			TagSchema {
+				value_type: TagType::Enum(
+					["SSS", "SS", "S", "A", "B", "C", "D", "E", "F"]
+						.into_iter()
+						.flat_map(|grade| {
+							["", "+", "++", "+++", "-", "--", "---"]
+								.into_iter()
+								.map(move |modifier| format!("{}{}", grade, modifier))
+						})
+						.collect(),
+				),
+				color: Some(egui::Color32::from_rgb(0xF0, 0xA0, 0x10)),
+				description: String::from(
+					"Priority used by the By Priority sorting, from \"SSS\" to \"F\" with an optional +/- modifier",
+				),
+				..Default::default()
+			},
+		);
+
+		Self { schemas }
+	}
+}
+
+impl TagSchemas {
+	/// Loads the schemas, the built-in ones if there is no file yet.
+	pub fn load() -> Result<Self, TagSchemaError> {
+		let path = crate::data_dir()?.tag_schemas();
+
+		if path.exists() {
+			Ok(ron::from_str(std::fs::read_to_string(path)?.as_str())?)
+		} else {
+			Ok(Self::default())
+		}
+	}
+
+	/// Writes the current schemas if there is no file yet, so that the user can edit them, and
+	/// returns the path of the file.
+	pub fn edit_file() -> Result<PathBuf, TagSchemaError> {
+		let path = crate::data_dir()?.tag_schemas();
+
+		if !path.exists() {
+			Self::get().save()?;
+		}
+
+		Ok(path.to_path_buf())
+	}
+
+	pub fn save(&self) -> Result<(), TagSchemaError> {
+		std::fs::write(
+			crate::data_dir()?.tag_schemas(),
+			ron::ser::to_string_pretty(self, ron::ser::PrettyConfig::default())
+				.expect("ron serialization of tag schemas should not fail"),
+		)?;
+		Ok(())
+	}
+
+	/// When the [`TagIndex`](super::TagIndex) is also locked, it must be locked first.
+	pub fn get() -> MutexGuard<'static, Self> {
+		TAG_SCHEMAS.lock().expect("Tag schemas should be lockable")
+	}
+
+	/// Reads the file again, after it was edited by hand.
+	pub fn reload() -> Result<(), TagSchemaError> {
+		*Self::get() = Self::load()?;
+		Ok(())
+	}
+
+	pub fn schema(&self, name: &str) -> Option<&TagSchema> {
+		self.schemas.get(name)
+	}
+
+	pub fn iter(&self) -> impl Iterator<Item = (&String, &TagSchema)> {
+		self.schemas.iter()
+	}
+
+	/// Checks a tag against the schema of its name, if it has one.
+	pub fn validate(&self, tag: &Tag) -> Result<(), TagError> {
+		self.schema(&tag.name)
+			.map_or(Ok(()), |schema| schema.validate(tag))
+	}
+
+	/// Gives its default value to a tag written without one.
+	pub fn fill_default(&self, tag: &mut Tag) {
+		if tag.value.is_none() {
+			tag.value = self
+				.schema(&tag.name)
+				.and_then(|schema| schema.default.clone());
+		}
+	}
+}
+
+#[derive(Debug, thiserror::Error)]
+pub enum TagSchemaError {
+	#[error("Could not access data directory: {0}")]
+	DataDir(
+		#[from]
+		#[source]
+		&'static DataDirError,
+	),
+
+	#[error("IO error: {0}")]
+	Io(
+		#[from]
+		#[source]
+		std::io::Error,
+	),
+
+	#[error("Parse error: {0}")]
+	Parse(
+		#[from]
+		#[source]
+		ron::error::SpannedError,
+	),
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	#[test]
+	fn validation() {
+		let mut schemas = TagSchemas::default();
+		schemas.schemas.insert(
+			String::from("estimate"),
+			TagSchema {
+				value_type: TagType::Int,
+				allowed: vec![TagValue::Int(1), TagValue::Int(2), TagValue::Int(3)],
+				default: Some(TagValue::Int(1)),
+				..Default::default()
+			},
+		);
+
+		let check = |text: &str| schemas.validate(&text.parse::<Tag>().unwrap());
+
+		assert_eq!(check("done"), Ok(()));
+		assert_eq!(check("priority(\"A+\")"), Ok(()));
+		assert_eq!(check("unknown_tag([1, \"a\"])"), Ok(()));
+		assert_eq!(check("estimate"), Ok(()));
+		assert_eq!(check("estimate(2)"), Ok(()));
+
+		assert_eq!(
+			check("done(true)"),
+			Err(TagError::UnexpectedValue(String::from("done")))
+		);
+		assert!(matches!(
+			check("priority(\"Z\")"),
+			Err(TagError::WrongValueType { .. })
+		));
+		assert!(matches!(
+			check("scheduled_on(\"tomorrow\")"),
+			Err(TagError::WrongValueType { .. })
+		));
+		assert!(matches!(
+			check("subtask_of"),
+			Err(TagError::MissingValue { .. })
+		));
+		assert_eq!(
+			check("estimate(5)"),
+			Err(TagError::NotAllowedValue {
+				tag: String::from("estimate"),
+				value: String::from("5"),
+				allowed: String::from("`1`, `2`, `3`"),
+			})
+		);
+
+		let mut tag: Tag = "estimate".parse().unwrap();
+		schemas.fill_default(&mut tag);
+		assert_eq!(tag.value, Some(TagValue::Int(1)));
+	}
+
+	#[test]
+	fn built_in() {
+		let schemas = TagSchemas::load().unwrap();
+
+		assert!(schemas.schema("priority").is_some());
+		assert!(!crate::data_dir().unwrap().tag_schemas().exists());
+	}
+
+	#[test]
+	fn list_types() {
+		let list = TagType::List(Box::new(TagType::Float));
+
+		assert!(list.matches(&"[1, 2.5]".parse().unwrap()));
+		assert!(list.matches(&TagValue::List(Vec::new())));
+		assert!(!list.matches(&"[1, \"a\"]".parse().unwrap()));
+		assert_eq!(list.describe(), "a list where each element is a number");
+
+		let durations = TagType::List(Box::new(TagType::Duration));
+
+		assert!(durations.matches(&"[1h30m, 2d]".parse().unwrap()));
+		assert!(!durations.matches(&"[2024-01-31T12:00]".parse().unwrap()));
+	}
+}
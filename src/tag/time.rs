@@ -0,0 +1,181 @@
+use chrono::{Datelike, Days, Months, NaiveDate, NaiveDateTime, Timelike, Weekday};
+
+const DATE_TIME_FORMAT: &str = "%Y-%m-%dT%H:%M";
+const DATE_TIME_SECONDS_FORMAT: &str = "%Y-%m-%dT%H:%M:%S";
+
+/// Units of durations like `2h30m`, with their length in seconds.
+const DURATION_UNITS: [(char, i64); 5] = [
+	('w', 7 * 24 * 3600),
+	('d', 24 * 3600),
+	('h', 3600),
+	('m', 60),
+	('s', 1),
+];
+
+pub fn parse_date_time(s: &str) -> Option<NaiveDateTime> {
+	NaiveDateTime::parse_from_str(s, DATE_TIME_SECONDS_FORMAT)
+		.or_else(|_| NaiveDateTime::parse_from_str(s, DATE_TIME_FORMAT))
+		.ok()
+}
+
+pub fn format_date_time(date_time: &NaiveDateTime) -> String {
+	date_time
+		.format(if date_time.second() == 0 {
+			DATE_TIME_FORMAT
+		} else {
+			DATE_TIME_SECONDS_FORMAT
+		})
+		.to_string()
+}
+
+/// Parses a duration made of numbers followed by `w`, `d`, `h`, `m` or `s`, like `1d12h`, into
+/// seconds.
+pub fn parse_duration(s: &str) -> Option<i64> {
+	let (negative, s) = match s.strip_prefix('-') {
+		Some(rest) => (true, rest),
+		None => (false, s),
+	};
+
+	let mut seconds: i64 = 0;
+	let mut number = String::new();
+
+	for c in s.chars() {
+		if c.is_ascii_digit() {
+			number.push(c);
+		} else {
+			let (_, unit) = DURATION_UNITS.iter().find(|(unit, _)| *unit == c)?;
+			seconds = seconds.checked_add(number.parse::<i64>().ok()?.checked_mul(*unit)?)?;
+			number.clear();
+		}
+	}
+
+	if !number.is_empty() || s.is_empty() {
+		return None;
+	}
+
+	Some(if negative { -seconds } else { seconds })
+}
+
+pub fn format_duration(seconds: i64) -> String {
+	if seconds == 0 {
+		return String::from("0s");
+	}
+
+	let mut rest = seconds.unsigned_abs();
+	let mut result = String::new();
+
+	if seconds < 0 {
+		result.push('-');
+	}
+
+	for (unit, length) in DURATION_UNITS {
+		// Weeks are written as days, and negative days as hours, as `-3d` is a relative date
+		if unit == 'w' || (unit == 'd' && seconds < 0) {
+			continue;
+		}
+
+		let length = length as u64;
+
+		if rest >= length {
+			result.push_str(format!("{}{}", rest / length, unit).as_str());
+			rest %= length;
+		}
+	}
+
+	result
+}
+
+/// Parses a date relative to `today`: a signed number of days, weeks, months or years like `+3d`
+/// or `-1m`.
+pub fn parse_relative_date(s: &str, today: NaiveDate) -> Option<NaiveDate> {
+	let (negative, rest) = if let Some(rest) = s.strip_prefix('+') {
+		(false, rest)
+	} else {
+		(true, s.strip_prefix('-')?)
+	};
+
+	let unit = rest.chars().last()?;
+	let amount: u32 = rest[..rest.len() - unit.len_utf8()].parse().ok()?;
+
+	match (unit, negative) {
+		('d', false) => today.checked_add_days(Days::new(amount as u64)),
+		('d', true) => today.checked_sub_days(Days::new(amount as u64)),
+		('w', false) => today.checked_add_days(Days::new(amount as u64 * 7)),
+		('w', true) => today.checked_sub_days(Days::new(amount as u64 * 7)),
+		('m', false) => today.checked_add_months(Months::new(amount)),
+		('m', true) => today.checked_sub_months(Months::new(amount)),
+		('y', false) => today.checked_add_months(Months::new(amount.checked_mul(12)?)),
+		('y', true) => today.checked_sub_months(Months::new(amount.checked_mul(12)?)),
+		_ => None,
+	}
+}
+
+/// Parses `today`, `tomorrow` and `yesterday`.
+pub fn parse_date_word(word: &str, today: NaiveDate) -> Option<NaiveDate> {
+	match word {
+		"today" => Some(today),
+		"tomorrow" => today.succ_opt(),
+		"yesterday" => today.pred_opt(),
+		_ => None,
+	}
+}
+
+/// The first `weekday` after `today` for `next`, or before it for `last`.
+pub fn parse_weekday_date(direction: &str, weekday: &str, today: NaiveDate) -> Option<NaiveDate> {
+	let weekday: Weekday = weekday.parse().ok()?;
+	let days_after = (weekday.num_days_from_monday() + 7 - today.weekday().num_days_from_monday()) % 7;
+
+	match direction {
+		"next" => today.checked_add_days(Days::new(if days_after == 0 { 7 } else { days_after } as u64)),
+		"last" => {
+			let days_before = (7 - days_after) % 7;
+			today.checked_sub_days(Days::new(if days_before == 0 { 7 } else { days_before } as u64))
+		}
+		_ => None,
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	#[test]
+	fn literals() {
+		// A wednesday
+		let today = NaiveDate::from_ymd_opt(2024, 1, 31).unwrap();
+		let date = |y, m, d| NaiveDate::from_ymd_opt(y, m, d);
+
+		assert_eq!(parse_relative_date("+3d", today), date(2024, 2, 3));
+		assert_eq!(parse_relative_date("-2w", today), date(2024, 1, 17));
+		assert_eq!(parse_relative_date("+1m", today), date(2024, 2, 29));
+		assert_eq!(parse_relative_date("+1y", today), date(2025, 1, 31));
+		assert_eq!(parse_relative_date("3d", today), None);
+		assert_eq!(parse_relative_date("+3h", today), None);
+
+		assert_eq!(parse_weekday_date("next", "monday", today), date(2024, 2, 5));
+		assert_eq!(parse_weekday_date("next", "wednesday", today), date(2024, 2, 7));
+		assert_eq!(parse_weekday_date("last", "fri", today), date(2024, 1, 26));
+		assert_eq!(parse_weekday_date("last", "wednesday", today), date(2024, 1, 24));
+		assert_eq!(parse_date_word("tomorrow", today), date(2024, 2, 1));
+
+		assert_eq!(parse_duration("2h30m"), Some(9000));
+		assert_eq!(parse_duration("1w"), Some(604800));
+		assert_eq!(parse_duration("-90s"), Some(-90));
+		assert_eq!(parse_duration("2h30"), None);
+		assert_eq!(parse_duration("h"), None);
+
+		for seconds in [0, 9000, 90061, -90000, 604800] {
+			assert_eq!(parse_duration(format_duration(seconds).as_str()), Some(seconds));
+		}
+
+		assert_eq!(format_duration(9000), "2h30m");
+		assert_eq!(format_duration(-90000), "-25h");
+
+		let date_time = parse_date_time("2024-01-31T14:30").unwrap();
+		assert_eq!(format_date_time(&date_time), "2024-01-31T14:30");
+		assert_eq!(
+			parse_date_time("2024-01-31T14:30:05").map(|date_time| format_date_time(&date_time)),
+			Some(String::from("2024-01-31T14:30:05"))
+		);
+	}
+}
@@ -4,14 +4,83 @@ use convert_case::Casing;
 use uuid::Uuid;
 
 use crate::{
+	handle_toast_error,
 	settings::{Settings, DEFAULT_DATE_FORMAT},
 	utils::ChronoDelayFormatExt,
 };
 
-use super::{Tag, TagValue};
+use super::{editor, time::format_duration, Tag, TagError, TagSchemas, TagValue};
 
 fn get_tag_color(tag: &Tag) -> egui::Color32 {
-	Settings::get().get_color(&tag.name)
+	TagSchemas::get()
+		.schema(&tag.name)
+		.and_then(|schema| schema.color)
+		.unwrap_or_else(|| Settings::get().get_color(&tag.name))
+}
+
+/// Validates a tag against its schema, returning the description of the schema if it has one.
+fn check_tag(tag: &Tag) -> Result<Option<String>, TagError> {
+	let schemas = TagSchemas::get();
+	schemas.validate(tag)?;
+
+	Ok(schemas
+		.schema(&tag.name)
+		.map(|schema| schema.description.clone())
+		.filter(|description| !description.is_empty()))
+}
+
+/// Shows suggestions below a text edit while it has the focus. Returns the clicked suggestion.
+pub fn suggestions_popup(
+	ui: &egui::Ui,
+	response: &egui::Response,
+	suggestions: impl FnOnce() -> Vec<String>,
+) -> Option<String> {
+	let popup_id = response.id.with("suggestions");
+	let is_open = ui.memory(|memory| memory.is_popup_open(popup_id));
+
+	if !response.has_focus() && !is_open {
+		return None;
+	}
+
+	let suggestions = suggestions();
+
+	if response.has_focus() && !suggestions.is_empty() {
+		ui.memory_mut(|memory| memory.open_popup(popup_id));
+	} else if is_open
+		&& (suggestions.is_empty()
+			// Clicking a suggestion also takes the focus, so the popup stays until the click is handled
+			|| (response.lost_focus()
+				&& !ui.input(|i| i.pointer.any_down() || i.pointer.any_released())))
+	{
+		ui.memory_mut(|memory| memory.close_popup());
+	}
+
+	let chosen = egui::popup_below_widget(
+		ui,
+		popup_id,
+		response,
+		egui::PopupCloseBehavior::CloseOnClickOutside,
+		|ui| {
+			ui.set_min_width(120.0);
+
+			let mut chosen = None;
+
+			for suggestion in suggestions {
+				if ui.selectable_label(false, suggestion.as_str()).clicked() {
+					chosen = Some(suggestion.clone());
+				}
+			}
+
+			chosen
+		},
+	)
+	.flatten();
+
+	if chosen.is_some() {
+		ui.memory_mut(|memory| memory.close_popup());
+	}
+
+	chosen
 }
 
 pub struct TagWidget<'tag> {
@@ -45,7 +114,61 @@ impl TagWidget<'_> {
 				swap_req = Some(TagSwapRequest::Backward);
 			}
 
-			ui.add(egui::TextEdit::singleline(self.tag.get_editing_text()).code_editor());
+			let id = ui.next_auto_id();
+
+			let response = if self.tag.is_editing_text() {
+				ui.add(egui::TextEdit::singleline(self.tag.get_editing_text()).code_editor())
+			} else {
+				ui.push_id(id, |ui| {
+					ui.horizontal(|ui| editor::tag_editor(ui, id, self.tag, task_names))
+						.response
+				})
+				.inner
+			};
+
+			// Checked every frame so that mistakes show up while typing, not only once applied
+			let checked = if self.tag.is_editing_text() {
+				self.tag
+					.get_editing_text()
+					.parse::<Tag>()
+					.and_then(|tag| check_tag(&tag))
+			} else {
+				check_tag(self.tag)
+			};
+			let valid = checked.is_ok();
+
+			match checked {
+				Ok(Some(description)) => {
+					response.on_hover_text(description);
+				}
+				Ok(None) => {}
+				Err(e) => {
+					ui.painter().rect_stroke(
+						response.rect,
+						2.0,
+						egui::Stroke::new(1.0, ui.visuals().error_fg_color),
+					);
+					response.on_hover_text(
+						egui::RichText::new(e.to_string()).color(ui.visuals().error_fg_color),
+					);
+				}
+			}
+
+			if self.tag.is_editing_text() {
+				if ui
+					.add_enabled(valid, egui::Button::new("☰").frame(false))
+					.on_hover_text("Edit with fields")
+					.clicked()
+				{
+					handle_toast_error!("Tag parsing error: {}", self.tag.apply_text());
+				}
+			} else if ui
+				.add(egui::Button::new("✎").frame(false))
+				.on_hover_text("Edit as text")
+				.clicked()
+			{
+				self.tag.get_editing_text();
+			}
 
 			if ui.add(egui::Button::new("▶").frame(false)).clicked() {
 				swap_req = Some(TagSwapRequest::Forward);
@@ -78,8 +201,12 @@ impl TagWidget<'_> {
 		selected_task: &mut Option<Uuid>,
 	) -> egui::Response {
 		let col = get_tag_color(tag);
+		let description = TagSchemas::get()
+			.schema(&tag.name)
+			.map(|schema| schema.description.clone())
+			.filter(|description| !description.is_empty());
 
-		egui::Frame::group(ui.style())
+		let response = egui::Frame::group(ui.style())
 			.stroke(egui::Stroke::new(
 				ui.style().visuals.window_stroke().width,
 				col,
@@ -92,7 +219,12 @@ impl TagWidget<'_> {
 					Self::draw_tag_value(ui, value, col, task_names, scroll_to, selected_task);
 				}
 			})
-			.response
+			.response;
+
+		match description {
+			Some(description) => response.on_hover_text(description),
+			None => response,
+		}
 	}
 
 	fn draw_tag_value(
@@ -154,6 +286,17 @@ impl TagWidget<'_> {
 								d.format_or_err(Settings::get().date_format.as_str())
 									.unwrap_or(d.format(DEFAULT_DATE_FORMAT).to_string()),
 							),
+							TagValue::DateTime(date_time) => egui::RichText::new(format!(
+								"{} {}",
+								date_time
+									.date()
+									.format_or_err(Settings::get().date_format.as_str())
+									.unwrap_or(date_time.format(DEFAULT_DATE_FORMAT).to_string()),
+								date_time.format("%H:%M")
+							)),
+							TagValue::Duration(seconds) => {
+								egui::RichText::new(format!("⏱ {}", format_duration(*seconds)))
+							}
 							TagValue::Text(t) => egui::RichText::new(t.clone()),
 							TagValue::List(_)
 							| TagValue::Dictionary(_)
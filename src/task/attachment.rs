@@ -0,0 +1,240 @@
+use std::{
+	path::{Path, PathBuf},
+	process::Command,
+};
+
+use uuid::Uuid;
+
+use crate::data_dir::DataDirError;
+
+/// A file or a link attached to a task.
+#[derive(Debug, Clone, PartialEq, Eq, serde::Serialize, serde::Deserialize)]
+pub enum Attachment {
+	/// A file where it was picked, or copied into the attachments folder of the task
+	File(PathBuf),
+	Url(String),
+}
+
+impl Attachment {
+	/// Reads a URL when the text has a scheme like `https://`, and a file path otherwise.
+	pub fn from_text(text: &str) -> Self {
+		let text = text.trim();
+
+		match text.split_once("://") {
+			Some((scheme, _))
+				if !scheme.is_empty()
+					&& scheme.chars().all(|c| c.is_ascii_alphanumeric() || "+-.".contains(c)) =>
+			{
+				Self::Url(text.to_string())
+			}
+			_ => Self::File(PathBuf::from(text)),
+		}
+	}
+
+	/// The file name, or the whole URL.
+	pub fn name(&self) -> String {
+		match self {
+			Self::File(path) => path
+				.file_name()
+				.unwrap_or(path.as_os_str())
+				.to_string_lossy()
+				.to_string(),
+			Self::Url(url) => url.clone(),
+		}
+	}
+
+	/// Opens the attachment with the program the system uses for it.
+	pub fn open(&self, ctx: &egui::Context) -> Result<(), AttachmentError> {
+		match self {
+			Self::File(path) => {
+				if !path.exists() {
+					return Err(AttachmentError::Missing(path.clone()));
+				}
+
+				open_with_system(path)?;
+			}
+			Self::Url(url) => {
+				ctx.output_mut(|o| {
+					o.open_url = Some(egui::OpenUrl::new_tab(url));
+				});
+			}
+		}
+
+		Ok(())
+	}
+
+	/// The same attachment for a copy of the task `from`, whose files were copied into the folder
+	/// of `to` by [`copy_task_files`].
+	pub fn for_copy(&self, from: &Uuid, to: &Uuid) -> Self {
+		match (self, crate::data_dir()) {
+			(Self::File(path), Ok(data_dir)) => {
+				match path.strip_prefix(data_dir.attachments().join(from.to_string())) {
+					Ok(relative) => Self::File(data_dir.attachments().join(to.to_string()).join(relative)),
+					Err(_) => self.clone(),
+				}
+			}
+			_ => self.clone(),
+		}
+	}
+}
+
+impl std::fmt::Display for Attachment {
+	fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+		match self {
+			Self::File(path) => write!(f, "{}", path.display()),
+			Self::Url(url) => write!(f, "{}", url),
+		}
+	}
+}
+
+#[cfg(target_os = "windows")]
+fn open_with_system(path: &Path) -> std::io::Result<()> {
+	Command::new("cmd").args(["/C", "start", ""]).arg(path).spawn()?;
+	Ok(())
+}
+
+#[cfg(target_os = "macos")]
+fn open_with_system(path: &Path) -> std::io::Result<()> {
+	Command::new("open").arg(path).spawn()?;
+	Ok(())
+}
+
+#[cfg(not(any(target_os = "windows", target_os = "macos")))]
+fn open_with_system(path: &Path) -> std::io::Result<()> {
+	Command::new("xdg-open").arg(path).spawn()?;
+	Ok(())
+}
+
+fn task_folder(uuid: &Uuid) -> Result<PathBuf, AttachmentError> {
+	Ok(crate::data_dir()?.attachments().join(uuid.to_string()))
+}
+
+/// Copies a file into the attachments folder of the task, and returns the attachment to the copy.
+/// A number is added to the name of the copy if another file of the task has the same name.
+pub fn copy_into_task_folder(path: &Path, uuid: &Uuid) -> Result<Attachment, AttachmentError> {
+	if !path.is_file() {
+		return Err(AttachmentError::Missing(path.to_path_buf()));
+	}
+
+	let folder = task_folder(uuid)?;
+	std::fs::create_dir_all(&folder)?;
+
+	let stem = path.file_stem().unwrap_or_default().to_string_lossy();
+	let extension = path
+		.extension()
+		.map(|extension| format!(".{}", extension.to_string_lossy()))
+		.unwrap_or_default();
+
+	let mut copy = folder.join(format!("{}{}", stem, extension));
+	let mut i = 2;
+
+	while copy.exists() {
+		copy = folder.join(format!("{} ({}){}", stem, i, extension));
+		i += 1;
+	}
+
+	std::fs::copy(path, &copy)?;
+	Ok(Attachment::File(copy))
+}
+
+/// Copies the attachments folder of the task `from`, if it has one, to the task `to`.
+pub fn copy_task_files(from: &Uuid, to: &Uuid) -> Result<(), AttachmentError> {
+	let from = task_folder(from)?;
+
+	if !from.is_dir() {
+		return Ok(());
+	}
+
+	let to = task_folder(to)?;
+	std::fs::create_dir_all(&to)?;
+
+	for entry in std::fs::read_dir(from)? {
+		let entry = entry?;
+
+		if entry.metadata()?.is_file() {
+			std::fs::copy(entry.path(), to.join(entry.file_name()))?;
+		}
+	}
+
+	Ok(())
+}
+
+/// Removes the files copied into the attachments folder of the task.
+pub fn remove_task_files(uuid: &Uuid) -> Result<(), AttachmentError> {
+	let folder = task_folder(uuid)?;
+
+	if folder.is_dir() {
+		std::fs::remove_dir_all(folder)?;
+	}
+
+	Ok(())
+}
+
+/// Removes the files of the attachments folder of the task that none of `attachments` points to
+/// anymore.
+pub fn remove_unlisted_files(uuid: &Uuid, attachments: &[Attachment]) -> Result<(), AttachmentError> {
+	let folder = task_folder(uuid)?;
+
+	if !folder.is_dir() {
+		return Ok(());
+	}
+
+	for entry in std::fs::read_dir(folder)? {
+		let path = entry?.path();
+
+		if path.is_file() && !attachments.contains(&Attachment::File(path.clone())) {
+			std::fs::remove_file(path)?;
+		}
+	}
+
+	Ok(())
+}
+
+#[derive(Debug, thiserror::Error)]
+pub enum AttachmentError {
+	#[error("Could not access data directory: {0}")]
+	DataDirError(
+		#[from]
+		#[source]
+		&'static DataDirError,
+	),
+
+	#[error("IO error: {0}")]
+	IOError(
+		#[from]
+		#[source]
+		std::io::Error,
+	),
+
+	#[error("File not found: {}", .0.display())]
+	Missing(PathBuf),
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	#[test]
+	fn from_text() {
+		assert_eq!(
+			Attachment::from_text(" https://example.com/a?b=c "),
+			Attachment::Url(String::from("https://example.com/a?b=c"))
+		);
+		assert_eq!(
+			Attachment::from_text("obsidian://open?vault=notes"),
+			Attachment::Url(String::from("obsidian://open?vault=notes"))
+		);
+		assert_eq!(
+			Attachment::from_text("/home/me/report.pdf"),
+			Attachment::File(PathBuf::from("/home/me/report.pdf"))
+		);
+		assert_eq!(Attachment::from_text("/home/me/report.pdf").name(), "report.pdf");
+
+		for attachment in [
+			Attachment::from_text("C:\\Users\\me\\notes.txt"),
+			Attachment::from_text("https://example.com"),
+		] {
+			assert_eq!(Attachment::from_text(attachment.to_string().as_str()), attachment);
+		}
+	}
+}
@@ -1,18 +1,32 @@
-use std::cmp::Ordering;
+use std::{
+	collections::HashMap,
+	path::{Path, PathBuf},
+	time::Duration,
+};
 
 use uuid::Uuid;
 
 use crate::{
+	handle_toast_error,
 	scripts::{
-		filter::FilterList, sorting::SortingList, value::AnyIntoPocketPyValue, PocketPyScript,
+		decorator::{run_decorator_script, Decoration, DecoratorList},
+		filter::{run_filter_script, FilterList},
+		runner,
+		sorting::{run_sorting_script, SortKey, SortingList},
+		PocketPyScript, PocketPyScriptError,
 	},
+	session::Session,
 	toast_error,
+	views::{manual_positions, ManualOrders},
 };
 
 use super::{list::TaskList, Task};
 
 pub struct TaskDisplayList {
 	tasks: Vec<Uuid>,
+	/// Chips of the displayed tasks, with the name of the decorator script that made each
+	decorations: HashMap<Uuid, Vec<(String, Decoration)>>,
+	incomplete: bool,
 }
 
 impl TaskDisplayList {
@@ -20,52 +34,38 @@ impl TaskDisplayList {
 		task_list: &TaskList,
 		filter_list: &FilterList,
 		sorting_list: &SortingList,
+		decorator_list: &DecoratorList,
 		parent_task: Option<Uuid>,
+		search: &str,
+		manual_orders: &ManualOrders,
 	) -> Self {
 		let task_list: Vec<&Task> = task_list.tasks().collect();
-		let mut task_passes = vec![true; task_list.len()];
-		let mut task_orderings: Vec<Vec<f64>> = Vec::new();
+		let positions = manual_positions(manual_orders, task_list.iter().copied());
+		let mut task_passes: Vec<bool> = task_list
+			.iter()
+			.map(|task| task.matches_search(search))
+			.collect();
+		let mut task_orderings: Vec<(Vec<SortKey>, bool)> = Vec::new();
+		let mut incomplete = false;
 
 		match crate::data_dir() {
 			Ok(data_dir) => {
 				for passes in filter_list.iter_set().filter_map(|filter_script_name| {
-					match PocketPyScript::load(
-						data_dir
-							.filter_scripts()
-							.join(filter_script_name)
-							.with_extension("py"),
-					) {
-						Ok(script) => match script.execute_function_for::<bool>(
-							crate::app::script_lock(),
-							script.name.as_str(),
-							[
-								(
-									"task",
-									task_list
-										.iter()
-										.map(|task| {
-											Box::new((*task).clone()) as AnyIntoPocketPyValue
-										})
-										.collect(),
-								),
-								(
-									"parent",
-									match parent_task {
-										Some(parent_task) => std::iter::once(parent_task)
-											.cycle()
-											.take(task_list.len())
-											.map(|u| Box::new(u) as AnyIntoPocketPyValue)
-											.collect(),
-										_ => std::iter::once(())
-											.cycle()
-											.take(task_list.len())
-											.map(|none| Box::new(none) as AnyIntoPocketPyValue)
-											.collect(),
-									},
-								),
-							],
+					let path = data_dir.filter_scripts().join(filter_script_name).with_extension("py");
+
+					match PocketPyScript::load(&path) {
+						Ok(script) if skip_script(&path, &script, &mut incomplete) => None,
+						Ok(script) => match run_filter_script(
+							&script,
+							&task_list,
+							parent_task,
+							filter_list.param_values(filter_script_name),
 						) {
 							Ok(passes) => Some(passes),
+							Err(PocketPyScriptError::Timeout(limit)) => {
+								disable_timed_out_script(path, &script, limit);
+								None
+							}
 							Err(e) => {
 								toast_error!("Error in filter script:\n{}", e);
 								None
@@ -81,24 +81,24 @@ impl TaskDisplayList {
 				}
 
 				for ordering in sorting_list.iter_set().filter_map(|sorting_script_name| {
-					match PocketPyScript::load(
-						data_dir
-							.sorting_scripts()
-							.join(sorting_script_name)
-							.with_extension("py"),
-					) {
-						Ok(script) => match script.execute_function_for::<f64>(
-							crate::app::script_lock(),
-							script.name.as_str(),
-							[(
-								"task",
-								task_list
-									.iter()
-									.map(|task| Box::new((*task).clone()) as AnyIntoPocketPyValue)
-									.collect(),
-							)],
+					let path = data_dir.sorting_scripts().join(sorting_script_name).with_extension("py");
+
+					match PocketPyScript::load(&path) {
+						Ok(script) if skip_script(&path, &script, &mut incomplete) => None,
+						Ok(script) => match run_sorting_script(
+							&script,
+							&task_list,
+							&positions,
+							sorting_list.param_values(sorting_script_name),
 						) {
-							Ok(orderings) => Some(orderings),
+							Ok(orderings) => Some((
+								orderings,
+								sorting_list.is_descending(sorting_script_name).unwrap_or(false),
+							)),
+							Err(PocketPyScriptError::Timeout(limit)) => {
+								disable_timed_out_script(path, &script, limit);
+								None
+							}
 							Err(e) => {
 								toast_error!("Error in sorting script:\n{}", e);
 								None
@@ -113,51 +113,115 @@ impl TaskDisplayList {
 			Err(_) => {}
 		}
 
-		let mut tasks: Vec<(Uuid, Vec<f64>)> = task_list
+		let mut tasks: Vec<(usize, &Task)> = task_list
 			.into_iter()
 			.zip(task_passes)
 			.enumerate()
-			.filter_map(|(idx, (task, pass))| {
-				if pass {
-					Some((
-						task.get_uuid().clone(),
-						task_orderings.iter().map(|o| o[idx]).collect::<Vec<f64>>(),
-					))
-				} else {
-					None
-				}
-			})
+			.filter_map(|(idx, (task, pass))| pass.then_some((idx, task)))
 			.collect();
 
-		tasks.sort_unstable_by(|(_, orderings_a), (_, orderings_b)| {
-			let mut final_ordering = Ordering::Equal;
+		// Tasks with equal keys keep their manual order, so that their order doesn't change between frames
+		tasks.sort_by(|(idx_a, task_a), (idx_b, task_b)| {
+			task_orderings
+				.iter()
+				.map(|(keys, descending)| keys[*idx_a].compare_directed(&keys[*idx_b], *descending))
+				.find(|ordering| ordering.is_ne())
+				.unwrap_or_else(|| {
+					positions[task_a.get_uuid()]
+						.cmp(&positions[task_b.get_uuid()])
+						.then_with(|| task_a.get_uuid().cmp(task_b.get_uuid()))
+				})
+		});
 
-			for (a, b) in orderings_a.iter().zip(orderings_b.iter()) {
-				let a = if a.is_finite() { *a } else { 0.0 };
-				let b = if b.is_finite() { *b } else { 0.0 };
+		let displayed: Vec<&Task> = tasks.into_iter().map(|(_, task)| task).collect();
+		let mut decorations: HashMap<Uuid, Vec<(String, Decoration)>> = HashMap::new();
 
-				match a.partial_cmp(&b).expect("always finite") {
-					Ordering::Equal => {}
-					Ordering::Less => {
-						final_ordering = Ordering::Less;
-						break;
-					}
-					Ordering::Greater => {
-						final_ordering = Ordering::Greater;
-						break;
+		if let Ok(data_dir) = crate::data_dir() {
+			for decorator_script_name in decorator_list.iter_set() {
+				let path = data_dir.decorator_scripts().join(decorator_script_name).with_extension("py");
+
+				let chips = match PocketPyScript::load(&path) {
+					Ok(script) if skip_script(&path, &script, &mut incomplete) => continue,
+					Ok(script) => match run_decorator_script(
+						&script,
+						&displayed,
+						decorator_list.param_values(decorator_script_name),
+					) {
+						Ok(chips) => chips,
+						Err(PocketPyScriptError::Timeout(limit)) => {
+							disable_timed_out_script(path, &script, limit);
+							continue;
+						}
+						Err(e) => {
+							toast_error!("Error in decorator script:\n{}", e);
+							continue;
+						}
+					},
+					Err(_) => continue,
+				};
+
+				for (task, chip) in displayed.iter().zip(chips) {
+					if let Some(chip) = chip {
+						decorations
+							.entry(*task.get_uuid())
+							.or_default()
+							.push((decorator_script_name.to_string(), chip));
 					}
 				}
 			}
-
-			final_ordering
-		});
+		}
 
 		Self {
-			tasks: tasks.into_iter().map(|(task, _)| task).collect(),
+			tasks: displayed.iter().map(|task| *task.get_uuid()).collect(),
+			decorations,
+			incomplete,
 		}
 	}
 
 	pub fn tasks(&self) -> impl Iterator<Item = &Uuid> {
 		self.tasks.iter()
 	}
+
+	/// Chips of a displayed task, with the name of the decorator script that made each.
+	pub fn decorations(&self, uuid: &Uuid) -> &[(String, Decoration)] {
+		self.decorations.get(uuid).map(Vec::as_slice).unwrap_or_default()
+	}
+
+	/// Whether some scripts were skipped because a standalone script was running, in which case
+	/// the list should be rebuilt once it finishes.
+	pub fn is_incomplete(&self) -> bool {
+		self.incomplete
+	}
+}
+
+/// Whether a script should not be run, either because it exceeded its time limit before or
+/// because a standalone script holds the interpreter.
+fn skip_script(path: &Path, script: &PocketPyScript, incomplete: &mut bool) -> bool {
+	if Session::current().is_timed_out(path, script.code_hash()) {
+		return true;
+	}
+
+	if runner::is_running() {
+		*incomplete = true;
+		return true;
+	}
+
+	false
+}
+
+fn disable_timed_out_script(path: PathBuf, script: &PocketPyScript, limit: Duration) {
+	let code_hash = script.code_hash();
+
+	handle_toast_error!(
+		"Failed to save session: {}",
+		Session::mutate(|session| {
+			session.timed_out_scripts.insert(path, code_hash);
+		})
+	);
+
+	toast_error!(
+		"Script {} took longer than {}ms and was disabled until edited",
+		script.name,
+		limit.as_millis()
+	);
 }
@@ -1,10 +1,21 @@
-use std::{collections::HashMap, time::Duration};
+use std::{
+	collections::{HashMap, HashSet},
+	time::Duration,
+};
 
 use uuid::Uuid;
 
-use crate::{data_dir::DataDirError, tag::TagValue};
+use crate::{
+	data_dir::DataDirError,
+	handle_toast_error,
+	scripts::hooks::{self, HookEvent},
+	settings::Settings,
+	tag::{Tag, TagValue},
+};
 
-use super::{NormalTaskData, Task, TaskError, TaskPath, TaskTypeData};
+use super::{
+	attachment, markdown, scheduled::ScheduledTask, NormalTaskData, Task, TaskError, TaskPath, TaskTypeData,
+};
 
 pub struct TaskList<T = NormalTaskData> {
 	tasks: HashMap<Uuid, Task<T>>,
@@ -49,10 +60,101 @@ impl<T: TaskTypeData> TaskList<T> {
 		self.tasks.values_mut()
 	}
 
+	pub fn get(&self, id: &Uuid) -> Option<&Task<T>> {
+		self.tasks.get(id)
+	}
+
 	pub fn get_mut(&mut self, id: &Uuid) -> Option<&mut Task<T>> {
 		self.tasks.get_mut(id)
 	}
 
+	/// A task is a root if none of its `subtask_of` tags point to a task of this list.
+	pub fn is_root(&self, task: &Task<T>) -> bool {
+		!task.tags.iter().any(|tag| {
+			tag.name.as_str() == "subtask_of"
+				&& matches!(tag.value, Some(TagValue::TaskReference(parent)) if self.tasks.contains_key(&parent))
+		})
+	}
+
+	/// Whether `uuid` is `ancestor` or one of its subtasks, at any depth.
+	pub fn is_in_tree_of(&self, uuid: &Uuid, ancestor: &Uuid) -> bool {
+		let mut visited = HashSet::new();
+		let mut to_visit = vec![*uuid];
+
+		while let Some(current) = to_visit.pop() {
+			if current == *ancestor {
+				return true;
+			}
+
+			if visited.insert(current) {
+				if let Some(task) = self.tasks.get(&current) {
+					to_visit.extend(task.parents());
+				}
+			}
+		}
+
+		false
+	}
+
+	/// Returns every task of the list, each root followed by its subtasks, along with their depth.
+	pub fn tree_order(&self) -> Vec<(Uuid, usize)> {
+		tree_order(self.tasks.values(), None)
+	}
+
+	/// Returns `root` followed by all of its subtasks, along with their depth.
+	pub fn subtree_order(&self, root: &Uuid) -> Vec<(Uuid, usize)> {
+		tree_order(self.tasks.values(), Some(root))
+	}
+
+	/// Clones the task `root` and all of its subtasks (recursively) into new tasks of another type.
+	///
+	/// Every cloned task gets a fresh uuid, and `subtask_of` references between the cloned tasks
+	/// are rewired to the new uuids. The root is always the first element of the result, each clone
+	/// coming with the uuid of its original. Attachments to files copied into the data folder point
+	/// to the folders of the new tasks, the files are copied by [`attachment::copy_task_files`]
+	/// once the clones are saved.
+	pub fn clone_tree<NewT: TaskTypeData>(&self, root: &Uuid) -> Vec<(Uuid, Task<NewT>)> {
+		let old_uuids = self.subtree_order(root);
+
+		let new_uuids: HashMap<Uuid, Uuid> = old_uuids
+			.iter()
+			.map(|(old_uuid, _)| (*old_uuid, Uuid::new_v4()))
+			.collect();
+
+		old_uuids
+			.into_iter()
+			.map(|(old_uuid, _)| {
+				let old_task = self.tasks.get(&old_uuid).expect("collected from this list");
+
+				let mut new_task = Task::<NewT> {
+					uuid: new_uuids[&old_uuid],
+					name: old_task.name.clone(),
+					description: old_task.description.clone(),
+					tags: old_task.tags.clone(),
+					attachments: old_task
+						.attachments
+						.iter()
+						.map(|attachment| attachment.for_copy(&old_uuid, &new_uuids[&old_uuid]))
+						.collect(),
+					notes: old_task.notes.clone(),
+					..Default::default()
+				};
+
+				for tag in new_task.tags.iter_mut() {
+					if tag.name.as_str() == "subtask_of" {
+						if let Some(TagValue::TaskReference(parent)) = &mut tag.value {
+							if let Some(new_parent) = new_uuids.get(parent) {
+								*parent = *new_parent;
+							}
+						}
+					}
+				}
+
+				(old_uuid, new_task)
+			})
+			.collect()
+	}
+
 	pub fn add_task(&mut self, task: Task<T>) -> Result<(), TaskError> {
 		task.save(self.path)?;
 		self.tasks.insert(task.uuid, task);
@@ -60,8 +162,19 @@ impl<T: TaskTypeData> TaskList<T> {
 	}
 
 	pub fn delete_task(&mut self, uuid: &Uuid) -> Result<i32, TaskError> {
+		self.remove_task(uuid, HookEvent::TaskDeleted)
+	}
+
+	/// Deletes the task and the subtasks that have no other parent, `event` being triggered for
+	/// each of them.
+	fn remove_task(&mut self, uuid: &Uuid, event: HookEvent) -> Result<i32, TaskError> {
 		if let Some(task) = self.tasks.remove(uuid) {
 			task.delete(self.path)?;
+
+			// A task deleted before its first save was never created for the hooks
+			if self.path == TaskPath::Tasks && !hooks::take_unsaved(uuid) {
+				hooks::trigger(event, Some(task.hook_snapshot()));
+			}
 		}
 
 		let mut deleted = 1;
@@ -91,7 +204,7 @@ impl<T: TaskTypeData> TaskList<T> {
 		}
 
 		for uuid in to_delete {
-			deleted += self.delete_task(&uuid)?;
+			deleted += self.remove_task(&uuid, event)?;
 		}
 
 		Ok(deleted)
@@ -115,6 +228,11 @@ impl<T: TaskTypeData> TaskList<T> {
 		let mut error_list = Vec::new();
 
 		for uuid in to_delete {
+			// Already gone if it was a subtask of a deleted task
+			if !self.tasks.contains_key(&uuid) {
+				continue;
+			}
+
 			match self.delete_task(&uuid) {
 				Ok(a) => amount += a,
 				Err(e) => error_list.push(e),
@@ -124,6 +242,108 @@ impl<T: TaskTypeData> TaskList<T> {
 		(amount, error_list)
 	}
 
+	/// Renames a tag in every task, merging it into the tags already named `to`. Returns how many
+	/// tasks were changed.
+	pub fn rename_tag(&mut self, from: &str, to: &str) -> Result<usize, TaskError> {
+		let mut changed = 0;
+
+		for task in self.tasks.values_mut() {
+			if task.rename_tag(from, to) {
+				task.save(self.path)?;
+				changed += 1;
+			}
+		}
+
+		Ok(changed)
+	}
+
+	/// Applies `change` to the tasks of `uuids`, saving the ones it changed. Returns how many
+	/// tasks were changed.
+	fn change_tasks(
+		&mut self,
+		uuids: &[Uuid],
+		mut change: impl FnMut(&mut Task<T>) -> bool,
+	) -> Result<usize, TaskError> {
+		let mut changed = 0;
+
+		for uuid in uuids {
+			if let Some(task) = self.tasks.get_mut(uuid) {
+				if change(task) {
+					task.save(self.path)?;
+					changed += 1;
+				}
+			}
+		}
+
+		Ok(changed)
+	}
+
+	pub fn add_tag(&mut self, uuids: &[Uuid], tag: &Tag) -> Result<usize, TaskError> {
+		self.change_tasks(uuids, |task| task.add_tag(tag))
+	}
+
+	pub fn set_tag(&mut self, uuids: &[Uuid], tag: &Tag) -> Result<usize, TaskError> {
+		self.change_tasks(uuids, |task| task.set_tag(tag))
+	}
+
+	pub fn remove_tag(&mut self, uuids: &[Uuid], name: &str) -> Result<usize, TaskError> {
+		self.change_tasks(uuids, |task| task.remove_tags(name))
+	}
+
+	/// Moves the tasks under `parent`, or to the root of the list. Tasks that `parent` is a
+	/// subtask of are skipped, as they would become their own subtask.
+	pub fn move_under(&mut self, uuids: &[Uuid], parent: Option<Uuid>) -> Result<usize, TaskError> {
+		let movable: Vec<Uuid> = uuids
+			.iter()
+			.filter(|uuid| parent.is_none_or(|parent| !self.is_in_tree_of(&parent, uuid)))
+			.copied()
+			.collect();
+
+		self.change_tasks(&movable, |task| task.set_parent(parent))
+	}
+
+	/// Marks the tasks that aren't done yet as done. Returns how many were marked.
+	pub fn mark_done(&mut self, uuids: &[Uuid]) -> Result<usize, TaskError> {
+		let mut marked = 0;
+
+		for uuid in uuids {
+			if let Some(task) = self.tasks.get_mut(uuid).filter(|task| !task.is_done()) {
+				task.mark_done(self.path)?;
+				marked += 1;
+			}
+		}
+
+		Ok(marked)
+	}
+
+	/// Deletes the tasks along with their subtasks. Returns how many tasks were deleted.
+	pub fn delete_tasks(&mut self, uuids: &[Uuid]) -> Result<i32, TaskError> {
+		let mut deleted = 0;
+
+		for uuid in uuids {
+			// Already gone if it was a subtask of a deleted task
+			if self.tasks.contains_key(uuid) {
+				deleted += self.delete_task(uuid)?;
+			}
+		}
+
+		Ok(deleted)
+	}
+
+	/// The tasks of `uuids` that aren't in the tree of another one of them.
+	fn tree_roots(&self, uuids: &[Uuid]) -> Vec<Uuid> {
+		uuids
+			.iter()
+			.filter(|uuid| {
+				self.tasks.contains_key(uuid)
+					&& !uuids
+						.iter()
+						.any(|other| other != *uuid && self.is_in_tree_of(uuid, other))
+			})
+			.copied()
+			.collect()
+	}
+
 	pub fn save_all(&self) {
 		for task in self.tasks.values() {
 			if let Err(e) = task.save(self.path) {
@@ -136,6 +356,169 @@ impl<T: TaskTypeData> TaskList<T> {
 	}
 }
 
+/// Orders `tasks` so that each one is followed by its subtasks, along with their depth, keeping
+/// the given order among siblings. With `root`, only that task and its subtasks are ordered.
+///
+/// Only the tasks of `tasks` count as parents, so tasks whose parents are all missing are roots.
+pub fn tree_order<'a, T: TaskTypeData + 'a>(
+	tasks: impl IntoIterator<Item = &'a Task<T>>,
+	root: Option<&Uuid>,
+) -> Vec<(Uuid, usize)> {
+	let tasks: Vec<&Task<T>> = tasks.into_iter().collect();
+	let known: HashSet<Uuid> = tasks.iter().map(|task| task.uuid).collect();
+
+	let mut subtasks: HashMap<Uuid, Vec<Uuid>> = HashMap::new();
+	let mut roots = Vec::new();
+
+	for task in tasks.iter() {
+		let mut parents = task.parents().filter(|parent| known.contains(parent)).peekable();
+
+		if parents.peek().is_none() {
+			roots.push(task.uuid);
+		}
+
+		for parent in parents {
+			subtasks.entry(parent).or_default().push(task.uuid);
+		}
+	}
+
+	fn push_tree_order(
+		uuid: Uuid,
+		depth: usize,
+		subtasks: &HashMap<Uuid, Vec<Uuid>>,
+		order: &mut Vec<(Uuid, usize)>,
+		visited: &mut HashSet<Uuid>,
+	) {
+		if !visited.insert(uuid) {
+			return;
+		}
+
+		order.push((uuid, depth));
+
+		for subtask in subtasks.get(&uuid).into_iter().flatten() {
+			push_tree_order(*subtask, depth + 1, subtasks, order, visited);
+		}
+	}
+
+	let mut order = Vec::new();
+	let mut visited = HashSet::new();
+
+	match root {
+		Some(root) if known.contains(root) => {
+			push_tree_order(*root, 0, &subtasks, &mut order, &mut visited);
+		}
+		Some(_) => {}
+		None => {
+			// Tasks in a subtask cycle have no root, they are ordered anyway
+			for uuid in roots.into_iter().chain(tasks.iter().map(|task| task.uuid)) {
+				push_tree_order(uuid, 0, &subtasks, &mut order, &mut visited);
+			}
+		}
+	}
+
+	order
+}
+
+impl TaskList<NormalTaskData> {
+	/// Copies of the task, and of its subtasks with `with_subtasks`, with new uuids and the uuids
+	/// of their originals. The copy stays a subtask of the same parents.
+	fn duplicate_tree(&self, uuid: &Uuid, with_subtasks: bool) -> Vec<(Uuid, Task)> {
+		let mut copies = self.clone_tree::<NormalTaskData>(uuid);
+
+		if !with_subtasks {
+			copies.truncate(1);
+		}
+
+		copies
+	}
+
+	/// Adds a copy of the task, and of its subtasks with `with_subtasks`. Returns the uuid of the
+	/// copy.
+	pub fn duplicate(&mut self, uuid: &Uuid, with_subtasks: bool) -> Result<Option<Uuid>, TaskError> {
+		let copies = self.duplicate_tree(uuid, with_subtasks);
+		let copy_uuid = copies.first().map(|(_, copy)| copy.uuid);
+
+		for (original, copy) in copies {
+			let (new_uuid, snapshot) = (copy.uuid, copy.hook_snapshot());
+			self.add_task(copy)?;
+			handle_toast_error!("Could not copy attachments: {}", attachment::copy_task_files(&original, &new_uuid));
+			hooks::trigger(HookEvent::TaskCreated, Some(snapshot));
+		}
+
+		Ok(copy_uuid)
+	}
+
+	/// Moves the checklist item on `line` of the description of the task into a new subtask of it,
+	/// done if the item was checked. Returns the uuid of the subtask.
+	pub fn make_checklist_item_subtask(&mut self, uuid: &Uuid, line: usize) -> Result<Option<Uuid>, TaskError> {
+		let Some((description, item, checked)) = self
+			.get(uuid)
+			.and_then(|task| markdown::take_checklist_item(&task.description, line))
+		else {
+			return Ok(None);
+		};
+
+		let mut subtask = Settings::get().default_task.clone();
+		subtask.new_uuid();
+		subtask.name = item;
+		subtask.set_parent(Some(*uuid));
+
+		if checked {
+			subtask.add_tag(&Tag::new(String::from("done"), None));
+		}
+
+		let subtask_uuid = subtask.uuid;
+		let snapshot = subtask.hook_snapshot();
+		self.add_task(subtask)?;
+		hooks::trigger(HookEvent::TaskCreated, Some(snapshot));
+
+		if let Some(task) = self.get_mut(uuid) {
+			task.description = description;
+			task.save(TaskPath::Tasks)?;
+			hooks::trigger(HookEvent::TaskSaved, Some(task.hook_snapshot()));
+		}
+
+		Ok(Some(subtask_uuid))
+	}
+
+	/// The task and its subtasks as scheduled tasks, not done, the task taking `type_data`.
+	fn scheduled_tree(&self, root: &Uuid, type_data: &ScheduledTask) -> Vec<(Uuid, Task<ScheduledTask>)> {
+		let mut tasks = self.clone_tree::<ScheduledTask>(root);
+
+		for (_, task) in tasks.iter_mut() {
+			task.remove_tags("done");
+		}
+
+		if let Some((_, root)) = tasks.first_mut() {
+			root.type_data = type_data.clone();
+		}
+
+		tasks
+	}
+
+	/// Moves the tasks and their subtasks to the scheduled tasks, where they are spawned again
+	/// following `type_data`. Returns how many scheduled tasks were created.
+	pub fn convert_to_scheduled(
+		&mut self,
+		uuids: &[Uuid],
+		type_data: &ScheduledTask,
+	) -> Result<usize, TaskError> {
+		let mut created = 0;
+
+		for root in self.tree_roots(uuids) {
+			for (original, task) in self.scheduled_tree(&root, type_data) {
+				task.save(TaskPath::Scheduled)?;
+				handle_toast_error!("Could not copy attachments: {}", attachment::copy_task_files(&original, &task.uuid));
+				created += 1;
+			}
+
+			self.remove_task(&root, HookEvent::TaskSnoozed)?;
+		}
+
+		Ok(created)
+	}
+}
+
 #[derive(Debug, thiserror::Error)]
 pub enum TaskListError {
 	#[error("Could not access data directory: {0}")]
@@ -152,3 +535,228 @@ pub enum TaskListError {
 		std::io::Error,
 	),
 }
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	fn task_with_parent(name: &str, parent: Option<Uuid>) -> Task<ScheduledTask> {
+		let mut task = Task::<ScheduledTask> {
+			name: name.to_string(),
+			..Default::default()
+		};
+
+		if let Some(parent) = parent {
+			task.tags.push(Tag::new(
+				String::from("subtask_of"),
+				Some(TagValue::TaskReference(parent)),
+			));
+		}
+
+		task
+	}
+
+	#[test]
+	fn clone_tree() {
+		let root = task_with_parent("root", None);
+		let child_a = task_with_parent("child_a", Some(root.uuid));
+		let child_b = task_with_parent("child_b", Some(root.uuid));
+		let grandchild = task_with_parent("grandchild", Some(child_a.uuid));
+		let other = task_with_parent("other", None);
+
+		let root_uuid = root.uuid;
+
+		let list = TaskList {
+			tasks: [root, child_a, child_b, grandchild, other]
+				.into_iter()
+				.map(|task| (task.uuid, task))
+				.collect(),
+			path: TaskPath::Scheduled,
+		};
+
+		assert_eq!(list.tree_order().len(), 5);
+
+		let (originals, cloned): (Vec<Uuid>, Vec<Task>) = list.clone_tree::<NormalTaskData>(&root_uuid).into_iter().unzip();
+		assert_eq!(cloned.len(), 4);
+		assert_eq!(originals[0], root_uuid);
+		assert_eq!(cloned[0].name, "root");
+
+		let new_uuids: HashSet<Uuid> = cloned.iter().map(|task| task.uuid).collect();
+		assert!(new_uuids.iter().all(|uuid| !list.tasks.contains_key(uuid)));
+
+		for task in &cloned[1..] {
+			let parents: Vec<Uuid> = task
+				.tags
+				.iter()
+				.filter_map(|tag| match tag.value {
+					Some(TagValue::TaskReference(parent)) => Some(parent),
+					_ => None,
+				})
+				.collect();
+
+			assert_eq!(parents.len(), 1);
+			assert!(new_uuids.contains(&parents[0]));
+		}
+
+		let grandchild = cloned
+			.iter()
+			.find(|task| task.name == "grandchild")
+			.unwrap();
+		let child_a = cloned.iter().find(|task| task.name == "child_a").unwrap();
+		assert!(grandchild.is_subtask_of(&child_a.uuid));
+	}
+
+	#[test]
+	fn tree() {
+		let root = task_with_parent("root", None);
+		let child = task_with_parent("child", Some(root.uuid));
+		let grandchild = task_with_parent("grandchild", Some(child.uuid));
+		let orphan = task_with_parent("orphan", Some(Uuid::new_v4()));
+
+		assert_eq!(
+			tree_order([&grandchild, &orphan, &child, &root], None),
+			vec![(orphan.uuid, 0), (root.uuid, 0), (child.uuid, 1), (grandchild.uuid, 2)]
+		);
+		assert_eq!(
+			tree_order([&grandchild, &orphan, &child, &root], Some(&child.uuid)),
+			vec![(child.uuid, 0), (grandchild.uuid, 1)]
+		);
+		assert!(tree_order([&grandchild, &child], Some(&root.uuid)).is_empty());
+
+		// Both in a cycle, shown from the first one met
+		let mut cycle_a = task_with_parent("cycle_a", None);
+		let cycle_b = task_with_parent("cycle_b", Some(cycle_a.uuid));
+		cycle_a.set_parent(Some(cycle_b.uuid));
+		assert_eq!(
+			tree_order([&cycle_b, &cycle_a], None),
+			vec![(cycle_b.uuid, 0), (cycle_a.uuid, 1)]
+		);
+	}
+
+	#[test]
+	fn rename_tag() {
+		let mut task = task_with_parent("task", None);
+		task.tags = ["prio(\"A\")", "priority(\"A\")", "prio(\"B\")", "done"]
+			.into_iter()
+			.map(|tag| tag.parse().unwrap())
+			.collect();
+
+		assert!(task.rename_tag("prio", "priority"));
+		assert_eq!(
+			task.tags,
+			["priority(\"A\")", "priority(\"B\")", "done"]
+				.into_iter()
+				.map(|tag| tag.parse::<Tag>().unwrap())
+				.collect::<Vec<_>>()
+		);
+		assert!(!task.rename_tag("prio", "priority"));
+	}
+
+	#[test]
+	fn bulk_changes() {
+		let root = task_with_parent("root", None);
+		let child = task_with_parent("child", Some(root.uuid));
+		let grandchild = task_with_parent("grandchild", Some(child.uuid));
+		let other = task_with_parent("other", None);
+
+		let uuids = [root.uuid, child.uuid, grandchild.uuid, other.uuid];
+
+		let mut list = TaskList {
+			tasks: [root, child, grandchild, other]
+				.into_iter()
+				.map(|task| (task.uuid, task))
+				.collect(),
+			path: TaskPath::Scheduled,
+		};
+
+		assert!(list.is_in_tree_of(&uuids[2], &uuids[0]));
+		assert!(!list.is_in_tree_of(&uuids[0], &uuids[2]));
+		assert_eq!(list.tree_roots(&uuids[..3]), vec![uuids[0]]);
+		assert_eq!(list.tree_roots(&[uuids[2], uuids[3]]), vec![uuids[2], uuids[3]]);
+
+		let task = list.get_mut(&uuids[1]).unwrap();
+		assert!(!task.set_parent(Some(uuids[0])));
+		assert!(task.set_parent(Some(uuids[3])));
+		assert_eq!(task.parents().collect::<Vec<_>>(), vec![uuids[3]]);
+		assert!(task.set_parent(None));
+		assert!(task.parents().next().is_none());
+
+		let tag: Tag = "priority(\"A\")".parse().unwrap();
+		assert!(task.add_tag(&tag));
+		assert!(!task.add_tag(&tag));
+		assert!(!task.set_tag(&tag));
+		assert!(task.set_tag(&"priority(\"B\")".parse().unwrap()));
+		assert_eq!(task.tags, vec!["priority(\"B\")".parse::<Tag>().unwrap()]);
+		assert!(task.remove_tags("priority"));
+		assert!(!task.remove_tags("priority"));
+	}
+
+	#[test]
+	fn duplicate_and_schedule() {
+		let parent = task_with_parent("parent", None).convert(NormalTaskData);
+		let mut task = task_with_parent("task", Some(parent.uuid)).convert(NormalTaskData);
+		let mut subtask = task_with_parent("subtask", Some(task.uuid)).convert(NormalTaskData);
+		task.tags.push(Tag::new(String::from("done"), None));
+
+		let source = std::env::temp_dir().join(format!("adhd_materia_attachment_{}.txt", std::process::id()));
+		std::fs::write(&source, "notes").unwrap();
+		task.attachments.push(attachment::copy_into_task_folder(&source, &task.uuid).unwrap());
+		subtask.attachments.push(attachment::copy_into_task_folder(&source, &subtask.uuid).unwrap());
+		std::fs::remove_file(&source).unwrap();
+
+		let task_uuid = task.uuid;
+		let subtask_uuid = subtask.uuid;
+		let attachments = crate::data_dir().unwrap().attachments();
+		let folders = || std::fs::read_dir(&attachments).unwrap().count();
+		let folder_count = folders();
+
+		let mut list = TaskList {
+			tasks: [parent, task, subtask]
+				.into_iter()
+				.map(|task| (task.uuid, task))
+				.collect(),
+			path: TaskPath::Tasks,
+		};
+
+		let copies = list.duplicate_tree(&task_uuid, false);
+		assert_eq!(copies.len(), 1);
+		assert_eq!(copies[0].0, task_uuid);
+		assert_ne!(copies[0].1.uuid, task_uuid);
+		assert_eq!(copies[0].1.tags, list.get(&task_uuid).unwrap().tags);
+
+		let copies = list.duplicate_tree(&task_uuid, true);
+		assert_eq!(copies.len(), 2);
+		assert!(copies[1].1.is_subtask_of(&copies[0].1.uuid));
+
+		// Files are only copied for the tasks that are added
+		assert_eq!(folders(), folder_count);
+
+		let copy_uuid = list.duplicate(&task_uuid, false).unwrap().unwrap();
+		assert_eq!(folders(), folder_count + 1);
+		let Some(attachment::Attachment::File(copied)) = list.get(&copy_uuid).unwrap().attachments.first().cloned() else {
+			panic!("the copy should have the file attachment");
+		};
+		assert!(copied.starts_with(attachments.join(copy_uuid.to_string())));
+		assert_eq!(std::fs::read_to_string(copied).unwrap(), "notes");
+
+		let type_data = ScheduledTask {
+			repeat_mode: crate::task::scheduled::RepeatMode::Weekly,
+			..Default::default()
+		};
+		let scheduled = list.scheduled_tree(&task_uuid, &type_data);
+		assert_eq!(scheduled.len(), 2);
+		assert_eq!(scheduled[0].1.type_data, type_data);
+		assert!(!scheduled[0].1.is_done());
+		assert_eq!(
+			scheduled[0].1.parents().collect::<Vec<_>>(),
+			list.get(&task_uuid).unwrap().parents().collect::<Vec<_>>()
+		);
+
+		list.delete_task(&copy_uuid).unwrap();
+		assert_eq!(folders(), folder_count);
+
+		for uuid in [task_uuid, subtask_uuid] {
+			attachment::remove_task_files(&uuid).unwrap();
+		}
+	}
+}
@@ -0,0 +1,465 @@
+/// A line of a task description, as markdown sees it.
+#[derive(Debug, Clone, PartialEq)]
+enum Line<'a> {
+	Blank,
+	Text(&'a str),
+	Heading(usize, &'a str),
+	Quote(&'a str),
+	Rule,
+	/// A ```` ``` ```` line, opening or closing a code block
+	CodeFence,
+	Code(&'a str),
+	ListItem {
+		indent: usize,
+		/// `•` for bullets, or the number of numbered items, like `1.`
+		marker: &'a str,
+		text: &'a str,
+	},
+	ChecklistItem {
+		indent: usize,
+		checked: bool,
+		text: &'a str,
+	},
+}
+
+/// A piece of text with a single style.
+#[derive(Debug, Clone, Default, PartialEq)]
+struct Span {
+	text: String,
+	bold: bool,
+	italic: bool,
+	strikethrough: bool,
+	code: bool,
+	link: Option<String>,
+}
+
+/// A click on a checklist item of a shown description, with the index of its line.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ChecklistClick {
+	Toggle(usize),
+	MakeSubtask(usize),
+}
+
+/// Lines are split on `\n` only, so that their indices match the ones the checklist edits use.
+fn parse_lines(text: &str) -> Vec<Line<'_>> {
+	let mut in_code = false;
+
+	text.split('\n')
+		.map(|line| {
+			let line = line.strip_suffix('\r').unwrap_or(line);
+
+			if line.trim_start().starts_with("```") {
+				in_code = !in_code;
+				Line::CodeFence
+			} else if in_code {
+				Line::Code(line)
+			} else {
+				parse_line(line)
+			}
+		})
+		.collect()
+}
+
+fn parse_line(line: &str) -> Line<'_> {
+	let trimmed = line.trim_start();
+	let indent = line[..line.len() - trimmed.len()]
+		.chars()
+		.map(|c| if c == '\t' { 2 } else { 1 })
+		.sum::<usize>()
+		/ 2;
+
+	if trimmed.is_empty() {
+		return Line::Blank;
+	}
+
+	let hashes = trimmed.chars().take_while(|c| *c == '#').count();
+
+	if (1..=6).contains(&hashes) {
+		let text = &trimmed[hashes..];
+
+		if text.is_empty() || text.starts_with(' ') {
+			return Line::Heading(hashes, text.trim());
+		}
+	}
+
+	if let Some(text) = trimmed.strip_prefix('>') {
+		return Line::Quote(text.trim_start());
+	}
+
+	let compact: String = trimmed.chars().filter(|c| !c.is_whitespace()).collect();
+
+	if compact.len() >= 3 && ['-', '*', '_'].iter().any(|c| compact.chars().all(|other| other == *c)) {
+		return Line::Rule;
+	}
+
+	if let Some(rest) = ["- ", "* ", "+ "].iter().find_map(|bullet| trimmed.strip_prefix(bullet)) {
+		for (prefix, checked) in [("[ ]", false), ("[x]", true), ("[X]", true)] {
+			if let Some(text) = rest.strip_prefix(prefix) {
+				if text.is_empty() || text.starts_with(' ') {
+					return Line::ChecklistItem {
+						indent,
+						checked,
+						text: text.trim_start(),
+					};
+				}
+			}
+		}
+
+		return Line::ListItem {
+			indent,
+			marker: "•",
+			text: rest,
+		};
+	}
+
+	let digits = trimmed.chars().take_while(char::is_ascii_digit).count();
+
+	if digits > 0 && trimmed[digits..].starts_with(". ") {
+		return Line::ListItem {
+			indent,
+			marker: &trimmed[..digits + 1],
+			text: &trimmed[digits + 2..],
+		};
+	}
+
+	Line::Text(line)
+}
+
+/// Splits a line into styled spans: `**bold**`, `*italic*` or `_italic_`, `~~strikethrough~~`,
+/// `` `code` ``, `[links](https://…)` and bare `https://` links. `\` escapes the next character.
+fn parse_inline(text: &str) -> Vec<Span> {
+	let mut spans = Vec::new();
+	let mut current = Span::default();
+	let mut i = 0;
+
+	let flush = |current: &mut Span, spans: &mut Vec<Span>| {
+		if !current.text.is_empty() {
+			spans.push(Span {
+				text: std::mem::take(&mut current.text),
+				..current.clone()
+			});
+		}
+	};
+
+	while i < text.len() {
+		let rest = &text[i..];
+		let previous = text[..i].chars().last();
+		let c = rest.chars().next().expect("i is before the end of the text");
+
+		if c == '\\' && rest.len() > 1 {
+			let escaped = rest[1..].chars().next().expect("rest is longer than the backslash");
+			current.text.push(escaped);
+			i += 1 + escaped.len_utf8();
+			continue;
+		}
+
+		if c == '`' {
+			if let Some(end) = rest[1..].find('`') {
+				flush(&mut current, &mut spans);
+				spans.push(Span {
+					text: rest[1..end + 1].to_string(),
+					code: true,
+					..Default::default()
+				});
+				i += end + 2;
+				continue;
+			}
+		}
+
+		if c == '[' {
+			if let Some((label, url)) = rest[1..].split_once("](").and_then(|(label, after)| {
+				after.find(')').map(|end| (label, &after[..end]))
+			}) {
+				if !label.contains(']') {
+					flush(&mut current, &mut spans);
+					spans.push(Span {
+						text: label.to_string(),
+						link: Some(url.to_string()),
+						..current.clone()
+					});
+					i += label.len() + url.len() + 4;
+					continue;
+				}
+			}
+		}
+
+		if (rest.starts_with("https://") || rest.starts_with("http://"))
+			&& !previous.is_some_and(char::is_alphanumeric)
+		{
+			let url = rest.split(char::is_whitespace).next().unwrap_or(rest);
+			let url = url.trim_end_matches(['.', ',', ')', ';', ':', '!', '?']);
+
+			flush(&mut current, &mut spans);
+			spans.push(Span {
+				text: url.to_string(),
+				link: Some(url.to_string()),
+				..current.clone()
+			});
+			i += url.len();
+			continue;
+		}
+
+		let toggles = |delimiter: &str, active: bool| {
+			rest.starts_with(delimiter) && (active || rest[delimiter.len()..].contains(delimiter))
+		};
+
+		if toggles("**", current.bold) {
+			flush(&mut current, &mut spans);
+			current.bold = !current.bold;
+			i += 2;
+			continue;
+		}
+
+		if toggles("~~", current.strikethrough) {
+			flush(&mut current, &mut spans);
+			current.strikethrough = !current.strikethrough;
+			i += 2;
+			continue;
+		}
+
+		// `_` only marks italics at the edges of words, so that snake_case names stay as they are
+		let next = rest[c.len_utf8()..].chars().next();
+		let at_edge = c == '*'
+			|| if current.italic {
+				!next.is_some_and(char::is_alphanumeric)
+			} else {
+				!previous.is_some_and(char::is_alphanumeric)
+			};
+
+		if (c == '*' || c == '_') && at_edge && toggles(&c.to_string(), current.italic) {
+			flush(&mut current, &mut spans);
+			current.italic = !current.italic;
+			i += 1;
+			continue;
+		}
+
+		current.text.push(c);
+		i += c.len_utf8();
+	}
+
+	flush(&mut current, &mut spans);
+	spans
+}
+
+/// Shows a task description as markdown. Checklist items can be toggled, and made into subtasks
+/// from their context menu when `can_make_subtasks`.
+pub fn show(ui: &mut egui::Ui, text: &str, can_make_subtasks: bool) -> Option<ChecklistClick> {
+	let mut click = None;
+	let mut code_block: Vec<&str> = Vec::new();
+
+	for (i, line) in parse_lines(text).into_iter().enumerate() {
+		match line {
+			Line::Blank => ui.add_space(4.0),
+			Line::Text(text) => show_inline(ui, text, |text| text),
+			Line::Heading(1, text) => show_inline(ui, text, egui::RichText::heading),
+			Line::Heading(2, text) => show_inline(ui, text, |text| text.strong().size(17.0)),
+			Line::Heading(_, text) => show_inline(ui, text, egui::RichText::strong),
+			Line::Quote(text) => {
+				ui.horizontal(|ui| {
+					ui.label(egui::RichText::new("▏").weak());
+					show_inline(ui, text, |text| text.italics().weak());
+				});
+			}
+			Line::Rule => {
+				ui.separator();
+			}
+			Line::CodeFence if code_block.is_empty() => {}
+			Line::CodeFence => {
+				show_code_block(ui, &code_block);
+				code_block.clear();
+			}
+			Line::Code(code) => code_block.push(code),
+			Line::ListItem { indent, marker, text } => {
+				ui.horizontal(|ui| {
+					ui.add_space(16.0 * indent as f32);
+					ui.label(marker);
+					show_inline(ui, text, |text| text);
+				});
+			}
+			Line::ChecklistItem {
+				indent,
+				checked,
+				text,
+			} => {
+				ui.horizontal(|ui| {
+					ui.add_space(16.0 * indent as f32);
+
+					let mut shown_checked = checked;
+					let mut checkbox = ui.add(egui::Checkbox::without_text(&mut shown_checked));
+
+					if can_make_subtasks {
+						checkbox = checkbox.on_hover_text("Right-click to make it a subtask");
+						checkbox.context_menu(|ui| {
+							if ui.button("Make Subtask").clicked() {
+								click = Some(ChecklistClick::MakeSubtask(i));
+								ui.close_menu();
+							}
+						});
+					}
+
+					if checkbox.clicked() {
+						click = Some(ChecklistClick::Toggle(i));
+					}
+
+					if checked {
+						show_inline(ui, text, |text| text.strikethrough().weak());
+					} else {
+						show_inline(ui, text, |text| text);
+					}
+				});
+			}
+		}
+	}
+
+	// A code block that is never closed goes to the end of the text
+	if !code_block.is_empty() {
+		show_code_block(ui, &code_block);
+	}
+
+	click
+}
+
+fn show_code_block(ui: &mut egui::Ui, lines: &[&str]) {
+	egui::Frame::group(ui.style())
+		.fill(ui.visuals().code_bg_color)
+		.show(ui, |ui| {
+			ui.label(egui::RichText::new(lines.join("\n")).monospace());
+		});
+}
+
+fn show_inline(ui: &mut egui::Ui, text: &str, style: impl Fn(egui::RichText) -> egui::RichText) {
+	ui.horizontal_wrapped(|ui| {
+		ui.spacing_mut().item_spacing.x = 0.0;
+
+		for span in parse_inline(text) {
+			let mut rich_text = style(egui::RichText::new(span.text.as_str()));
+
+			if span.bold {
+				rich_text = rich_text.strong();
+			}
+
+			if span.italic {
+				rich_text = rich_text.italics();
+			}
+
+			if span.strikethrough {
+				rich_text = rich_text.strikethrough();
+			}
+
+			if span.code {
+				rich_text = rich_text.code();
+			}
+
+			match span.link {
+				Some(url) => ui.hyperlink_to(rich_text, url),
+				None => ui.label(rich_text),
+			};
+		}
+	});
+}
+
+/// The text with the checklist item on line `line` checked, or unchecked if it was.
+pub fn toggle_checklist_item(text: &str, line: usize) -> Option<String> {
+	let Line::ChecklistItem { checked, .. } = parse_lines(text).get(line)?.clone() else {
+		return None;
+	};
+
+	let mut lines: Vec<String> = text.split('\n').map(String::from).collect();
+	let item = &mut lines[line];
+	let start = item.find('[')?;
+	item.replace_range(start..start + 3, if checked { "[ ]" } else { "[x]" });
+
+	Some(lines.join("\n"))
+}
+
+/// The text without the checklist item on line `line`, with the text of the item and whether it
+/// was checked.
+pub fn take_checklist_item(text: &str, line: usize) -> Option<(String, String, bool)> {
+	let Line::ChecklistItem { checked, text: item, .. } = parse_lines(text).get(line)?.clone() else {
+		return None;
+	};
+
+	let item = item.trim().to_string();
+	let mut lines: Vec<&str> = text.split('\n').collect();
+	lines.remove(line);
+
+	Some((lines.join("\n"), item, checked))
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	#[test]
+	fn lines() {
+		let text = "# Title\n\n- [ ] first\n  - [x] nested\n- item\n2. second\n```\n- [ ] code\n```\n> quote\n---";
+
+		assert_eq!(
+			parse_lines(text),
+			vec![
+				Line::Heading(1, "Title"),
+				Line::Blank,
+				Line::ChecklistItem {
+					indent: 0,
+					checked: false,
+					text: "first",
+				},
+				Line::ChecklistItem {
+					indent: 1,
+					checked: true,
+					text: "nested",
+				},
+				Line::ListItem {
+					indent: 0,
+					marker: "•",
+					text: "item",
+				},
+				Line::ListItem {
+					indent: 0,
+					marker: "2.",
+					text: "second",
+				},
+				Line::CodeFence,
+				Line::Code("- [ ] code"),
+				Line::CodeFence,
+				Line::Quote("quote"),
+				Line::Rule,
+			]
+		);
+
+		assert_eq!(parse_line("#hashtag"), Line::Text("#hashtag"));
+		assert_eq!(parse_line("-[ ] not an item"), Line::Text("-[ ] not an item"));
+
+		let toggled = toggle_checklist_item(text, 2).unwrap();
+		assert!(toggled.contains("- [x] first\n  - [x] nested"));
+		assert_eq!(toggle_checklist_item(&toggled, 3).unwrap(), toggled.replace("- [x] nested", "- [ ] nested"));
+		assert_eq!(toggle_checklist_item(text, 7), None);
+		assert_eq!(toggle_checklist_item(text, 100), None);
+
+		let (rest, item, checked) = take_checklist_item(text, 3).unwrap();
+		assert_eq!((item.as_str(), checked), ("nested", true));
+		assert_eq!(rest, text.replace("  - [x] nested\n", ""));
+	}
+
+	#[test]
+	fn inline() {
+		let text = |span: &Span| span.text.clone();
+
+		let spans = parse_inline("a **bold** and *it* `co*de` ~~gone~~");
+		assert_eq!(
+			spans.iter().map(text).collect::<Vec<_>>(),
+			["a ", "bold", " and ", "it", " ", "co*de", " ", "gone"]
+		);
+		assert!(spans[1].bold && spans[3].italic && spans[5].code && spans[7].strikethrough);
+
+		let spans = parse_inline("see [docs](https://example.com) or https://example.org.");
+		assert_eq!(spans[1].link.as_deref(), Some("https://example.com"));
+		assert_eq!(spans[1].text, "docs");
+		assert_eq!(spans[3].link.as_deref(), Some("https://example.org"));
+		assert_eq!(spans[4].text, ".");
+
+		assert_eq!(parse_inline("snake_case_name and 2 * 3")[0].text, "snake_case_name and 2 * 3");
+		assert_eq!(parse_inline("\\*not italic\\*")[0].text, "*not italic*");
+		assert!(parse_inline("_word_")[0].italic);
+	}
+}
@@ -1,20 +1,50 @@
 use std::{path::Path, str::FromStr, time::Duration};
 
+use attachment::Attachment;
+use chrono::{NaiveDateTime, Timelike};
 use ui::TaskWidget;
 use uuid::Uuid;
 
 use crate::{
 	data_dir::DataDirError,
 	handle_toast_error,
+	scripts::hooks::{self, HookEvent},
 	session::Session,
+	settings::{Settings, DEFAULT_DATE_FORMAT},
 	tag::{Tag, TagValue},
+	utils::ChronoDelayFormatExt,
 };
 
+pub mod attachment;
 pub mod display_list;
 pub mod list;
+pub mod markdown;
 pub mod scheduled;
 mod ui;
 
+pub use ui::TaskAction;
+
+/// A timestamped entry of the notes of a task.
+#[derive(Debug, Clone, PartialEq, Eq, serde::Serialize, serde::Deserialize)]
+pub struct Note {
+	pub time: NaiveDateTime,
+	pub text: String,
+}
+
+impl Note {
+	/// The date of the note in the date format of the settings, and its time.
+	pub fn time_label(&self) -> String {
+		let date = self.time.date();
+
+		format!(
+			"{} {}",
+			date.format_or_err(Settings::get().date_format.as_str())
+				.unwrap_or(date.format(DEFAULT_DATE_FORMAT).to_string()),
+			self.time.format("%H:%M")
+		)
+	}
+}
+
 #[derive(Debug, Clone, serde::Serialize, serde::Deserialize)]
 #[serde(default)]
 pub struct Task<T = NormalTaskData> {
@@ -24,6 +54,9 @@ pub struct Task<T = NormalTaskData> {
 	pub name: String,
 	pub description: String,
 	pub tags: Vec<Tag>,
+	pub attachments: Vec<Attachment>,
+	/// Log of progress on the task, oldest first. Notes are only ever appended
+	pub notes: Vec<Note>,
 
 	pub type_data: T,
 
@@ -41,6 +74,8 @@ impl<T: TaskTypeData> PartialEq for Task<T> {
 			&& self.description == other.description
 			&& self.type_data == other.type_data
 			&& self.tags == other.tags
+			&& self.attachments == other.attachments
+			&& self.notes == other.notes
 	}
 }
 
@@ -52,6 +87,8 @@ impl<T: TaskTypeData> Default for Task<T> {
 			name: String::from("Unnamed"),
 			description: String::new(),
 			tags: Vec::new(),
+			attachments: Vec::new(),
+			notes: Vec::new(),
 
 			type_data: T::default(),
 
@@ -121,6 +158,11 @@ impl<T: TaskTypeData> Task<T> {
 		let name = self.uuid.to_string();
 		let res = std::fs::remove_file(Self::get_data_dir(&name, path)?.join(name))?;
 
+		handle_toast_error!(
+			"Could not remove attachments: {}",
+			attachment::remove_task_files(&self.uuid)
+		);
+
 		if self.is_done() {
 			handle_toast_error!(
 				"Could not count as done: {}",
@@ -161,11 +203,36 @@ impl<T: TaskTypeData> Task<T> {
 				.error(format!("Could not save task: {}", e))
 				.set_closable(true)
 				.set_duration(Some(Duration::from_millis(10_000)));
+		} else if path == TaskPath::Tasks {
+			let event = if hooks::take_unsaved(&self.uuid) {
+				HookEvent::TaskCreated
+			} else {
+				HookEvent::TaskSaved
+			};
+
+			hooks::trigger(event, Some(self.hook_snapshot()));
 		}
 
 		self.state = TaskState::Display;
 	}
 
+	/// Ticks or unticks the checklist item on `line` of the description, then saves the task like
+	/// [`Self::display`] does. Returns whether there was an item to toggle.
+	pub fn toggle_checklist_item(&mut self, line: usize, path: TaskPath) -> Result<bool, TaskError> {
+		let Some(description) = markdown::toggle_checklist_item(&self.description, line) else {
+			return Ok(false);
+		};
+
+		self.description = description;
+		self.save(path)?;
+
+		if path == TaskPath::Tasks {
+			hooks::trigger(HookEvent::TaskSaved, Some(self.hook_snapshot()));
+		}
+
+		Ok(true)
+	}
+
 	pub fn edit(&mut self) {
 		self.state = TaskState::Edit {
 			pending_delete: false,
@@ -180,6 +247,14 @@ impl<T: TaskTypeData> Task<T> {
 		}
 	}
 
+	/// Enters edit mode with the delete confirmation already requested.
+	pub fn request_delete(&mut self) {
+		self.state = TaskState::Edit {
+			pending_delete: true,
+			no_buttons: false,
+		};
+	}
+
 	pub fn is_pending_delete(&self) -> bool {
 		match self.state {
 			TaskState::Edit { pending_delete, .. } => pending_delete,
@@ -198,15 +273,80 @@ impl<T: TaskTypeData> Task<T> {
 			name: self.name,
 			description: self.description,
 			tags: self.tags,
+			attachments: self.attachments,
+			notes: self.notes,
 			state: self.state,
 			marked_for_delete: self.marked_for_delete,
 		}
 	}
 
+	pub fn mark_done(&mut self, path: TaskPath) -> Result<(), TaskError> {
+		self.tags.insert(0, Tag::new(String::from("done"), None));
+		self.save(path)?;
+
+		if path == TaskPath::Tasks {
+			hooks::trigger(HookEvent::TaskDone, Some(self.hook_snapshot()));
+		}
+
+		Ok(())
+	}
+
+	/// A copy of this task as a normal task, to pass to hooked scripts.
+	pub(crate) fn hook_snapshot(&self) -> Task {
+		Task {
+			uuid: self.uuid,
+			name: self.name.clone(),
+			description: self.description.clone(),
+			tags: self.tags.clone(),
+			attachments: self.attachments.clone(),
+			notes: self.notes.clone(),
+			..Task::default()
+		}
+	}
+
 	pub fn is_done(&self) -> bool {
 		self.tags.iter().any(|tag| tag.name == "done")
 	}
 
+	/// Adds a note at the current time, to the second, to the log of the task.
+	pub fn add_note(&mut self, text: &str) {
+		let now = chrono::Local::now().naive_local();
+
+		self.notes.push(Note {
+			time: now.with_nanosecond(0).unwrap_or(now),
+			text: text.trim().to_string(),
+		});
+	}
+
+	/// Adds the notes of `notes` that follow the current ones, as the log can only grow. Returns
+	/// false, adding nothing, if `notes` doesn't start with the current notes.
+	pub fn append_notes(&mut self, notes: &[Note]) -> bool {
+		match notes.strip_prefix(self.notes.as_slice()) {
+			Some(new_notes) => {
+				self.notes.extend_from_slice(new_notes);
+				true
+			}
+			None => false,
+		}
+	}
+
+	/// Whether the name, description, a tag or a note of this task contains `search`, ignoring case.
+	pub fn matches_search(&self, search: &str) -> bool {
+		let search = search.trim().to_lowercase();
+
+		search.is_empty()
+			|| self.name.to_lowercase().contains(search.as_str())
+			|| self.description.to_lowercase().contains(search.as_str())
+			|| self
+				.tags
+				.iter()
+				.any(|tag| tag.to_string().to_lowercase().contains(search.as_str()))
+			|| self
+				.notes
+				.iter()
+				.any(|note| note.text.to_lowercase().contains(search.as_str()))
+	}
+
 	pub fn is_subtask_of(&self, other: &Uuid) -> bool {
 		self.tags
 			.iter()
@@ -216,6 +356,90 @@ impl<T: TaskTypeData> Task<T> {
 			})
 			.is_some()
 	}
+
+	/// The tasks this task is a subtask of.
+	pub fn parents(&self) -> impl Iterator<Item = Uuid> + '_ {
+		self.tags.iter().filter_map(|tag| match tag.value {
+			Some(TagValue::TaskReference(parent)) if tag.name.as_str() == "subtask_of" => {
+				Some(parent)
+			}
+			_ => None,
+		})
+	}
+
+	/// Makes this task a subtask of `parent` only, or a root task. Returns whether it changed.
+	pub fn set_parent(&mut self, parent: Option<Uuid>) -> bool {
+		if self.parents().eq(parent) {
+			return false;
+		}
+
+		self.tags.retain(|tag| tag.name.as_str() != "subtask_of");
+
+		if let Some(parent) = parent {
+			self.tags.push(Tag::new(
+				String::from("subtask_of"),
+				Some(TagValue::TaskReference(parent)),
+			));
+		}
+
+		true
+	}
+
+	/// Adds the tag unless the task already has it. Returns whether it was added.
+	pub fn add_tag(&mut self, tag: &Tag) -> bool {
+		if self.tags.contains(tag) {
+			return false;
+		}
+
+		self.tags.push(tag.clone());
+		true
+	}
+
+	/// Gives its value to the first tag with the same name, or adds the tag if there is none.
+	/// Returns whether the task changed.
+	pub fn set_tag(&mut self, tag: &Tag) -> bool {
+		match self.tags.iter_mut().find(|other| other.name == tag.name) {
+			Some(other) if other == tag => false,
+			Some(other) => {
+				other.value = tag.value.clone();
+				true
+			}
+			None => {
+				self.tags.push(tag.clone());
+				true
+			}
+		}
+	}
+
+	/// Removes every tag named `name`. Returns whether one was removed.
+	pub fn remove_tags(&mut self, name: &str) -> bool {
+		let count = self.tags.len();
+		self.tags.retain(|tag| tag.name != name);
+		self.tags.len() != count
+	}
+
+	/// Renames the tags named `from`, merging the tags that become identical. Returns whether a
+	/// tag was renamed.
+	pub fn rename_tag(&mut self, from: &str, to: &str) -> bool {
+		if !self.tags.iter().any(|tag| tag.name == from) {
+			return false;
+		}
+
+		let mut tags: Vec<Tag> = Vec::with_capacity(self.tags.len());
+
+		for mut tag in self.tags.drain(..) {
+			if tag.name == from {
+				tag.name = to.to_string();
+			}
+
+			if tag.name != to || !tags.contains(&tag) {
+				tags.push(tag);
+			}
+		}
+
+		self.tags = tags;
+		true
+	}
 }
 
 #[derive(Debug, Eq, PartialEq, Clone, Default)]
@@ -299,3 +523,53 @@ impl<T: Into<TaskErrorKind>> From<T> for TaskError {
 		}
 	}
 }
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	#[test]
+	fn notes() {
+		// Saved before tasks had notes and attachments
+		let mut task: Task = "(name: \"Order parts\", description: \"\", tags: [], type_data: ())"
+			.parse()
+			.unwrap();
+		assert!(task.notes.is_empty() && task.attachments.is_empty());
+
+		task.add_note(" called vendor, waiting for reply\n");
+		assert_eq!(task.notes[0].text, "called vendor, waiting for reply");
+		assert!(task.matches_search("VENDOR"));
+		assert!(!task.matches_search("invoice"));
+
+		let saved = ron::to_string(&task).unwrap();
+		assert_eq!(saved.parse::<Task>().unwrap().notes, task.notes);
+
+		let mut notes = task.notes.clone();
+		notes.push(Note { time: notes[0].time, text: String::from("vendor called back") });
+		assert!(task.append_notes(&notes));
+		assert_eq!(task.notes, notes);
+
+		notes[0].text = String::from("never called");
+		assert!(!task.append_notes(&notes));
+		assert!(!task.append_notes(&notes[..1]));
+		assert_eq!(task.notes[0].text, "called vendor, waiting for reply");
+	}
+
+	#[test]
+	fn toggle_checklist_item() {
+		let _queue = hooks::TEST_QUEUE.lock().unwrap_or_else(std::sync::PoisonError::into_inner);
+
+		let mut task = Task::<NormalTaskData>::default();
+		task.name = String::from("Pack");
+		task.description = String::from("- [ ] tent\n- [x] stove");
+
+		assert!(!task.toggle_checklist_item(5, TaskPath::Tasks).unwrap());
+		assert!(hooks::queued_events(&task.uuid).is_empty());
+
+		assert!(task.toggle_checklist_item(0, TaskPath::Tasks).unwrap());
+		assert_eq!(task.description, "- [x] tent\n- [x] stove");
+		assert_eq!(hooks::queued_events(&task.uuid), vec![HookEvent::TaskSaved]);
+
+		task.delete(TaskPath::Tasks).unwrap();
+	}
+}
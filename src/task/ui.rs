@@ -3,6 +3,7 @@ use std::collections::HashMap;
 use uuid::Uuid;
 
 use crate::{
+	scripts::decorator::Decoration,
 	settings::{Settings, DEFAULT_DATE_FORMAT},
 	tag::Tag,
 	toast_error,
@@ -10,6 +11,8 @@ use crate::{
 };
 
 use super::{
+	attachment::{self, Attachment},
+	markdown::{self, ChecklistClick},
 	scheduled::{RepeatMode, ScheduledTask},
 	NormalTaskData, Task, TaskPath, TaskState, TaskTypeData,
 };
@@ -18,13 +21,32 @@ pub struct TaskWidgetResponse {
 	pub changed: bool,
 	pub selected: bool,
 	pub rect: egui::Rect,
+	/// Chosen in the menu of the task, to be done on the task list
+	pub action: Option<TaskAction>,
+}
+
+/// Actions of the menu of a task that change the task list.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum TaskAction {
+	Duplicate { with_subtasks: bool },
+	MakeRecurring,
+	Snooze,
+	/// Turns the checklist item on this line of the description into a subtask
+	MakeChecklistItemSubtask { line: usize },
 }
 
 pub struct TaskWidget<'task, T> {
 	task: &'task mut Task<T>,
+	decorations: &'task [(String, Decoration)],
 }
 
-impl TaskWidget<'_, NormalTaskData> {
+impl<'task> TaskWidget<'task, NormalTaskData> {
+	/// Shows these chips from decorator scripts next to the name of the task.
+	pub fn with_decorations(mut self, decorations: &'task [(String, Decoration)]) -> Self {
+		self.decorations = decorations;
+		self
+	}
+
 	pub fn show(
 		mut self,
 		ui: &mut egui::Ui,
@@ -47,28 +69,35 @@ impl TaskWidget<'_, NormalTaskData> {
 
 impl<'task, T: TaskTypeData> TaskWidget<'task, T> {
 	pub fn new(task: &'task mut Task<T>) -> Self {
-		Self { task }
+		Self {
+			task,
+			decorations: &[],
+		}
 	}
 }
 
 impl TaskWidget<'_, ScheduledTask> {
+	/// Subtasks of another scheduled task are spawned along with their parent,
+	/// so their own date and repeat mode are not shown.
 	pub fn show(
 		mut self,
 		ui: &mut egui::Ui,
 		task_names: &HashMap<Uuid, String>,
+		is_subtask: bool,
 		scroll_to: &mut Option<Uuid>,
 		selected_task: &mut Option<Uuid>,
 	) -> TaskWidgetResponse {
 		let uuid = self.task.get_uuid().clone();
 
 		ui.push_id(uuid, |ui| {
-			if !self.task.type_data.active {
+			if !self.task.type_data.active && !is_subtask {
 				ui.set_opacity(0.5);
 			}
 
 			ui.group(|ui| {
 				ui.vertical(|ui| {
 					match self.task.state {
+						_ if is_subtask => {}
 						TaskState::Display => {
 							ui.horizontal_top(|ui| {
 								ui.add(egui::Checkbox::without_text(
@@ -166,6 +195,7 @@ impl<T: TaskTypeData> TaskWidget<'_, T> {
 			changed: false,
 			selected: false,
 			rect: egui::Rect::ZERO,
+			action: None,
 		};
 
 		ui.push_id(*self.task.get_uuid(), |ui| {
@@ -200,11 +230,7 @@ impl<T: TaskTypeData> TaskWidget<'_, T> {
 
 										if ui.button(egui::RichText::new("✅").size(20.0)).clicked()
 										{
-											self.task
-												.tags
-												.insert(0, Tag::new(String::from("done"), None));
-
-											if let Err(e) = self.task.save(path) {
+											if let Err(e) = self.task.mark_done(path) {
 												toast_error!("Could not save task: {}", e);
 											}
 
@@ -239,11 +265,64 @@ impl<T: TaskTypeData> TaskWidget<'_, T> {
 								{
 									self.task.edit();
 								}
+
+								if path == TaskPath::Tasks {
+									ui.menu_button("⋯", |ui| {
+										for (text, action) in [
+											("Duplicate", TaskAction::Duplicate { with_subtasks: false }),
+											(
+												"Duplicate with subtasks",
+												TaskAction::Duplicate { with_subtasks: true },
+											),
+											("Make recurring…", TaskAction::MakeRecurring),
+											("Snooze until…", TaskAction::Snooze),
+										] {
+											if ui.button(text).clicked() {
+												response.action = Some(action);
+												ui.close_menu();
+											}
+										}
+									});
+								}
+
+								for (script_name, decoration) in self.decorations {
+									decoration.show(ui, script_name.as_str());
+								}
 							});
 
 							if !self.task.description.is_empty() {
 								ui.separator();
-								ui.label(self.task.description.as_str());
+
+								match markdown::show(ui, self.task.description.as_str(), path == TaskPath::Tasks) {
+									Some(ChecklistClick::Toggle(line)) => match self.task.toggle_checklist_item(line, path) {
+										Ok(changed) => response.changed |= changed,
+										Err(e) => {
+											toast_error!("Could not save task: {}", e);
+										}
+									},
+									Some(ChecklistClick::MakeSubtask(line)) => {
+										response.action = Some(TaskAction::MakeChecklistItemSubtask { line });
+									}
+									None => {}
+								}
+							}
+
+							if !self.task.attachments.is_empty() {
+								ui.separator();
+
+								ui.horizontal_wrapped(|ui| {
+									for attachment in self.task.attachments.iter() {
+										if ui
+											.button(attachment_label(attachment))
+											.on_hover_text(attachment.to_string())
+											.clicked()
+										{
+											if let Err(e) = attachment.open(ui.ctx()) {
+												toast_error!("Could not open attachment: {}", e);
+											}
+										}
+									}
+								});
 							}
 
 							if !self.task.tags.is_empty() {
@@ -323,6 +402,8 @@ impl<T: TaskTypeData> TaskWidget<'_, T> {
 
 							ui.text_edit_multiline(&mut self.task.description);
 
+							ui.separator();
+							self.edit_attachments(ui);
 							ui.separator();
 
 							ui.horizontal_wrapped(|ui| {
@@ -390,4 +471,80 @@ impl<T: TaskTypeData> TaskWidget<'_, T> {
 
 		response
 	}
+
+	fn edit_attachments(&mut self, ui: &mut egui::Ui) {
+		let mut to_remove = None;
+
+		for (i, attachment) in self.task.attachments.iter().enumerate() {
+			ui.horizontal(|ui| {
+				ui.label(attachment_label(attachment))
+					.on_hover_text(attachment.to_string());
+
+				if ui
+					.small_button(egui::RichText::from("❌").color(ui.style().visuals.error_fg_color))
+					.clicked()
+				{
+					to_remove = Some(i);
+				}
+			});
+		}
+
+		if let Some(i) = to_remove {
+			self.task.attachments.remove(i);
+		}
+
+		// The typed path or URL, and whether files are copied into the data directory
+		let new_attachment_id = ui.id().with("new_attachment");
+		let (mut text, mut copy): (String, bool) =
+			ui.data_mut(|data| data.get_temp(new_attachment_id).unwrap_or_default());
+
+		ui.horizontal(|ui| {
+			ui.add(
+				egui::TextEdit::singleline(&mut text)
+					.hint_text("File path or URL")
+					.desired_width(240.0),
+			);
+
+			let attachment = Attachment::from_text(text.as_str());
+
+			ui.add_enabled(
+				matches!(attachment, Attachment::File(_)),
+				egui::Checkbox::new(&mut copy, "Copy"),
+			)
+			.on_hover_text(
+				"Copy the file into the data folder, so that it stays even if the original moves. The copy is removed with the task.",
+			);
+
+			if ui
+				.add_enabled(!text.trim().is_empty(), egui::Button::new("Attach"))
+				.clicked()
+			{
+				let attachment = match attachment {
+					Attachment::File(path) if copy => {
+						attachment::copy_into_task_folder(&path, self.task.get_uuid())
+					}
+					attachment => Ok(attachment),
+				};
+
+				match attachment {
+					Ok(attachment) => {
+						self.task.attachments.push(attachment);
+						text.clear();
+					}
+					Err(e) => {
+						toast_error!("Could not attach file: {}", e);
+					}
+				}
+			}
+		});
+
+		ui.data_mut(|data| data.insert_temp(new_attachment_id, (text, copy)));
+	}
+}
+
+fn attachment_label(attachment: &Attachment) -> String {
+	match attachment {
+		Attachment::File(_) => format!("📎 {}", attachment.name()),
+		Attachment::Url(_) => format!("🔗 {}", attachment.name()),
+	}
 }
@@ -0,0 +1,438 @@
+use std::collections::HashMap;
+
+use uuid::Uuid;
+
+use crate::{
+	data_dir::DataDirError,
+	session::Session,
+	tag::TagValue,
+	task::Task,
+};
+
+/// Manual orders of the tasks of a view, one for the subtasks of each parent, under `None` for the
+/// tasks without a parent.
+pub type ManualOrders = HashMap<Option<Uuid>, Vec<Uuid>>;
+
+/// A named combination of filters, sortings, decorators, search text and layout, shown as a tab above the task list.
+#[derive(Debug, Clone, PartialEq, Eq, serde::Serialize, serde::Deserialize)]
+#[serde(default)]
+pub struct View {
+	pub name: String,
+	pub filters: Vec<String>,
+	pub sortings: Vec<String>,
+	pub decorators: Vec<String>,
+	pub search: String,
+	pub layout: ViewLayout,
+	/// Tasks in the order they were dragged to among their siblings. Tasks missing from the order of
+	/// their parent come after them, by uuid
+	pub manual_orders: ManualOrders,
+}
+
+impl Default for View {
+	fn default() -> Self {
+		Self {
+			name: String::from("All"),
+			filters: Vec::new(),
+			sortings: Vec::new(),
+			decorators: Vec::new(),
+			search: String::new(),
+			layout: ViewLayout::default(),
+			manual_orders: ManualOrders::new(),
+		}
+	}
+}
+
+impl View {
+	/// Moves `dragged` right before `target` in the manual order of their parent, or right after it
+	/// when `after`. Returns whether it moved, tasks under different parents are not reordered.
+	///
+	/// Every sibling in `tasks` gets a place in the order, and tasks that no longer exist are dropped
+	/// from the orders.
+	pub fn move_task<'a>(&mut self, tasks: impl Iterator<Item = &'a Task>, dragged: Uuid, target: Uuid, after: bool) -> bool {
+		let tasks: HashMap<Uuid, &Task> = tasks.map(|task| (*task.get_uuid(), task)).collect();
+
+		let parent = match (tasks.get(&dragged), tasks.get(&target)) {
+			(Some(dragged_task), Some(target_task)) if dragged != target && order_parent(dragged_task) == order_parent(target_task) => {
+				order_parent(dragged_task)
+			}
+			_ => return false,
+		};
+
+		let siblings = tasks.values().copied().filter(|task| order_parent(task) == parent);
+		let mut order: Vec<(Uuid, usize)> = manual_positions(&self.manual_orders, siblings)
+			.into_iter()
+			.filter(|(uuid, _)| *uuid != dragged)
+			.collect();
+		order.sort_by_key(|(_, position)| *position);
+
+		let mut order: Vec<Uuid> = order.into_iter().map(|(uuid, _)| uuid).collect();
+		let idx = order.iter().position(|uuid| *uuid == target).expect("target is a sibling");
+		order.insert(if after { idx + 1 } else { idx }, dragged);
+
+		self.manual_orders.insert(parent, order);
+		self.manual_orders.retain(|parent, _| parent.is_none_or(|parent| tasks.contains_key(&parent)));
+		true
+	}
+}
+
+#[derive(
+	Debug, Clone, PartialEq, Eq, Default, serde::Serialize, serde::Deserialize, kinded::Kinded,
+)]
+pub enum ViewLayout {
+	#[default]
+	List,
+	Tree,
+	Board {
+		column_tag: String,
+	},
+}
+
+pub struct ViewList {
+	views: Vec<View>,
+	current: usize,
+}
+
+impl ViewList {
+	pub fn load() -> Result<Self, ViewsError> {
+		let path = crate::data_dir()?.views();
+
+		let views: Vec<View> = if path.exists() {
+			ron::from_str(std::fs::read_to_string(path)?.as_str())?
+		} else {
+			Vec::new()
+		};
+
+		let session = Session::current();
+
+		let mut views = Self {
+			current: session
+				.current_view
+				.as_ref()
+				.and_then(|name| views.iter().position(|view| &view.name == name))
+				.unwrap_or(0),
+			views,
+		};
+
+		if views.views.is_empty() {
+			views.views.push(View {
+				filters: session.set_filters.clone(),
+				sortings: session.set_sortings.clone(),
+				decorators: session.set_decorators.clone(),
+				..Default::default()
+			});
+		}
+
+		Ok(views)
+	}
+
+	pub fn save(&self) -> Result<(), ViewsError> {
+		std::fs::write(crate::data_dir()?.views(), self.to_string())?;
+		Ok(())
+	}
+
+	pub fn iter(&self) -> impl Iterator<Item = &View> {
+		self.views.iter()
+	}
+
+	pub fn current_index(&self) -> usize {
+		self.current
+	}
+
+	pub fn current(&self) -> &View {
+		&self.views[self.current]
+	}
+
+	pub fn current_mut(&mut self) -> &mut View {
+		&mut self.views[self.current]
+	}
+
+	/// Makes `idx` the current view, and writes its filters, sortings and decorators into the session.
+	pub fn select(&mut self, idx: usize) -> Result<(), ViewsError> {
+		self.current = idx.min(self.views.len() - 1);
+
+		let view = self.current();
+
+		Session::mutate(|session| {
+			session.set_filters = view.filters.clone();
+			session.set_sortings = view.sortings.clone();
+			session.set_decorators = view.decorators.clone();
+			session.current_view = Some(view.name.clone());
+		})?;
+
+		Ok(())
+	}
+
+	/// Adds a copy of the current view under a new name, and selects it.
+	pub fn duplicate_current(&mut self) -> Result<(), ViewsError> {
+		let mut view = self.current().clone();
+		let mut i = self.views.len() + 1;
+
+		while self.views.iter().any(|v| v.name == format!("View {}", i)) {
+			i += 1;
+		}
+
+		view.name = format!("View {}", i);
+		self.views.push(view);
+		self.select(self.views.len() - 1)?;
+		self.save()
+	}
+
+	/// Renames the current view. Names are unique, as the current view is restored by name.
+	pub fn rename_current(&mut self, name: &str) -> Result<(), ViewsError> {
+		let name = name.trim();
+
+		if name.is_empty() {
+			return Err(ViewsError::EmptyName);
+		}
+
+		if self.views.iter().enumerate().any(|(idx, view)| idx != self.current && view.name == name) {
+			return Err(ViewsError::NameTaken(name.to_string()));
+		}
+
+		self.current_mut().name = name.to_string();
+		Session::mutate(|session| session.current_view = Some(name.to_string()))?;
+		self.save()
+	}
+
+	pub fn remove(&mut self, idx: usize) -> Result<(), ViewsError> {
+		if self.views.len() > 1 {
+			self.views.remove(idx);
+
+			// The current view moved back with the views after the removed one
+			if idx < self.current {
+				self.current -= 1;
+			}
+
+			self.select(self.current.min(self.views.len() - 1))?;
+		}
+
+		self.save()
+	}
+}
+
+impl std::fmt::Display for ViewList {
+	fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+		write!(
+			f,
+			"{}",
+			ron::ser::to_string_pretty(&self.views, ron::ser::PrettyConfig::default())
+				.expect("ron serialization of views should not fail")
+		)
+	}
+}
+
+/// The parent a task is ordered under, its first one.
+pub fn order_parent(task: &Task) -> Option<Uuid> {
+	task.parents().next()
+}
+
+/// The position of each task among its siblings in the manual orders, from 0. Tasks missing from
+/// the order of their parent come after the others, by uuid.
+pub fn manual_positions<'a>(manual_orders: &ManualOrders, tasks: impl Iterator<Item = &'a Task>) -> HashMap<Uuid, usize> {
+	let mut siblings: HashMap<Option<Uuid>, Vec<&Uuid>> = HashMap::new();
+
+	for task in tasks {
+		siblings.entry(order_parent(task)).or_default().push(task.get_uuid());
+	}
+
+	siblings
+		.into_iter()
+		.flat_map(|(parent, mut uuids)| {
+			// Reversed so that the first place of a task listed twice wins
+			let listed: HashMap<&Uuid, usize> = manual_orders
+				.get(&parent)
+				.map(|order| order.iter().enumerate().rev().map(|(position, uuid)| (uuid, position)).collect())
+				.unwrap_or_default();
+
+			uuids.sort_by_key(|uuid| (listed.get(uuid).copied().unwrap_or(usize::MAX), **uuid));
+			uuids.into_iter().enumerate().map(|(position, uuid)| (*uuid, position))
+		})
+		.collect()
+}
+
+/// Groups `tasks` into columns by the value of their `column_tag` tag. Tasks without it go in a last, unnamed column.
+pub fn board_columns(tasks: &[&Task], column_tag: &str) -> Vec<(String, Vec<Uuid>)> {
+	let mut columns: Vec<(String, Vec<Uuid>)> = Vec::new();
+	let mut untagged = Vec::new();
+
+	for task in tasks {
+		let column = task
+			.tags
+			.iter()
+			.find(|tag| tag.name == column_tag)
+			.map(|tag| match &tag.value {
+				Some(TagValue::Text(text)) => text.clone(),
+				Some(value) => value.to_string(),
+				None => tag.name.clone(),
+			});
+
+		match column {
+			Some(column) => match columns.iter_mut().find(|(name, _)| *name == column) {
+				Some((_, tasks)) => tasks.push(*task.get_uuid()),
+				None => columns.push((column, vec![*task.get_uuid()])),
+			},
+			None => untagged.push(*task.get_uuid()),
+		}
+	}
+
+	if !untagged.is_empty() {
+		columns.push((String::new(), untagged));
+	}
+
+	columns
+}
+
+#[derive(Debug, thiserror::Error)]
+pub enum ViewsError {
+	#[error("Could not access data directory: {0}")]
+	DataDirError(
+		#[from]
+		#[source]
+		&'static DataDirError,
+	),
+
+	#[error("IO error: {0}")]
+	IOError(
+		#[from]
+		#[source]
+		std::io::Error,
+	),
+
+	#[error("Parse error: {0}")]
+	ParseError(
+		#[from]
+		#[source]
+		ron::error::SpannedError,
+	),
+
+	#[error("A view is already named {0}")]
+	NameTaken(String),
+
+	#[error("The name of a view can't be empty")]
+	EmptyName,
+
+	#[error("Could not save session: {0}")]
+	Session(
+		#[from]
+		#[source]
+		crate::session::SessionError,
+	),
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use crate::tag::Tag;
+
+	fn task(name: &str, tags: Vec<Tag>) -> Task {
+		let mut task = Task::default();
+		task.name = name.to_string();
+		task.tags = tags;
+		task
+	}
+
+	fn subtask_of(parent: &Task) -> Tag {
+		Tag::new(
+			String::from("subtask_of"),
+			Some(TagValue::TaskReference(*parent.get_uuid())),
+		)
+	}
+
+	#[test]
+	fn rename_and_remove() {
+		let mut views = ViewList {
+			views: ["a", "b", "c"]
+				.into_iter()
+				.map(|name| View {
+					name: name.to_string(),
+					..Default::default()
+				})
+				.collect(),
+			current: 2,
+		};
+
+		assert!(matches!(views.rename_current("a"), Err(ViewsError::NameTaken(_))));
+		assert!(matches!(views.rename_current(" "), Err(ViewsError::EmptyName)));
+		views.rename_current(" d ").unwrap();
+		assert_eq!(views.current().name, "d");
+
+		views.remove(0).unwrap();
+		assert_eq!(views.current_index(), 1);
+		assert_eq!(views.current().name, "d");
+
+		views.remove(1).unwrap();
+		assert_eq!(views.current().name, "b");
+	}
+
+	#[test]
+	fn manual_order() {
+		let mut tasks: Vec<Task> = ["a", "b", "c", "d"].into_iter().map(|name| task(name, vec![])).collect();
+		let uuid = |tasks: &[Task], i: usize| *tasks[i].get_uuid();
+		let parent = uuid(&tasks, 0);
+		tasks.push(task("e", vec![subtask_of(&tasks[0])]));
+		tasks.push(task("f", vec![subtask_of(&tasks[0])]));
+		let uuid = |i: usize| uuid(&tasks, i);
+
+		let mut by_uuid: Vec<Uuid> = (0..4).map(uuid).collect();
+		by_uuid.sort();
+
+		let manual_orders = ManualOrders::from([
+			(None, vec![uuid(2), Uuid::new_v4(), uuid(0)]),
+			(Some(parent), vec![uuid(5)]),
+		]);
+		let positions = manual_positions(&manual_orders, tasks.iter());
+		assert_eq!(positions[&uuid(2)], 0);
+		assert_eq!(positions[&uuid(0)], 1);
+		assert!(positions[&uuid(1)] > 1 && positions[&uuid(3)] > 1);
+		assert_eq!(positions[&uuid(5)], 0);
+		assert_eq!(positions[&uuid(4)], 1);
+		assert_eq!(positions.len(), 6);
+
+		let mut view = View::default();
+		assert!(view.move_task(tasks.iter(), by_uuid[3], by_uuid[0], false));
+		assert_eq!(view.manual_orders[&None], [by_uuid[3], by_uuid[0], by_uuid[1], by_uuid[2]]);
+
+		assert!(view.move_task(tasks.iter(), by_uuid[3], by_uuid[1], true));
+		assert_eq!(view.manual_orders[&None], [by_uuid[0], by_uuid[1], by_uuid[3], by_uuid[2]]);
+
+		// Only siblings are reordered
+		assert!(!view.move_task(tasks.iter(), uuid(4), by_uuid[0], false));
+		assert!(!view.move_task(tasks.iter(), by_uuid[0], Uuid::new_v4(), true));
+		assert_eq!(view.manual_orders.len(), 1);
+
+		assert!(view.move_task(tasks.iter(), uuid(5), uuid(4), true));
+		assert_eq!(view.manual_orders[&Some(parent)], [uuid(4), uuid(5)]);
+		assert_eq!(view.manual_orders[&None], [by_uuid[0], by_uuid[1], by_uuid[3], by_uuid[2]]);
+
+		// The order of a deleted parent is dropped
+		let without_parent: Vec<&Task> = tasks.iter().filter(|task| *task.get_uuid() != parent).collect();
+		assert!(view.move_task(without_parent.into_iter(), uuid(1), uuid(2), false));
+		assert!(!view.manual_orders.contains_key(&Some(parent)));
+	}
+
+	#[test]
+	fn board() {
+		let status = |value: &str| {
+			Tag::new(
+				String::from("status"),
+				Some(TagValue::Text(value.to_string())),
+			)
+		};
+
+		let a = task("a", vec![status("doing")]);
+		let b = task("b", vec![]);
+		let c = task("c", vec![status("todo")]);
+		let d = task("d", vec![status("doing")]);
+
+		let columns = board_columns(&[&a, &b, &c, &d], "status");
+
+		assert_eq!(
+			columns,
+			vec![
+				(String::from("doing"), vec![*a.get_uuid(), *d.get_uuid()]),
+				(String::from("todo"), vec![*c.get_uuid()]),
+				(String::new(), vec![*b.get_uuid()]),
+			]
+		);
+	}
+}